@@ -1,51 +1,164 @@
+use crate::minify::MinifyOptions;
+use crate::resolver::NodeResolver;
+use crate::script_pool::{self, ScriptConfig, ScriptPool, ScriptRoutes};
+use crate::bundler::{BundleTarget, Bundler};
+use crate::compile_cache::CompileCache;
+use crate::define::Define;
+use crate::dev_server::{self, DevServer};
+use crate::diagnostics::ParseDiagnostic;
+use crate::module_format::ModuleFormat;
+use crate::source_maps::SourceMaps;
+use crate::target::TargetOptions;
+use crate::watcher::CompileError;
+use std::path::Path;
 use crate::{react_compiler, ssr, StringHttpRequest};
+use std::sync::Arc;
 use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer, Result};
 use actix_web::web::Data;
+use tokio::sync::broadcast::error::RecvError;
 
-/// Basic route handler for JavaScript processing
-pub async fn handle_js_request(req: HttpRequest) -> Result<HttpResponse> {
-    // Extract request information
-    let path = req.path();
-    let host = req
-        .headers()
-        .get("host")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("localhost");
-    let user_agent = req
-        .headers()
-        .get("user-agent")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("");
-    let referer = req
-        .headers()
-        .get("referer")
-        .and_then(|h| h.to_str().ok())
-        .unwrap_or("");
-
-    // Create StringHttpRequest
-    let _string_request = StringHttpRequest::new(path, host, user_agent, referer);
-
-    // For now, return a simple response indicating the request was processed
-    // In the future, this will process JavaScript and return rendered content
-    Ok(HttpResponse::Ok().content_type("text/html").body(format!(
-        "<html><body><h1>JavaScript Processing</h1><p>Path: {}</p><p>Host: {}</p></body></html>",
-        path, host
-    )))
+/// The request JavaScript sees, with every header, the query and the body
+pub fn string_request(req: &HttpRequest, body: &[u8]) -> StringHttpRequest {
+    // the host comes from the connection info, which honours forwarding headers
+    let mut request = StringHttpRequest::new(req.path(), req.connection_info().host(), "", "")
+        .with_method(req.method().as_str())
+        .with_query(req.query_string())
+        .with_protocol(req.connection_info().scheme())
+        .with_body(body);
+    // every value of a repeated header, in order
+    for (name, value) in req.headers() {
+        if let Ok(value) = value.to_str()
+            && name != "host"
+        {
+            request = request.with_header(name.as_str(), value);
+        }
+    }
+    match req.connection_info().realip_remote_addr() {
+        Some(remote_addr) => request.with_remote_addr(remote_addr),
+        None => request,
+    }
+}
+
+/// Runs the `Process` function of the script routed to the request path
+///
+/// Scripts come from the [`ScriptRoutes`] passed to [`create_app`], and what
+/// they write to `output` becomes the response. Paths no script serves are a
+/// 404, and script errors a 500. Errors are logged, and only debug builds send
+/// them to the client, as they name script paths and carry JavaScript messages.
+pub async fn handle_js_request(
+    req: HttpRequest,
+    body: web::Bytes,
+    scripts: Data<ScriptRoutes>,
+) -> Result<HttpResponse> {
+    let Some(pool) = scripts.pool(req.path()) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    Ok(match pool.process(string_request(&req, &body)).await {
+        Ok(response) => response.into(),
+        Err(e) => {
+            eprintln!("{} {}: {e}", req.method(), req.path());
+            let body = if cfg!(debug_assertions) {
+                e.to_string()
+            } else {
+                "Internal Server Error".to_string()
+            };
+            HttpResponse::InternalServerError()
+                .content_type("text/plain")
+                .body(body)
+        }
+    })
+}
+
+/// Script served by the `/js` routes when `JS_PROCESSOR_ROUTES` is not set
+const EXAMPLE_SCRIPT: &str = "./src/examples/process.js";
+
+/// The scripts of the `/js` routes, loaded from the [`ScriptRoutes::load`] file
+/// `JS_PROCESSOR_ROUTES` names, or else the example script for every path
+pub fn script_routes() -> anyhow::Result<ScriptRoutes> {
+    match std::env::var_os("JS_PROCESSOR_ROUTES") {
+        Some(path) => ScriptRoutes::load(Path::new(&path)),
+        None => {
+            let pool = ScriptPool::start(&ScriptConfig::new(EXAMPLE_SCRIPT))?;
+            Ok(ScriptRoutes::new().route("/", pool))
+        }
+    }
+}
+
+/// Component served by the React routes
+const REACT_ENTRY: &str = "./src/examples/react-component.tsx";
+
+lazy_static::lazy_static! {
+    /// Compiled output shared by every request, also kept on disk when
+    /// `JS_PROCESSOR_CACHE_DIR` is set so restarts and CI builds reuse it
+    static ref COMPILE_CACHE: Arc<CompileCache> = {
+        let cache = CompileCache::new(512);
+        Arc::new(match std::env::var_os("JS_PROCESSOR_CACHE_DIR") {
+            Some(dir) => cache.persistent(dir),
+            None => cache,
+        })
+    };
+}
+
+/// The environment both builds are compiled for, which takes `process.env`
+/// branches out of code the SSR runtime has no `process` for
+fn build_define() -> Define {
+    Define::env(if cfg!(debug_assertions) {
+        "development"
+    } else {
+        "production"
+    })
+}
+
+/// The compiler of the server build, shared by the `/react` route and the watcher
+fn ssr_compiler(resolver: Arc<NodeResolver>) -> react_compiler::ReactCompiler {
+    // components written for the automatic runtime don't import React
+    let react_compiler = react_compiler::ReactCompiler::new()
+        .with_resolver(resolver)
+        .with_jsx(react_compiler::JsxOptions::automatic())
+        .with_define(build_define())
+        .with_cache(COMPILE_CACHE.clone());
+    // release builds serve production-sized code
+    if cfg!(debug_assertions) {
+        react_compiler
+    } else {
+        react_compiler.with_minify(MinifyOptions::new())
+    }
+}
+
+fn project_resolver() -> Arc<NodeResolver> {
+    Arc::new(NodeResolver::for_project(".").unwrap_or_default())
 }
 
 /// Route handler for React component rendering
-pub async fn handle_react_render() -> Result<HttpResponse> {
-    let react_compiler = react_compiler::ReactCompiler::new();
+///
+/// Under [`create_dev_app`] the page gets the live reload script, and compile
+/// errors are shown in its overlay instead of a plain 500.
+pub async fn handle_react_render(req: HttpRequest) -> Result<HttpResponse> {
+    let resolver = project_resolver();
+    let dev = req.app_data::<Data<DevServer>>().is_some();
 
-    // Step 1: Compile the React component file (this is just JSX-to-JS via SWC)
-    let compiled_component = react_compiler
-        .compile_react_file("./src/examples/react-component.tsx")
-        .map_err(|e| {
-            actix_web::error::ErrorInternalServerError(format!(
+    // Step 1: Bundle the React component file and the files it imports
+    let compiled_component = match Bundler::new(resolver.clone())
+        .with_compiler(ssr_compiler(resolver))
+        .bundle(REACT_ENTRY)
+    {
+        Ok(bundle) => bundle.code,
+        Err(e) if dev => {
+            let path = e
+                .downcast_ref::<ParseDiagnostic>()
+                .map_or(REACT_ENTRY.into(), |diagnostic| diagnostic.file_name.clone().into());
+            return Ok(HttpResponse::InternalServerError()
+                .content_type("text/html")
+                .body(dev_server::error_page(&[CompileError::new(path, &e)])));
+        }
+        Err(e) => {
+            return Err(actix_web::error::ErrorInternalServerError(format!(
                 "Failed to compile React component file: {}",
                 e
-            ))
-        })?;
+            )));
+        }
+    };
 
     // Step 2: Create a proper CommonJS environment for SSR
     let wrapped_ssr_js = format!(r#"
@@ -59,6 +172,13 @@ pub async fn handle_react_render() -> Result<HttpResponse> {
                     Fragment: 'Fragment'
                 }};
             }}
+            if (moduleName === 'react/jsx-runtime' || moduleName === 'react/jsx-dev-runtime') {{
+                const jsx = function(type, props) {{
+                    const {{ children, ...rest }} = props || {{}};
+                    return {{ type, props: rest, children: children === undefined ? [] : [].concat(children) }};
+                }};
+                return {{ jsx, jsxs: jsx, jsxDEV: jsx, Fragment: 'Fragment' }};
+            }}
             if (moduleName === 'react-dom/server') {{
                 return {{
                     renderToString: function(element) {{
@@ -67,6 +187,9 @@ pub async fn handle_react_render() -> Result<HttpResponse> {
                         if (typeof element === 'number') return String(element);
                         
                         const {{ type, props, children }} = element;
+                        if (typeof type === 'function') {{
+                            return this.renderToString(type({{ ...props, children }}));
+                        }}
                         if (typeof type === 'string') {{
                             let html = `<${{type}}`;
                             if (props) {{
@@ -91,18 +214,14 @@ pub async fn handle_react_render() -> Result<HttpResponse> {
                     }}
                 }};
             }}
-            return {{}};
+            throw new Error("Cannot find module '" + moduleName + "'");
         }};
 
-        // Mock module and exports
-        globalThis.module = {{ exports: {{}} }};
-        globalThis.exports = globalThis.module.exports;
-
         {compiled}
 
         // Entry point function - make it global
         globalThis.entrypoint = function() {{
-            const Component = module.exports.default;
+            const Component = __require_entry().default;
             if (!Component) {{ throw new Error('No default export found'); }}
             const React = require('react');
             const ReactDOMServer = require('react-dom/server');
@@ -115,14 +234,12 @@ pub async fn handle_react_render() -> Result<HttpResponse> {
     println!("Wrapped SSR JS code:\n{}", wrapped_ssr_js);
 
     // Step 3: Initialize SSR runtime with this wrapped JS code
-    ssr_rs::Ssr::create_platform();
+    script_pool::init_v8();
 
     println!("Initializing SSR with wrapped JS code...");
 
     let mut ssr_instance = ssr_rs::Ssr::from(wrapped_ssr_js, "entrypoint")
-        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("SSR init error: {}", e)))
-        .expect("Failed to initialize SSR");
-        
+        .map_err(|e| actix_web::error::ErrorInternalServerError(format!("SSR init error: {}", e)))?;
 
     // Step 4: Render to string using SSR
     let rendered_html = ssr_instance
@@ -149,12 +266,78 @@ pub async fn handle_react_render() -> Result<HttpResponse> {
         rendered_html
     );
 
+    let full_html = if dev {
+        dev_server::inject_client(&full_html)
+    } else {
+        full_html
+    };
     Ok(HttpResponse::Ok().content_type("text/html").body(full_html))
 }
 
+/// Browsers served `/react/client.js`, which load it as a module script
+const MODERN_BROWSERS: &str = "defaults and supports es6-module";
 
-/// Create and configure the Actix-Web application
-pub fn create_app() -> App<
+/// Browsers served `/react/client.legacy.js` through a `nomodule` script
+const LEGACY_BROWSERS: &str = "> 0.25%, not dead";
+
+/// The bundler of the browser build for `browsers`, which is [hot](Bundler::hot)
+/// in development
+fn client_bundler(resolver: Arc<NodeResolver>, dev: bool, browsers: &str) -> Bundler {
+    let mut react_compiler = react_compiler::ReactCompiler::new()
+        .with_resolver(resolver.clone())
+        .with_define(build_define())
+        .with_target(TargetOptions::browsers(browsers))
+        .with_cache(COMPILE_CACHE.clone())
+        .with_refresh(dev);
+    if !cfg!(debug_assertions) {
+        react_compiler = react_compiler.with_minify(MinifyOptions::new());
+    }
+
+    Bundler::new(resolver)
+        .with_compiler(react_compiler)
+        .target(BundleTarget::Client {
+            global_name: Some("App".to_string()),
+        })
+        .external("react", "React")
+        .external("react-dom", "ReactDOM")
+        .hot(dev)
+}
+
+/// Route handler for the browser build of the React component
+///
+/// React and ReactDOM are expected as the `React` and `ReactDOM` globals, and
+/// the component's exports are assigned to `App`. Under [`create_dev_app`] its
+/// modules can be swapped by [`handle_hot_update`] while keeping component state.
+pub async fn handle_react_client(req: HttpRequest) -> Result<HttpResponse> {
+    client_build(&req, MODERN_BROWSERS)
+}
+
+/// Route handler for [`handle_react_client`] downleveled for older browsers
+pub async fn handle_react_client_legacy(req: HttpRequest) -> Result<HttpResponse> {
+    client_build(&req, LEGACY_BROWSERS)
+}
+
+fn client_build(req: &HttpRequest, browsers: &str) -> Result<HttpResponse> {
+    let dev = req.app_data::<Data<DevServer>>().is_some();
+    let bundle = client_bundler(project_resolver(), dev, browsers)
+        .bundle(REACT_ENTRY)
+        .map_err(|e| {
+            actix_web::error::ErrorInternalServerError(format!(
+                "Failed to bundle React component file: {}",
+                e
+            ))
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/javascript")
+        .body(bundle.code))
+}
+
+/// Create and configure the Actix-Web application, with `scripts` serving `/`
+/// and the `/js` routes
+pub fn create_app(
+    scripts: ScriptRoutes,
+) -> App<
     impl actix_web::dev::ServiceFactory<
         actix_web::dev::ServiceRequest,
         Config = (),
@@ -164,16 +347,126 @@ pub fn create_app() -> App<
     >,
 > {
     App::new()
-        .route("/", web::get().to(handle_js_request))
-        .route("/js/*", web::get().to(handle_js_request))
+        .app_data(Data::new(scripts))
+        .route("/", web::route().to(handle_js_request))
+        .route("/js/*", web::route().to(handle_js_request))
         .route("/react", web::get().to(handle_react_render))
+        .route("/react/client.js", web::get().to(handle_react_client))
+        .route("/react/client.legacy.js", web::get().to(handle_react_client_legacy))
+}
+
+/// [`create_app`] with live reload, for local development
+///
+/// Pages rendered by the React routes hot-swap or reload when a source watched
+/// by `dev` changes and show compile errors in an overlay, and project files are
+/// served compiled under `/__dev/src/`.
+pub fn create_dev_app(
+    dev: DevServer,
+    scripts: ScriptRoutes,
+) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    create_app(scripts)
+        .app_data(Data::new(dev))
+        .route(dev_server::SOCKET_PATH, web::get().to(dev_server::handle_socket))
+        .route(dev_server::CLIENT_PATH, web::get().to(dev_server::handle_client_script))
+        .route(dev_server::REFRESH_RUNTIME_PATH, web::get().to(dev_server::handle_refresh_runtime))
+        .route(&format!("{}{{path:.*}}", dev_server::HOT_PATH), web::get().to(handle_hot_update))
+        .route("/__dev/src/{path:.*}", web::get().to(handle_dev_asset))
+}
+
+/// Whether a path from a URL stays inside the directory it is joined to
+fn is_relative_path(path: &Path) -> bool {
+    path.components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+}
+
+/// Serves the [`Bundler::hot_update`] of a module of the browser build, named by
+/// its id relative to the working directory
+pub async fn handle_hot_update(path: web::Path<String>) -> Result<HttpResponse> {
+    let relative = Path::new(path.as_str());
+    if !is_relative_path(relative) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    Ok(match client_bundler(project_resolver(), true, MODERN_BROWSERS).hot_update(Path::new(".").join(relative)) {
+        Ok(code) => HttpResponse::Ok()
+            .content_type("application/javascript")
+            .body(code),
+        Err(e) => HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!("{e:#}")),
+    })
+}
+
+/// Serves a file of `./src` compiled to an ES module with an inline source map
+pub async fn handle_dev_asset(path: web::Path<String>) -> Result<HttpResponse> {
+    let relative = Path::new(path.as_str());
+    if !is_relative_path(relative) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+    let file = Path::new("./src").join(relative);
+    let Ok(code) = std::fs::read_to_string(&file) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let compiled = react_compiler::ReactCompiler::new()
+        .with_resolver(project_resolver())
+        .with_module_format(ModuleFormat::Esm)
+        .with_define(build_define())
+        .with_source_maps(SourceMaps::Inline)
+        .with_cache(COMPILE_CACHE.clone())
+        .compile(&code, Some(&file.to_string_lossy()));
+    Ok(match compiled {
+        Ok(output) => HttpResponse::Ok()
+            .content_type("application/javascript")
+            .body(output.code),
+        Err(e) => HttpResponse::InternalServerError()
+            .content_type("text/plain")
+            .body(format!("{e:#}")),
+    })
 }
 
 /// Start the Actix-Web server
+///
+/// Debug builds run [`create_dev_app`], watching `./src`.
 pub async fn start_server() -> std::io::Result<()> {
     println!("Starting server at http://localhost:8080");
+    let scripts = script_routes().map_err(|e| std::io::Error::other(format!("{e:#}")))?;
+
+    if !cfg!(debug_assertions) {
+        return HttpServer::new(move || create_app(scripts.clone()))
+            .bind("127.0.0.1:8080")?
+            .run()
+            .await;
+    }
+
+    // the watcher recompiles changed sources in the background, so requests
+    // find them in the compile cache
+    let dev = DevServer::start("./src", || ssr_compiler(project_resolver()));
+    let mut events = dev.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    println!("Recompiled {} module(s)", event.recompiled.len());
+                    for error in event.errors {
+                        eprintln!("{}: {}", error.path.display(), error.message);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
 
-    HttpServer::new(|| create_app())
+    HttpServer::new(move || create_dev_app(dev.clone(), scripts.clone()))
         .bind("127.0.0.1:8080")?
         .run()
         .await
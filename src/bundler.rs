@@ -0,0 +1,593 @@
+use crate::compile_cache::{CacheEntry, CompileCache, Dependency};
+use crate::js_parser::JavaScriptParser;
+use crate::module_graph::{ImportKind, collect_imports, normalize_path};
+use crate::module_loader::{is_package_file, is_typescript};
+use crate::react_compiler::ReactCompiler;
+use crate::resolver::{NodeResolver, ResolveKind, Resolved};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Global the server build assigns the entry's exported functions to, for
+/// `ssr_rs::Ssr::from(bundle.code, SSR_ENTRY_POINT)`
+pub const SSR_ENTRY_POINT: &str = "__ssr_entry";
+
+/// The runtime a [`Bundle`] is built for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum BundleTarget {
+    /// A classic script for `ssr_rs`
+    ///
+    /// It defines `__require_entry()`, returning the exports of the entry, and
+    /// [`SSR_ENTRY_POINT`], holding its exported functions. Imports that are not
+    /// bundled are loaded with `globalThis.require`.
+    #[default]
+    Server,
+    /// A self-executing function for a `<script>` tag, assigning the exports of
+    /// the entry to `global_name` when set
+    ///
+    /// Imports that are not bundled are read from the globals set with
+    /// [`Bundler::external`], or from a global of the same name.
+    Client { global_name: Option<String> },
+}
+
+/// One script holding an entry and every module it imports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    pub code: String,
+    /// The bundled files, entry first
+    pub modules: Vec<PathBuf>,
+}
+
+/// Links an entry and the files it imports into a [`Bundle`]
+///
+/// Project files, TypeScript and ES modules are compiled with the
+/// [`ReactCompiler`]; CommonJS files of packages and JSON are included as
+/// they are. Node built-ins, unresolvable specifiers and
+/// [externals](Self::external) are left to the runtime.
+pub struct Bundler {
+    resolver: Arc<NodeResolver>,
+    compiler: ReactCompiler,
+    target: BundleTarget,
+    externals: BTreeMap<String, String>,
+    root: PathBuf,
+    hot: bool,
+}
+
+impl Bundler {
+    pub fn new(resolver: Arc<NodeResolver>) -> Self {
+        Self {
+            compiler: ReactCompiler::new().with_resolver(resolver.clone()),
+            resolver,
+            target: BundleTarget::default(),
+            externals: BTreeMap::new(),
+            root: std::env::current_dir().unwrap_or_default(),
+            hot: false,
+        }
+    }
+
+    pub fn target(mut self, target: BundleTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Compiles modules with `compiler`, which must emit CommonJS
+    pub fn with_compiler(mut self, compiler: ReactCompiler) -> Self {
+        self.compiler = compiler;
+        self
+    }
+
+    /// Keeps `specifier` out of the bundle; client builds read it from `global`
+    pub fn external(mut self, specifier: &str, global: &str) -> Self {
+        self.externals
+            .insert(specifier.to_string(), global.to_string());
+        self
+    }
+
+    /// Lets client builds swap modules in place with React Fast Refresh
+    ///
+    /// Modules define `$RefreshReg$` and `$RefreshSig$` for a compiler with
+    /// [`ReactCompiler::with_refresh`], and the bundle sets up `globalThis.__hmr`,
+    /// which runs the scripts of [`Self::hot_update`]. Registration goes to
+    /// `globalThis.__RefreshRuntime`, the `react-refresh/runtime` package, which
+    /// must be loaded before React.
+    pub fn hot(mut self, hot: bool) -> Self {
+        self.hot = hot;
+        self
+    }
+
+    /// Directory module ids are relative to, which defaults to the current one
+    pub fn root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.root = normalize_path(root.as_ref());
+        self
+    }
+
+    /// Bundles `entry`
+    ///
+    /// When the compiler has a [`CompileCache`], a bundle is reused as long as
+    /// none of its modules changed, and otherwise only changed modules are
+    /// compiled again.
+    pub fn bundle<P: AsRef<Path>>(&self, entry: P) -> Result<Bundle> {
+        let entry = normalize_path(entry.as_ref());
+        let Some(cache) = self.compiler.cache() else {
+            return self.link(entry);
+        };
+
+        let key = CompileCache::key(&[
+            "bundle",
+            &self.compiler.fingerprint(),
+            &format!("{:?} {:?} {}", self.target, self.externals, self.hot),
+            &self.root.to_string_lossy(),
+            &entry.to_string_lossy(),
+        ]);
+        if let Some(cached) = cache.get(&key) {
+            return Ok(Bundle {
+                code: cached.code,
+                modules: cached
+                    .dependencies
+                    .into_iter()
+                    .map(|dependency| dependency.path)
+                    .collect(),
+            });
+        }
+
+        let bundle = self.link(entry)?;
+        cache.insert(
+            key,
+            CacheEntry {
+                code: bundle.code.clone(),
+                map: None,
+                dependencies: bundle.modules.iter().filter_map(Dependency::of).collect(),
+            },
+        );
+        Ok(bundle)
+    }
+
+    fn link(&self, entry: PathBuf) -> Result<Bundle> {
+        let modules = self.collect_modules(&entry)?;
+
+        let mut registry = String::from("{\n");
+        for (path, (code, dependencies)) in &modules {
+            let dependencies: BTreeMap<&String, String> = dependencies
+                .iter()
+                .map(|(specifier, target)| (specifier, self.module_id(target)))
+                .collect();
+            registry.push_str(&format!(
+                "{}: [{}, {}],\n",
+                serde_json::to_string(&self.module_id(path))?,
+                self.factory(code),
+                serde_json::to_string(&dependencies)?
+            ));
+        }
+        registry.push('}');
+
+        let entry_id = serde_json::to_string(&self.module_id(&entry))?;
+        let code = match &self.target {
+            BundleTarget::Server => format!(
+                "var __require_entry = {};\nvar {SSR_ENTRY_POINT} = {SERVER_EXPORTS};\n",
+                linker(&registry, &entry_id, "globalThis.require(name)", false),
+            ),
+            BundleTarget::Client { global_name } => {
+                let run = match global_name {
+                    Some(name) => format!(
+                        "globalThis[{}] = __require_entry();",
+                        serde_json::to_string(name)?
+                    ),
+                    None => "__require_entry();".to_string(),
+                };
+                format!(
+                    "(function () {{\nvar __globals = {};\nvar __require_entry = {};\n{run}\n}})();\n",
+                    serde_json::to_string(&self.externals)?,
+                    linker(
+                        &registry,
+                        &entry_id,
+                        "globalThis[__globals[name] || name]",
+                        self.hot,
+                    ),
+                )
+            }
+        };
+
+        let mut files: Vec<PathBuf> = modules.into_keys().filter(|path| *path != entry).collect();
+        files.insert(0, entry);
+        Ok(Bundle {
+            code,
+            modules: files,
+        })
+    }
+
+    /// A script replacing the module `path` of a running [`Self::hot`] bundle
+    ///
+    /// The page reloads instead when the module exports anything but components
+    /// or imports a file the bundle does not contain.
+    pub fn hot_update<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let path = normalize_path(path.as_ref());
+        let (code, dependencies) = self.load_module(&JavaScriptParser::new(), &path)?;
+        let dependencies: BTreeMap<&String, String> = dependencies
+            .iter()
+            .map(|(specifier, target)| (specifier, self.module_id(target)))
+            .collect();
+
+        Ok(format!(
+            "globalThis.__hmr ? globalThis.__hmr.update({}, {}, {}) : location.reload();\n",
+            serde_json::to_string(&self.module_id(&path))?,
+            self.factory(&code),
+            serde_json::to_string(&dependencies)?
+        ))
+    }
+
+    /// Every file reachable from `entry`, with its CommonJS code and the file
+    /// each bundled specifier resolved to
+    #[allow(clippy::type_complexity)]
+    fn collect_modules(
+        &self,
+        entry: &Path,
+    ) -> Result<BTreeMap<PathBuf, (String, BTreeMap<String, PathBuf>)>> {
+        let parser = JavaScriptParser::new();
+        let mut modules = BTreeMap::new();
+        let mut queue = vec![entry.to_path_buf()];
+
+        while let Some(path) = queue.pop() {
+            if modules.contains_key(&path) {
+                continue;
+            }
+            let (code, dependencies) = self.load_module(&parser, &path)?;
+            queue.extend(dependencies.values().cloned());
+            modules.insert(path, (code, dependencies));
+        }
+        Ok(modules)
+    }
+
+    /// The CommonJS code of one file and the file each bundled specifier resolved to
+    fn load_module(
+        &self,
+        parser: &JavaScriptParser,
+        path: &Path,
+    ) -> Result<(String, BTreeMap<String, PathBuf>)> {
+        let filename = path.to_string_lossy().into_owned();
+        let code =
+            std::fs::read_to_string(path).with_context(|| format!("failed to read {filename}"))?;
+        let code = if path.extension().is_some_and(|ext| ext == "json") {
+            format!("module.exports = {code};")
+        } else if is_package_file(path) && !is_typescript(path) && !self.resolver.is_esm(path) {
+            // packages pick their builds through `process.env.NODE_ENV`
+            if self.compiler.define().is_empty() {
+                code
+            } else {
+                self.compiler
+                    .define()
+                    .apply(&code, &filename)
+                    .with_context(|| format!("failed to compile {filename}"))?
+            }
+        } else {
+            self.compiler
+                .compile_react_component(&code, Some(&filename))
+                .with_context(|| format!("failed to compile {filename}"))?
+        };
+
+        let module = parser.parse_code(&code, Some("bundled.cjs"))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut dependencies = BTreeMap::new();
+        for import in collect_imports(parser.source_map(), &module) {
+            if import.kind != ImportKind::Require || self.externals.contains_key(&import.specifier)
+            {
+                continue;
+            }
+            if let Ok(Resolved::File(target)) =
+                self.resolver
+                    .resolve(&import.specifier, dir, ResolveKind::Require)
+            {
+                dependencies.insert(import.specifier, target);
+            }
+        }
+        Ok((code, dependencies))
+    }
+
+    /// The function a module's code runs in
+    fn factory(&self, code: &str) -> String {
+        let refresh = if self.hot {
+            // ids are unique per file, like the module ids of webpack
+            "var $RefreshReg$ = function (type, id) {\n    globalThis.__hmr.register(type, __filename + \" \" + id);\n};\nvar $RefreshSig$ = globalThis.__hmr.signature;\n"
+        } else {
+            ""
+        };
+        format!(
+            "function (exports, require, module, __filename, __dirname) {{\n{refresh}{code}\n}}"
+        )
+    }
+
+    fn module_id(&self, path: &Path) -> String {
+        module_id(&self.root, path)
+    }
+}
+
+/// `./`-prefixed path of a file under `root`, or its absolute path
+pub(crate) fn module_id(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(relative) => format!("./{}", relative.to_string_lossy().replace('\\', "/")),
+        Err(_) => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// A function expression loading `modules` on demand, which returns the
+/// exports of `entry_id` when called
+///
+/// `external` is evaluated with `name` bound to a specifier that was not bundled.
+/// With `hot`, it also defines [`HOT_RUNTIME`].
+fn linker(modules: &str, entry_id: &str, external: &str, hot: bool) -> String {
+    let hot = if hot { HOT_RUNTIME } else { "" };
+    format!(
+        r#"(function (modules, entry) {{
+    var cache = {{}};
+    function load(id) {{
+        if (!cache[id]) {{
+            var definition = modules[id];
+            var module = {{ exports: {{}} }};
+            cache[id] = module;
+            var require = function (name) {{
+                return definition[1][name] ? load(definition[1][name]) : {external};
+            }};
+            definition[0](module.exports, require, module, id, id.slice(0, id.lastIndexOf("/")));
+        }}
+        return cache[id].exports;
+    }}
+{hot}    return function () {{
+        return load(entry);
+    }};
+}})({modules}, {entry_id})"#
+    )
+}
+
+/// `globalThis.__hmr`, which re-runs changed modules and lets React Fast Refresh
+/// swap the components they export while keeping their state
+///
+/// Importers keep the exports they already have, which is fine for components
+/// since React renders the latest registration of each, so modules exporting
+/// anything else reload the page.
+const HOT_RUNTIME: &str = r#"    var refresh = globalThis.__RefreshRuntime;
+    var hmr = globalThis.__hmr = {
+        register: function (type, id) {
+            if (refresh) {
+                refresh.register(type, id);
+            }
+        },
+        signature: function () {
+            return refresh ? refresh.createSignatureFunctionForTransform() : function (type) {
+                return type;
+            };
+        },
+        accepts: function (id) {
+            return !!(refresh && modules[id]);
+        },
+        update: function (id, factory, dependencies) {
+            var swapped = hmr.accepts(id) && Object.keys(dependencies).every(function (name) {
+                return modules[dependencies[name]];
+            });
+            if (swapped) {
+                try {
+                    modules[id] = [factory, dependencies];
+                    delete cache[id];
+                    var exports = load(id);
+                    swapped = Object.keys(exports).every(function (name) {
+                        return refresh.isLikelyComponentType(exports[name]);
+                    });
+                } catch (error) {
+                    console.error(error);
+                    swapped = false;
+                }
+            }
+            if (swapped) {
+                refresh.performReactRefresh();
+            } else {
+                location.reload();
+            }
+        }
+    };
+"#;
+
+/// Copies the exported functions of the entry into a plain object, since
+/// `ssr_rs` calls every property of [`SSR_ENTRY_POINT`]
+const SERVER_EXPORTS: &str = r#"(function (exports) {
+    var entry = {};
+    Object.keys(exports).forEach(function (name) {
+        if (typeof exports[name] === "function") {
+            entry[name] = exports[name];
+        }
+    });
+    return entry;
+})(__require_entry())"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("js_processor_bundler_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        for (file, contents) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root.canonicalize().unwrap()
+    }
+
+    fn app(name: &str) -> PathBuf {
+        project(
+            name,
+            &[
+                (
+                    "src/app.tsx",
+                    "import { greet } from './greet';
+import pad from 'left-pad';
+import React from 'react';
+
+export function render(name: string): string {
+    return pad(greet(name), 2);
+}
+
+export const Title = () => <h1>{greet('title')}</h1>;
+export const version = 1;
+",
+                ),
+                (
+                    "src/greet.ts",
+                    "export const greet = (name: string) => `Hello, ${name}`;\n",
+                ),
+                (
+                    "node_modules/left-pad/package.json",
+                    r#"{ "name": "left-pad", "main": "index.js" }"#,
+                ),
+                (
+                    "node_modules/left-pad/index.js",
+                    "module.exports = function (text, n) { return ' '.repeat(n) + text; };\n",
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_bundle_follows_relative_and_package_imports() {
+        let root = app("server");
+        let bundle = Bundler::new(Arc::new(NodeResolver::new()))
+            .root(&root)
+            .bundle(root.join("src/app.tsx"))
+            .unwrap();
+
+        assert_eq!(
+            bundle.modules,
+            vec![
+                root.join("src/app.tsx"),
+                root.join("node_modules/left-pad/index.js"),
+                root.join("src/greet.ts"),
+            ]
+        );
+        assert!(bundle.code.contains(r#""./src/app.tsx": [function"#));
+        assert!(bundle.code.contains(r#""./src/greet.ts": [function"#));
+        assert!(
+            bundle
+                .code
+                .contains(r#""left-pad":"./node_modules/left-pad/index.js""#)
+        );
+        // `react` is not installed, so it is left to the runtime
+        assert!(!bundle.code.contains(r#""react":"#));
+        assert!(bundle.code.contains("globalThis.require(name)"));
+        assert!(bundle.code.contains("var __ssr_entry = "));
+        JavaScriptParser::new()
+            .parse_code(&bundle.code, Some("bundle.js"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_client_bundle_reads_externals_from_globals() {
+        let root = app("client");
+        let bundle = Bundler::new(Arc::new(NodeResolver::new()))
+            .root(&root)
+            .target(BundleTarget::Client {
+                global_name: Some("App".to_string()),
+            })
+            .external("left-pad", "leftPad")
+            .external("react", "React")
+            .bundle(root.join("src/app.tsx"))
+            .unwrap();
+
+        assert_eq!(bundle.modules.len(), 2);
+        assert!(bundle.code.starts_with("(function () {\n"));
+        assert!(
+            bundle
+                .code
+                .contains(r#"var __globals = {"left-pad":"leftPad","react":"React"};"#)
+        );
+        assert!(
+            bundle
+                .code
+                .contains(r#"globalThis["App"] = __require_entry();"#)
+        );
+        assert!(!bundle.code.contains("node_modules"));
+        assert!(!bundle.code.contains(SSR_ENTRY_POINT));
+        JavaScriptParser::new()
+            .parse_code(&bundle.code, Some("bundle.js"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_cached_bundle_is_rebuilt_when_a_module_changes() {
+        let root = app("cached");
+        let resolver = Arc::new(NodeResolver::new());
+        let cache = Arc::new(CompileCache::new(16));
+        let bundler = Bundler::new(resolver.clone())
+            .with_compiler(
+                ReactCompiler::new()
+                    .with_resolver(resolver)
+                    .with_cache(cache.clone()),
+            )
+            .root(&root);
+
+        let first = bundler.bundle(root.join("src/app.tsx")).unwrap();
+        let misses = cache.stats().misses;
+        assert_eq!(bundler.bundle(root.join("src/app.tsx")).unwrap(), first);
+        assert_eq!(cache.stats().misses, misses);
+
+        fs::write(
+            root.join("src/greet.ts"),
+            "export const greet = (name: string) => `Hi, ${name}`;\n",
+        )
+        .unwrap();
+        let second = bundler.bundle(root.join("src/app.tsx")).unwrap();
+        assert!(second.code.contains("Hi, "));
+        // the bundle, `greet.ts` and `app.tsx`, which imports it, are built again
+        assert_eq!(cache.stats().misses, misses + 3);
+    }
+
+    #[test]
+    fn test_hot_bundle_and_update() {
+        let root = project(
+            "hot",
+            &[
+                (
+                    "src/counter.tsx",
+                    "import { useState } from 'react';
+export default function Counter() {
+    const [count] = useState(0);
+    return <p>{count}</p>;
+}
+",
+                ),
+                (
+                    "src/app.tsx",
+                    "import Counter from './counter';\nexport const App = () => <Counter />;\n",
+                ),
+            ],
+        );
+        let resolver = Arc::new(NodeResolver::new());
+        let bundler = Bundler::new(resolver.clone())
+            .with_compiler(
+                ReactCompiler::new()
+                    .with_resolver(resolver)
+                    .with_refresh(true),
+            )
+            .target(BundleTarget::Client { global_name: None })
+            .external("react", "React")
+            .hot(true)
+            .root(&root);
+
+        let bundle = bundler.bundle(root.join("src/app.tsx")).unwrap();
+        assert!(bundle.code.contains("var hmr = globalThis.__hmr = {"));
+        assert!(
+            bundle
+                .code
+                .contains("var $RefreshReg$ = function (type, id) {")
+        );
+        assert!(bundle.code.contains("$RefreshReg$(_c, \"Counter\")"));
+
+        let update = bundler.hot_update(root.join("src/counter.tsx")).unwrap();
+        assert!(update.starts_with(
+            r#"globalThis.__hmr ? globalThis.__hmr.update("./src/counter.tsx", function"#
+        ));
+        assert!(update.ends_with(": location.reload();\n"));
+        JavaScriptParser::new()
+            .parse_code(&update, Some("update.js"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+use crate::diagnostics::Severity;
+use crate::formatter::{FormatOptions, Formatter, QuoteStyle, TrailingComma};
+use crate::js_parser::JavaScriptParser;
+use crate::lint::{LintConfig, Linter, RuleLevel};
+use anyhow::{Context, Result, anyhow, bail};
+use std::fs;
+use std::io::Read;
+
+const FMT_USAGE: &str = "usage: js_processor fmt [--check | --write] [--indent-width <n>] [--use-tabs] \
+[--single-quote] [--no-semi] [--trailing-comma none|es5|all] [--line-width <n>] [files...]";
+
+const LINT_USAGE: &str = "usage: js_processor lint [--config <.eslintrc.json>] \
+[--rule <name>=off|warn|error]... <files...>";
+
+/// What `fmt` does with the formatted output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FmtMode {
+    Print,
+    Check,
+    Write,
+}
+
+#[derive(Debug)]
+struct FmtArgs {
+    mode: FmtMode,
+    options: FormatOptions,
+    files: Vec<String>,
+}
+
+fn parse_fmt_args(args: &[String]) -> Result<FmtArgs> {
+    let mut mode = FmtMode::Print;
+    let mut options = FormatOptions::new();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("{flag} expects a value"))
+                .cloned()
+        };
+        match arg.as_str() {
+            "--check" => mode = FmtMode::Check,
+            "--write" => mode = FmtMode::Write,
+            "--use-tabs" => options = options.use_tabs(true),
+            "--single-quote" => options = options.quote_style(QuoteStyle::Single),
+            "--no-semi" => options = options.semicolons(false),
+            "--indent-width" => {
+                let width = value(arg)?;
+                options = options.indent_width(
+                    width
+                        .parse()
+                        .with_context(|| format!("invalid indent width: {width}"))?,
+                );
+            }
+            "--line-width" | "--print-width" => {
+                let width = value(arg)?;
+                options = options.line_width(
+                    width
+                        .parse()
+                        .with_context(|| format!("invalid line width: {width}"))?,
+                );
+            }
+            "--trailing-comma" => {
+                let trailing_comma = match value(arg)?.as_str() {
+                    "none" => TrailingComma::None,
+                    "es5" => TrailingComma::Es5,
+                    "all" => TrailingComma::All,
+                    other => bail!("invalid trailing comma style: {other}"),
+                };
+                options = options.trailing_comma(trailing_comma);
+            }
+            flag if flag.starts_with("--") => bail!("unknown option: {flag}"),
+            file => files.push(file.to_string()),
+        }
+    }
+
+    if mode != FmtMode::Print && files.is_empty() {
+        bail!("--check and --write need at least one file");
+    }
+
+    Ok(FmtArgs {
+        mode,
+        options,
+        files,
+    })
+}
+
+/// Runs `js_processor fmt` and returns the process exit code.
+///
+/// Without files the source is read from stdin and printed to stdout. `--check`
+/// lists the files that are not formatted and exits with 1, `--write` rewrites
+/// them in place. Invalid arguments and parse errors exit with 2.
+pub fn fmt(args: &[String]) -> i32 {
+    let args = match parse_fmt_args(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err:#}\n{FMT_USAGE}");
+            return 2;
+        }
+    };
+    let formatter = Formatter::new(args.options);
+
+    if args.files.is_empty() {
+        let mut code = String::new();
+        if let Err(err) = std::io::stdin().read_to_string(&mut code) {
+            eprintln!("error: failed to read stdin: {err}");
+            return 2;
+        }
+        return match formatter.format_code(&code, None) {
+            Ok(formatted) => {
+                print!("{formatted}");
+                0
+            }
+            Err(err) => {
+                eprintln!("error: {err:#}");
+                2
+            }
+        };
+    }
+
+    let mut unformatted = false;
+    let mut failed = false;
+    for file in &args.files {
+        match format_one(&formatter, file, args.mode) {
+            Ok(changed) => unformatted |= changed,
+            Err(err) => {
+                eprintln!("error: {file}: {err:#}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        2
+    } else if unformatted && args.mode == FmtMode::Check {
+        1
+    } else {
+        0
+    }
+}
+
+#[derive(Debug)]
+struct LintArgs {
+    config: LintConfig,
+    files: Vec<String>,
+}
+
+fn parse_lint_args(args: &[String]) -> Result<LintArgs> {
+    let mut config = LintConfig::new();
+    let mut rules = Vec::new();
+    let mut files = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .ok_or_else(|| anyhow!("{flag} expects a value"))
+                .cloned()
+        };
+        match arg.as_str() {
+            "--config" | "-c" => {
+                let path = value(arg)?;
+                let json = fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {path}"))?;
+                config = LintConfig::from_json(&json)
+                    .with_context(|| format!("invalid lint config {path}"))?;
+            }
+            "--rule" => {
+                let rule = value(arg)?;
+                let (name, level) = rule
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("--rule expects <name>=<level>, got {rule}"))?;
+                let json = match level.parse::<u64>() {
+                    Ok(number) => number.into(),
+                    Err(_) => level.into(),
+                };
+                let level = RuleLevel::from_json(&json)
+                    .ok_or_else(|| anyhow!("invalid level for rule '{name}': {level}"))?;
+                rules.push((name.to_string(), level));
+            }
+            flag if flag.starts_with("--") => bail!("unknown option: {flag}"),
+            file => files.push(file.to_string()),
+        }
+    }
+
+    if files.is_empty() {
+        bail!("lint needs at least one file");
+    }
+    // `--rule` overrides the config file wherever it appears
+    for (name, level) in rules {
+        config = config.rule(&name, level);
+    }
+
+    Ok(LintArgs { config, files })
+}
+
+/// Runs `js_processor lint` and returns the process exit code.
+///
+/// Problems are printed with their code frames. The exit code is 1 when any
+/// rule at the `error` level reported a problem, and 2 for invalid arguments,
+/// unknown rules and files that cannot be read or parsed; warnings alone exit with 0.
+pub fn lint(args: &[String]) -> i32 {
+    let args = match parse_lint_args(args) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err:#}\n{LINT_USAGE}");
+            return 2;
+        }
+    };
+    let linter = match Linter::new().config(args.config) {
+        Ok(linter) => linter,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            return 2;
+        }
+    };
+    let parser = JavaScriptParser::new();
+
+    let (mut errors, mut warnings) = (0, 0);
+    let mut failed = false;
+    for file in &args.files {
+        let diagnostics = match linter.lint_file(&parser, file) {
+            Ok(diagnostics) => diagnostics,
+            Err(err) => {
+                eprintln!("error: {file}: {err:#}");
+                failed = true;
+                continue;
+            }
+        };
+        for diagnostic in diagnostics {
+            let level = match diagnostic.severity {
+                Severity::Error => {
+                    errors += 1;
+                    "error"
+                }
+                Severity::Warning => {
+                    warnings += 1;
+                    "warning"
+                }
+            };
+            println!("{level}: {diagnostic}\n{}", diagnostic.code_frame);
+        }
+    }
+    if errors + warnings > 0 {
+        println!("{errors} error(s), {warnings} warning(s)");
+    }
+
+    if failed {
+        2
+    } else if errors > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Formats one file, returning whether its contents differ from the output
+fn format_one(formatter: &Formatter, file: &str, mode: FmtMode) -> Result<bool> {
+    let code = fs::read_to_string(file).with_context(|| format!("failed to read {file}"))?;
+    let formatted = formatter.format_code(&code, Some(file))?;
+    let changed = formatted != code;
+
+    match mode {
+        FmtMode::Print => print!("{formatted}"),
+        FmtMode::Check if changed => println!("{file}"),
+        FmtMode::Write if changed => {
+            fs::write(file, &formatted).with_context(|| format!("failed to write {file}"))?
+        }
+        _ => {}
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_fmt_args() {
+        let args = parse_fmt_args(&strings(&[
+            "--check",
+            "--single-quote",
+            "--no-semi",
+            "--indent-width",
+            "4",
+            "--trailing-comma",
+            "es5",
+            "src/app.tsx",
+        ]))
+        .unwrap();
+
+        assert_eq!(args.mode, FmtMode::Check);
+        assert_eq!(args.files, vec!["src/app.tsx"]);
+        assert_eq!(args.options.indent_width, 4);
+        assert_eq!(args.options.quote_style, QuoteStyle::Single);
+        assert!(!args.options.semicolons);
+        assert_eq!(args.options.trailing_comma, TrailingComma::Es5);
+
+        assert!(parse_fmt_args(&strings(&["--write"])).is_err());
+        assert!(parse_fmt_args(&strings(&["--line-width"])).is_err());
+        assert!(parse_fmt_args(&strings(&["--trailing-comma", "some", "a.js"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_lint_args() {
+        let args = parse_lint_args(&strings(&[
+            "--rule",
+            "eqeqeq=off",
+            "src/app.tsx",
+            "--rule",
+            "no-debugger=1",
+        ]))
+        .unwrap();
+
+        assert_eq!(args.files, vec!["src/app.tsx"]);
+        assert_eq!(args.config.rules["eqeqeq"], RuleLevel::Off);
+        assert_eq!(args.config.rules["no-debugger"], RuleLevel::Warn);
+
+        assert!(parse_lint_args(&strings(&[])).is_err());
+        assert!(parse_lint_args(&strings(&["--rule", "eqeqeq", "a.js"])).is_err());
+        assert!(parse_lint_args(&strings(&["--rule", "eqeqeq=loud", "a.js"])).is_err());
+        assert!(parse_lint_args(&strings(&["--config", "missing.json", "a.js"])).is_err());
+    }
+
+    #[test]
+    fn test_lint_exit_codes() {
+        let dir = std::env::temp_dir().join(format!("js_processor_lint_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("input.js");
+        fs::write(&file, "debugger;\n").unwrap();
+        let config = dir.join(".eslintrc.json");
+        fs::write(&config, r#"{"rules": {"no-debugger": "off"}}"#).unwrap();
+        let file = file.to_string_lossy().to_string();
+        let config = config.to_string_lossy().to_string();
+
+        assert_eq!(lint(&strings(&[&file])), 1);
+        assert_eq!(lint(&strings(&["--rule", "no-debugger=warn", &file])), 0);
+        assert_eq!(lint(&strings(&["--config", &config, &file])), 0);
+        assert_eq!(lint(&strings(&["--config", &config, "--rule", "no-debugger=2", &file])), 1);
+        assert_eq!(lint(&strings(&["--rule", "no-such-rule=warn", &file])), 2);
+        assert_eq!(lint(&strings(&[&dir.join("missing.js").to_string_lossy()])), 2);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fmt_check_and_write() {
+        let dir = std::env::temp_dir().join(format!("js_processor_fmt_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("input.js");
+        fs::write(&file, "const a = {b:1}\n").unwrap();
+        let file = file.to_string_lossy().to_string();
+
+        assert_eq!(fmt(&strings(&["--check", &file])), 1);
+        assert_eq!(fmt(&strings(&["--write", &file])), 0);
+        assert_eq!(fmt(&strings(&["--check", &file])), 0);
+        assert_eq!(fs::read_to_string(&file).unwrap(), "const a = { b: 1 };\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
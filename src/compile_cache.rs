@@ -0,0 +1,233 @@
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::fs;
+use std::hash::Hasher;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A file compiled output depends on, with the hash of its contents at the time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependency {
+    pub path: PathBuf,
+    pub hash: String,
+}
+
+impl Dependency {
+    /// Hashes the current contents of `path`, or returns `None` if it cannot be read
+    pub fn of<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let contents = fs::read(path).ok()?;
+        Some(Self {
+            path: path.to_path_buf(),
+            hash: content_hash(&[&contents]),
+        })
+    }
+
+    fn is_current(&self) -> bool {
+        fs::read(&self.path).is_ok_and(|contents| content_hash(&[&contents]) == self.hash)
+    }
+}
+
+/// Compiled code stored in a [`CompileCache`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub code: String,
+    pub map: Option<String>,
+    /// Files whose change makes the entry stale
+    pub dependencies: Vec<Dependency>,
+}
+
+/// Hits and misses of a [`CompileCache`] since it was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// Compiled output keyed by a hash of the source text, the compiler options and
+/// the crate version
+///
+/// Entries live in an in-memory LRU and, with [`Self::persistent`], as JSON files
+/// in a directory that outlives the process, such as a CI cache. An entry is
+/// dropped on lookup once any of its [dependencies](CacheEntry::dependencies)
+/// changed or disappeared. The cache is shared between threads, so one
+/// `Arc<CompileCache>` can serve every [`crate::react_compiler::ReactCompiler`].
+pub struct CompileCache {
+    memory: Mutex<LruCache<String, CacheEntry>>,
+    dir: Option<PathBuf>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl CompileCache {
+    /// An in-memory cache holding up to `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            memory: Mutex::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN),
+            )),
+            dir: None,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Also stores entries in `dir`, which is created when the first one is written
+    pub fn persistent<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// The key of compiling with the given inputs, which must include everything
+    /// that affects the output, such as the source, its filename and the options
+    pub fn key(parts: &[&str]) -> String {
+        let parts: Vec<&[u8]> = std::iter::once(env!("CARGO_PKG_VERSION"))
+            .chain(parts.iter().copied())
+            .map(str::as_bytes)
+            .collect();
+        content_hash(&parts)
+    }
+
+    pub fn get(&self, key: &str) -> Option<CacheEntry> {
+        let entry = self.memory.lock().unwrap().get(key).cloned();
+        let entry = entry.or_else(|| {
+            let entry: CacheEntry =
+                serde_json::from_slice(&fs::read(self.path(key)?).ok()?).ok()?;
+            self.memory
+                .lock()
+                .unwrap()
+                .put(key.to_string(), entry.clone());
+            Some(entry)
+        });
+
+        match entry {
+            Some(entry) if entry.dependencies.iter().all(Dependency::is_current) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry)
+            }
+            stale => {
+                if stale.is_some() {
+                    self.remove(key);
+                }
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Stores an entry; failing to write it to disk only costs a recompile later
+    pub fn insert(&self, key: String, entry: CacheEntry) {
+        if let Some(path) = self.path(&key)
+            && let Ok(json) = serde_json::to_vec(&entry)
+            && fs::create_dir_all(path.parent().unwrap_or(Path::new("."))).is_ok()
+        {
+            // a temporary file keeps concurrent readers from seeing half an entry
+            let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+            if fs::write(&temporary, json).is_ok() && fs::rename(&temporary, &path).is_err() {
+                let _ = fs::remove_file(&temporary);
+            }
+        }
+        self.memory.lock().unwrap().put(key, entry);
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.memory.lock().unwrap().pop(key);
+        if let Some(path) = self.path(key) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Empties the in-memory layer, leaving the files on disk
+    pub fn clear_memory(&self) {
+        self.memory.lock().unwrap().clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{key}.json")))
+    }
+}
+
+/// A hex SipHash-1-3 of `parts`, which unlike `DefaultHasher` is the same on
+/// every Rust release and so can name files on disk
+pub(crate) fn content_hash(parts: &[&[u8]]) -> String {
+    let mut hasher = SipHasher13::new();
+    for part in parts {
+        // the length keeps `["ab", "c"]` and `["a", "bc"]` apart
+        hasher.write_usize(part.len());
+        hasher.write(part);
+    }
+    format!("{:032x}", hasher.finish128().as_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(code: &str, dependencies: Vec<Dependency>) -> CacheEntry {
+        CacheEntry {
+            code: code.to_string(),
+            map: None,
+            dependencies,
+        }
+    }
+
+    #[test]
+    fn test_key_and_lru_eviction() {
+        assert_eq!(
+            CompileCache::key(&["a", "b"]),
+            CompileCache::key(&["a", "b"])
+        );
+        assert_ne!(
+            CompileCache::key(&["ab", "c"]),
+            CompileCache::key(&["a", "bc"])
+        );
+
+        let cache = CompileCache::new(2);
+        cache.insert("one".to_string(), entry("1", vec![]));
+        cache.insert("two".to_string(), entry("2", vec![]));
+        assert_eq!(cache.get("one").unwrap().code, "1");
+        cache.insert("three".to_string(), entry("3", vec![]));
+
+        // `two` was the least recently used
+        assert!(cache.get("two").is_none());
+        assert!(cache.get("one").is_some());
+        assert!(cache.get("three").is_some());
+        assert_eq!(cache.stats(), CacheStats { hits: 3, misses: 1 });
+    }
+
+    #[test]
+    fn test_persistent_cache_and_dependency_invalidation() {
+        let dir = std::env::temp_dir().join(format!("js_processor_cache_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dependency = dir.join("greet.ts");
+        fs::write(&dependency, "export const greet = 1;").unwrap();
+
+        let key = CompileCache::key(&["source"]);
+        let cache = CompileCache::new(8).persistent(dir.join("cache"));
+        cache.insert(
+            key.clone(),
+            entry("compiled", vec![Dependency::of(&dependency).unwrap()]),
+        );
+
+        // a new process only has the files on disk
+        let reopened = CompileCache::new(8).persistent(dir.join("cache"));
+        assert_eq!(reopened.get(&key).unwrap().code, "compiled");
+
+        fs::write(&dependency, "export const greet = 2;").unwrap();
+        assert!(reopened.get(&key).is_none());
+        assert!(!dir.join("cache").join(format!("{key}.json")).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
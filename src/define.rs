@@ -0,0 +1,260 @@
+use crate::js_parser::JavaScriptParser;
+use crate::react_compiler::snapshot_comments;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use swc_common::pass::Repeat;
+use swc_common::sync::Lrc;
+use swc_common::{
+    DUMMY_SP, FileName, GLOBALS, Globals, Mark, SourceMap, Span, Spanned, SyntaxContext,
+};
+use swc_ecma_ast::{
+    ComputedPropName, EsVersion, Expr, Ident, KeyValueProp, Lit, MemberProp, MetaPropKind, Pass,
+    Prop, PropName,
+};
+use swc_ecma_codegen::Emitter;
+use swc_ecma_codegen::text_writer::JsWriter;
+use swc_ecma_parser::{Syntax, parse_file_as_expr};
+use swc_ecma_transforms_base::fixer::fixer;
+use swc_ecma_transforms_optimization::simplify::{dead_branch_remover, expr_simplifier};
+use swc_ecma_visit::{VisitMut, VisitMutWith, visit_mut_pass};
+
+/// Global expressions replaced at compile time, for
+/// [`crate::react_compiler::ReactCompiler::with_define`]
+///
+/// Names are identifiers or dotted member paths such as `process.env.NODE_ENV`
+/// or `import.meta.env.MODE`, and values are JavaScript expressions, so strings
+/// keep their quotes. Conditions that become constant are folded and their dead
+/// branches removed, taking out code the SSR runtime could not run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Define {
+    entries: BTreeMap<String, String>,
+}
+
+impl Define {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The environment of a build in `mode`, usually `development` or `production`
+    ///
+    /// Sets `process.env.NODE_ENV`, `import.meta.env.MODE`, `import.meta.env.DEV`,
+    /// `import.meta.env.PROD` and `__DEV__`.
+    pub fn env(mode: &str) -> Self {
+        let production = mode == "production";
+        let mode = serde_json::to_string(mode).expect("strings are always serializable");
+        Self::new()
+            .set("process.env.NODE_ENV", &mode)
+            .set("import.meta.env.MODE", &mode)
+            .set("import.meta.env.DEV", &(!production).to_string())
+            .set("import.meta.env.PROD", &production.to_string())
+            .set("__DEV__", &(!production).to_string())
+    }
+
+    /// Replaces `name` with the expression `value`
+    pub fn set(mut self, name: &str, value: &str) -> Self {
+        self.entries.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Replaces the defined names and folds the code depending on them
+    ///
+    /// Fails if a name is not a dotted path or a value does not parse.
+    pub(crate) fn pass(&self, unresolved_mark: Mark) -> Result<impl Pass + use<>> {
+        let unresolved_ctxt = SyntaxContext::empty().apply_mark(unresolved_mark);
+        let mut replacements = BTreeMap::new();
+        for (name, value) in &self.entries {
+            if name.split('.').any(str::is_empty) {
+                anyhow::bail!("invalid define name `{name}`");
+            }
+            replacements.insert(name.clone(), parse_value(name, value, unresolved_ctxt)?);
+        }
+
+        Ok((
+            visit_mut_pass(Replacer {
+                replacements,
+                unresolved_ctxt,
+            }),
+            Repeat::new((
+                expr_simplifier(unresolved_mark, Default::default()),
+                dead_branch_remover(unresolved_mark),
+            )),
+        ))
+    }
+
+    /// Applies [`Self::pass`] to a file that is bundled without compiling, such
+    /// as the CommonJS build of a package
+    pub(crate) fn apply(&self, code: &str, filename: &str) -> Result<String> {
+        let parser = JavaScriptParser::new();
+        let program = parser.parse_program(code, Some(filename))?;
+
+        GLOBALS.set(&Globals::new(), || {
+            let unresolved_mark = Mark::new();
+            let program = program.apply((
+                swc_ecma_transforms_base::resolver(unresolved_mark, Mark::new(), false),
+                self.pass(unresolved_mark)?,
+                fixer(Some(parser.comments())),
+            ));
+
+            // the emitter consumes the comments it prints
+            let comments = snapshot_comments(parser.comments());
+            let mut buf = Vec::new();
+            {
+                let mut emitter = Emitter {
+                    cfg: swc_ecma_codegen::Config::default(),
+                    cm: parser.source_map().clone(),
+                    comments: Some(&comments),
+                    wr: JsWriter::new(parser.source_map().clone(), "\n", &mut buf, None),
+                };
+                emitter
+                    .emit_program(&program)
+                    .map_err(|e| anyhow::anyhow!("Emit error: {:?}", e))?;
+            }
+            Ok(String::from_utf8(buf)?)
+        })
+    }
+}
+
+/// Parses a define value, detached from its own source map and referring to globals
+fn parse_value(name: &str, value: &str, unresolved_ctxt: SyntaxContext) -> Result<Expr> {
+    let source_map: Lrc<SourceMap> = Default::default();
+    let source_file = source_map.new_source_file(
+        Lrc::new(FileName::Custom(name.to_string())),
+        value.trim().to_string(),
+    );
+    let mut expr = parse_file_as_expr(
+        &source_file,
+        Syntax::Es(Default::default()),
+        EsVersion::latest(),
+        None,
+        &mut vec![],
+    )
+    .map_err(|e| anyhow::anyhow!("invalid value for define `{name}`: {}", e.kind().msg()))?;
+    // the parser stops after the first expression
+    if expr.span_hi() != source_file.end_pos {
+        anyhow::bail!("invalid value for define `{name}`: expected a single expression");
+    }
+
+    expr.visit_mut_with(&mut Detach(unresolved_ctxt));
+    Ok(*expr)
+}
+
+struct Detach(SyntaxContext);
+
+impl VisitMut for Detach {
+    fn visit_mut_span(&mut self, span: &mut Span) {
+        *span = DUMMY_SP;
+    }
+
+    fn visit_mut_ident(&mut self, ident: &mut Ident) {
+        ident.span = DUMMY_SP;
+        ident.ctxt = self.0;
+    }
+}
+
+struct Replacer {
+    replacements: BTreeMap<String, Expr>,
+    unresolved_ctxt: SyntaxContext,
+}
+
+impl Replacer {
+    /// The dotted path of a global or a member of one, unless a local binding shadows it
+    fn path(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::Ident(ident) if ident.ctxt == self.unresolved_ctxt => Some(ident.sym.to_string()),
+            Expr::MetaProp(meta) if meta.kind == MetaPropKind::ImportMeta => {
+                Some("import.meta".to_string())
+            }
+            Expr::Member(member) => {
+                let prop = match &member.prop {
+                    MemberProp::Ident(ident) => &ident.sym,
+                    MemberProp::Computed(ComputedPropName { expr, .. }) => match &**expr {
+                        Expr::Lit(Lit::Str(string)) => &string.value,
+                        _ => return None,
+                    },
+                    MemberProp::PrivateName(_) => return None,
+                };
+                Some(format!("{}.{prop}", self.path(&member.obj)?))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl VisitMut for Replacer {
+    fn visit_mut_expr(&mut self, expr: &mut Expr) {
+        // the longest path matches first, as parents are visited before members
+        if let Some(value) = self
+            .path(expr)
+            .and_then(|path| self.replacements.get(&path))
+        {
+            *expr = value.clone();
+            return;
+        }
+        expr.visit_mut_children_with(self);
+    }
+
+    fn visit_mut_prop(&mut self, prop: &mut Prop) {
+        if let Prop::Shorthand(ident) = prop
+            && ident.ctxt == self.unresolved_ctxt
+            && let Some(value) = self.replacements.get(ident.sym.as_str())
+        {
+            *prop = Prop::KeyValue(KeyValueProp {
+                key: PropName::Ident(ident.clone().into()),
+                value: Box::new(value.clone()),
+            });
+            return;
+        }
+        prop.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_replaces_and_removes_dead_branches() {
+        let code = r#"
+            if (process.env.NODE_ENV === "production") {
+                module.exports = require("./cjs/lib.production.js");
+            } else {
+                module.exports = require("./cjs/lib.development.js");
+            }
+            function local(process) {
+                return process.env.NODE_ENV;
+            }
+            var flags = { __DEV__ };
+        "#;
+
+        let output = Define::env("production").apply(code, "index.js").unwrap();
+        assert!(output.contains(r#"module.exports = require("./cjs/lib.production.js");"#));
+        assert!(!output.contains("development"));
+        assert!(!output.contains("if ("));
+        // a parameter shadows the global
+        assert!(output.contains("return process.env.NODE_ENV;"));
+        assert!(output.contains("__DEV__: false"));
+    }
+
+    #[test]
+    fn test_invalid_define_fails() {
+        let error = Define::new()
+            .set("process.env.API", "not valid (")
+            .apply("process.env.API;", "index.js")
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("invalid value for define `process.env.API`")
+        );
+
+        let error = Define::new()
+            .set("process..env", "1")
+            .apply("1;", "index.js")
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid define name"));
+    }
+}
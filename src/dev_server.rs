@@ -0,0 +1,423 @@
+use crate::bundler::{BundleTarget, Bundler, module_id};
+use crate::define::Define;
+use crate::module_graph::normalize_path;
+use crate::react_compiler::ReactCompiler;
+use crate::resolver::{NodeResolver, ResolveKind, Resolved};
+use crate::watcher::{CompileError, WatchEvent, WatchHandle, Watcher};
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{Codec, Frame, Message};
+use actix_web::body::BodyStream;
+use actix_web::web::{BytesMut, Data, Payload};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+
+/// Path of the live reload WebSocket
+pub const SOCKET_PATH: &str = "/__dev/ws";
+/// Path of the script that connects to it
+pub const CLIENT_PATH: &str = "/__dev/client.js";
+/// Path of the React Fast Refresh runtime, which pages load before React
+pub const REFRESH_RUNTIME_PATH: &str = "/__dev/refresh-runtime.js";
+/// Prefix of the [`crate::bundler::Bundler::hot_update`] scripts, followed by a module id
+/// without its `./`
+pub const HOT_PATH: &str = "/__dev/hot/";
+
+/// What the dev server tells connected pages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum DevMessage {
+    /// Sources were removed, so the page should be reloaded
+    Reload,
+    /// Sources changed and compiled
+    ///
+    /// Pages running a [hot](crate::bundler::Bundler::hot) bundle swap the
+    /// modules with these ids, others reload.
+    Update { modules: Vec<String> },
+    /// Sources changed but some failed to compile
+    Error { errors: Vec<CompileError> },
+}
+
+impl DevMessage {
+    fn from_event(event: &WatchEvent, root: &Path) -> Self {
+        if !event.errors.is_empty() {
+            Self::Error {
+                errors: event.errors.clone(),
+            }
+        } else if event.removed.is_empty() {
+            Self::Update {
+                modules: event
+                    .changed
+                    .iter()
+                    .map(|path| module_id(root, path))
+                    .collect(),
+            }
+        } else {
+            Self::Reload
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("dev messages are always serializable")
+    }
+}
+
+/// Live reload for [`crate::actix_integration::create_dev_app`]
+///
+/// Watches a source directory and pushes a [`DevMessage`] to every page
+/// connected to [`SOCKET_PATH`] when something in it changes.
+#[derive(Clone)]
+pub struct DevServer {
+    watcher: Arc<WatchHandle>,
+    /// Directory module ids are relative to, like in [`crate::bundler::Bundler`]
+    root: PathBuf,
+}
+
+impl DevServer {
+    /// Starts watching `root`, compiling changes with the compiler `compiler` returns
+    pub fn start<P, F>(root: P, compiler: F) -> Self
+    where
+        P: AsRef<Path>,
+        F: FnOnce() -> ReactCompiler + Send + 'static,
+    {
+        Self::from_watcher(Watcher::new(root).spawn(compiler))
+    }
+
+    pub fn from_watcher(watcher: WatchHandle) -> Self {
+        Self {
+            watcher: Arc::new(watcher),
+            root: normalize_path(&std::env::current_dir().unwrap_or_default()),
+        }
+    }
+
+    /// Directory the module ids of [`DevMessage::Update`] are relative to, the
+    /// working directory by default like in [`crate::bundler::Bundler`]
+    pub fn root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.root = normalize_path(root.as_ref());
+        self
+    }
+
+    /// The messages sent for each change from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.watcher.subscribe()
+    }
+}
+
+/// Adds the live reload script to an HTML page, before `</body>` if it has one,
+/// and the Fast Refresh runtime at the start of its `<head>`
+pub fn inject_client(html: &str) -> String {
+    let runtime = format!(r#"<script src="{REFRESH_RUNTIME_PATH}"></script>"#);
+    let html = match html.find("<head>") {
+        Some(index) => format!("{}{runtime}{}", &html[..index + 6], &html[index + 6..]),
+        None => format!("{runtime}\n{html}"),
+    };
+
+    let client = format!(r#"<script src="{CLIENT_PATH}"></script>"#);
+    match html.rfind("</body>") {
+        Some(index) => format!("{}{client}\n{}", &html[..index], &html[index..]),
+        None => format!("{html}{client}\n"),
+    }
+}
+
+/// A page showing compile errors in the overlay, which reloads once they are fixed
+pub fn error_page(errors: &[CompileError]) -> String {
+    let errors = serde_json::to_string(&DevMessage::Error {
+        errors: errors.to_vec(),
+    })
+    .expect("dev messages are always serializable")
+    // keeps a `</script>` in an error message from closing the tag
+    .replace("</", "<\\/");
+
+    inject_client(&format!(
+        "<!DOCTYPE html>\n<html>\n<body>\n<script>window.__DEV_MESSAGE__ = {errors};</script>\n</body>\n</html>\n"
+    ))
+}
+
+/// Serves [`CLIENT_SCRIPT`]
+pub async fn handle_client_script() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("application/javascript")
+        .body(
+            CLIENT_SCRIPT
+                .replace("__SOCKET_PATH__", SOCKET_PATH)
+                .replace("__HOT_PATH__", HOT_PATH),
+        )
+}
+
+/// Serves the `react-refresh/runtime` package of the project as the
+/// `__RefreshRuntime` global, hooked into React
+///
+/// Without the package, hot bundles fall back to reloading the page.
+pub async fn handle_refresh_runtime() -> HttpResponse {
+    let resolver = Arc::new(NodeResolver::for_project(".").unwrap_or_default());
+    let runtime = match resolver.resolve("react-refresh/runtime", Path::new("."), ResolveKind::Require) {
+        Ok(Resolved::File(path)) => Bundler::new(resolver.clone())
+            .with_compiler(
+                ReactCompiler::new()
+                    .with_resolver(resolver)
+                    .with_define(Define::env("development")),
+            )
+            .target(BundleTarget::Client {
+                global_name: Some("__RefreshRuntime".to_string()),
+            })
+            .bundle(path),
+        _ => Err(anyhow::anyhow!("react-refresh is not installed")),
+    };
+
+    let script = match runtime {
+        Ok(bundle) => format!(
+            "{}__RefreshRuntime.injectIntoGlobalHook(window);\n",
+            bundle.code
+        ),
+        Err(err) => format!(
+            "console.warn({});\n",
+            serde_json::to_string(&format!("{err:#}, so changes reload the page"))
+                .expect("strings are always serializable")
+        ),
+    };
+    HttpResponse::Ok()
+        .content_type("application/javascript")
+        .body(script)
+}
+
+/// Upgrades to a WebSocket that receives a [`DevMessage`] per change
+pub async fn handle_socket(
+    req: HttpRequest,
+    mut payload: Payload,
+    dev: Data<DevServer>,
+) -> Result<HttpResponse> {
+    let mut response = actix_http::ws::handshake(req.head())?;
+    let (sender, outgoing) = mpsc::unbounded_channel::<Message>();
+
+    // answers pings and closes until the page goes away
+    actix_web::rt::spawn({
+        let sender = sender.clone();
+        async move {
+            let mut codec = Codec::new();
+            let mut buffer = BytesMut::new();
+            while let Some(Ok(chunk)) = payload.next().await {
+                buffer.extend_from_slice(&chunk);
+                while let Ok(Some(frame)) = codec.decode(&mut buffer) {
+                    let reply = match frame {
+                        Frame::Ping(bytes) => Message::Pong(bytes),
+                        Frame::Close(reason) => Message::Close(reason),
+                        _ => continue,
+                    };
+                    let closing = matches!(reply, Message::Close(_));
+                    if sender.send(reply).is_err() || closing {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    let mut events = dev.subscribe();
+    actix_web::rt::spawn(async move {
+        loop {
+            let message = match events.recv().await {
+                Ok(event) => DevMessage::from_event(&event, &dev.root),
+                Err(RecvError::Lagged(_)) => DevMessage::Reload,
+                Err(RecvError::Closed) => break,
+            };
+            if sender
+                .send(Message::Text(message.to_json().into()))
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    let frames = futures_util::stream::unfold(
+        (outgoing, Codec::new(), false),
+        |(mut outgoing, mut codec, closed)| async move {
+            if closed {
+                return None;
+            }
+            let message = outgoing.recv().await?;
+            let closing = matches!(message, Message::Close(_));
+            let mut buffer = BytesMut::new();
+            codec.encode(message, &mut buffer).ok()?;
+            Some((
+                Ok::<_, actix_web::Error>(buffer.freeze()),
+                (outgoing, codec, closing),
+            ))
+        },
+    );
+    let response = response.body(BodyStream::new(frames));
+    Ok(HttpResponse::from(response.map_into_boxed_body()))
+}
+
+/// Reloads the page on [`DevMessage::Reload`] and shows [`DevMessage::Error`]
+/// in an overlay, reconnecting after the server restarts
+const CLIENT_SCRIPT: &str = r#"(function () {
+    var overlay = null;
+
+    function showErrors(errors) {
+        if (!overlay) {
+            overlay = document.createElement("div");
+            overlay.id = "__dev_overlay";
+            overlay.style.cssText = "position:fixed;inset:0;z-index:2147483647;overflow:auto;" +
+                "padding:32px;background:rgba(24,24,27,0.95);color:#fafafa;" +
+                "font:14px/1.5 ui-monospace,Menlo,Consolas,monospace";
+            document.body.appendChild(overlay);
+        }
+        overlay.innerHTML = "";
+        errors.forEach(function (error) {
+            var title = document.createElement("div");
+            title.style.cssText = "color:#f87171;font-weight:bold;margin-bottom:8px";
+            title.textContent = error.message;
+            var file = document.createElement("div");
+            file.style.cssText = "color:#a1a1aa;margin-bottom:8px";
+            file.textContent = error.path;
+            overlay.appendChild(title);
+            overlay.appendChild(file);
+            if (error.codeFrame) {
+                var frame = document.createElement("pre");
+                frame.style.cssText = "margin:0 0 32px;padding:16px;background:#27272a;overflow:auto";
+                frame.textContent = error.codeFrame;
+                overlay.appendChild(frame);
+            }
+        });
+    }
+
+    // runs the hot update of each module, or reloads if the page cannot swap one
+    function update(modules) {
+        var hmr = window.__hmr;
+        if (!hmr || !modules.every(hmr.accepts)) {
+            location.reload();
+            return;
+        }
+        if (overlay) {
+            overlay.remove();
+            overlay = null;
+        }
+        modules.forEach(function (id) {
+            var script = document.createElement("script");
+            script.src = "__HOT_PATH__" + id.replace(/^\.\//, "") + "?t=" + Date.now();
+            script.onload = function () {
+                script.remove();
+            };
+            document.head.appendChild(script);
+        });
+    }
+
+    function handle(message) {
+        if (message.type === "reload") {
+            location.reload();
+        } else if (message.type === "update") {
+            update(message.modules);
+        } else if (message.type === "error") {
+            showErrors(message.errors);
+        }
+    }
+
+    function connect(reconnecting) {
+        var protocol = location.protocol === "https:" ? "wss:" : "ws:";
+        var socket = new WebSocket(protocol + "//" + location.host + "__SOCKET_PATH__");
+        socket.onopen = function () {
+            if (reconnecting) {
+                location.reload();
+            }
+        };
+        socket.onmessage = function (event) {
+            handle(JSON.parse(event.data));
+        };
+        socket.onclose = function () {
+            setTimeout(function () {
+                connect(true);
+            }, 1000);
+        };
+    }
+
+    if (window.__DEV_MESSAGE__) {
+        handle(window.__DEV_MESSAGE__);
+    }
+    connect(false);
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+    use actix_web::{App, test as actix_test, web};
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    #[test]
+    fn test_inject_client_and_error_page() {
+        assert_eq!(
+            inject_client("<html><head></head><body><p>hi</p></body></html>"),
+            "<html><head><script src=\"/__dev/refresh-runtime.js\"></script></head>\
+             <body><p>hi</p><script src=\"/__dev/client.js\"></script>\n</body></html>"
+        );
+
+        let page = error_page(&[CompileError {
+            path: PathBuf::from("src/app.tsx"),
+            message: "Expected '</', got ';'".to_string(),
+            code_frame: Some("> 1 | const a = <p>;\n".to_string()),
+        }]);
+        assert!(page.contains(
+            r#"window.__DEV_MESSAGE__ = {"type":"error","errors":[{"path":"src/app.tsx""#
+        ));
+        assert!(page.contains(r#""codeFrame":"> 1 | const a = <p>;\n""#));
+        assert!(page.contains("Expected '<\\/', got ';'"));
+        assert!(page.contains(CLIENT_PATH));
+    }
+
+    #[actix_web::test]
+    async fn test_socket_pushes_update_on_change() {
+        let root = std::env::temp_dir().join("js_processor_dev_server");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("app.tsx"), "export const a = 1;\n").unwrap();
+
+        let watcher = Watcher::new(&root)
+            .interval(Duration::from_millis(10))
+            .spawn(ReactCompiler::new);
+        let app = actix_test::init_service(
+            App::new()
+                .app_data(Data::new(DevServer::from_watcher(watcher).root(&root)))
+                .route(SOCKET_PATH, web::get().to(handle_socket)),
+        )
+        .await;
+        let request = actix_test::TestRequest::get()
+            .uri(SOCKET_PATH)
+            .insert_header(("upgrade", "websocket"))
+            .insert_header(("connection", "upgrade"))
+            .insert_header(("sec-websocket-version", "13"))
+            .insert_header(("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ=="))
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), 101);
+
+        fs::write(root.join("app.tsx"), "export const a = 12;\n").unwrap();
+        let mut body = Box::pin(response.into_body());
+        let bytes = std::future::poll_fn(|cx| body.as_mut().poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        let frame = Codec::new()
+            .client_mode()
+            .decode(&mut BytesMut::from(&bytes[..]))
+            .unwrap();
+        assert_eq!(
+            frame,
+            Some(Frame::Text(
+                DevMessage::Update {
+                    modules: vec!["./app.tsx".to_string()]
+                }
+                .to_json()
+                .into()
+            ))
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+use serde::Serialize;
+use swc_common::{SourceFile, SourceMap, Spanned};
+use swc_ecma_parser::error::{Error as SwcError, SyntaxError};
+
+/// Number of unchanged lines shown around the highlighted span in a code frame.
+const CONTEXT_LINES: usize = 2;
+
+/// How serious a parse diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A machine-readable parse error attached to an exact source location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{file_name}:{start_line}:{start_column}: {message}")]
+pub struct ParseDiagnostic {
+    pub severity: Severity,
+    /// The name of the SWC syntax error variant, e.g. `UnterminatedStrLit` or `TS1005`
+    /// for the TypeScript errors SWC names after their `tsc` code
+    pub code: String,
+    pub message: String,
+    pub file_name: String,
+    /// 1-based line of the first character of the span
+    pub start_line: usize,
+    /// 1-based column (in characters) of the first character of the span
+    pub start_column: usize,
+    /// 1-based line of the end of the span
+    pub end_line: usize,
+    /// 1-based column (in characters) just past the end of the span
+    pub end_column: usize,
+    /// Source excerpt with the span underlined
+    pub code_frame: String,
+}
+
+impl ParseDiagnostic {
+    /// Builds a diagnostic from an SWC parser error
+    pub fn from_swc_error(source_map: &SourceMap, error: &SwcError, severity: Severity) -> Self {
+        let span = error.span();
+        let mut kind = error.kind();
+        while let SyntaxError::WithLabel { inner, .. } = kind {
+            kind = inner.kind();
+        }
+
+        let lo = source_map.lookup_char_pos(span.lo);
+        let hi = source_map.lookup_char_pos(span.hi.max(span.lo));
+
+        Self {
+            severity,
+            code: error_code(kind),
+            message: kind.msg().into_owned(),
+            file_name: lo.file.name.to_string(),
+            start_line: lo.line,
+            start_column: lo.col.0 + 1,
+            end_line: hi.line,
+            end_column: hi.col.0 + 1,
+            code_frame: render_code_frame(&lo.file, lo.line, lo.col.0, hi.line, hi.col.0),
+        }
+    }
+
+    /// Serializes the diagnostic as a JSON object
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics are always serializable")
+    }
+}
+
+/// Serializes a list of diagnostics as a JSON array
+pub fn diagnostics_to_json(diagnostics: &[ParseDiagnostic]) -> String {
+    serde_json::to_string(diagnostics).expect("diagnostics are always serializable")
+}
+
+/// Derives the error code from the `SyntaxError` variant name
+fn error_code(kind: &SyntaxError) -> String {
+    let debug = format!("{kind:?}");
+    let end = debug
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(debug.len());
+    debug[..end].to_string()
+}
+
+/// Renders the lines around a span, marking the highlighted lines with `>` and
+/// underlining the covered characters with `^`.
+///
+/// Lines are 1-based and columns are 0-based character offsets.
+pub fn render_code_frame(
+    file: &SourceFile,
+    start_line: usize,
+    start_col: usize,
+    end_line: usize,
+    end_col: usize,
+) -> String {
+    let line_count = file.count_lines();
+    if line_count == 0 {
+        return String::new();
+    }
+
+    let first = start_line.saturating_sub(CONTEXT_LINES).max(1);
+    let last = (end_line + CONTEXT_LINES).min(line_count);
+    let gutter = last.to_string().len();
+
+    let mut frame = String::new();
+    for line_no in first..=last {
+        let text = file.get_line(line_no - 1).unwrap_or_default();
+        let text = text.trim_end_matches(['\r', '\n']);
+        let highlighted = (start_line..=end_line).contains(&line_no);
+        let marker = if highlighted { '>' } else { ' ' };
+
+        if text.is_empty() {
+            frame.push_str(&format!("{marker} {line_no:>gutter$} |\n"));
+        } else {
+            frame.push_str(&format!("{marker} {line_no:>gutter$} | {text}\n"));
+        }
+
+        if highlighted {
+            let from = if line_no == start_line { start_col } else { 0 };
+            let to = if line_no == end_line {
+                end_col
+            } else {
+                text.chars().count()
+            };
+            let width = to.saturating_sub(from).max(1);
+            // keep tabs so the carets line up with the source text
+            let padding: String = text
+                .chars()
+                .take(from)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            frame.push_str(&format!(
+                "  {:>gutter$} | {padding}{}\n",
+                "",
+                "^".repeat(width)
+            ));
+        }
+    }
+
+    frame
+}
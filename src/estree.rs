@@ -0,0 +1,2095 @@
+use serde_json::{json, Map, Value};
+use swc_common::sync::Lrc;
+use swc_common::{BytePos, SourceFile, SourceMap, Span, Spanned};
+use swc_ecma_ast::*;
+
+/// Converts a parsed module into an ESTree/Babel-compatible JSON AST
+///
+/// Every node carries `start`/`end`, `range` and `loc`. Offsets and columns
+/// are counted in UTF-16 code units and lines are 1-based, as in JavaScript
+/// tooling. TypeScript syntax is emitted using the typescript-estree node names.
+pub fn module_to_estree(source_map: &SourceMap, module: &Module) -> Value {
+    let converter = EstreeConverter::new(source_map, module.span);
+    let mut body = converter.directives(module.body.iter().map_while(|item| match item {
+        ModuleItem::Stmt(stmt) => Some(stmt),
+        ModuleItem::ModuleDecl(_) => None,
+    }));
+    body.extend(
+        module
+            .body
+            .iter()
+            .skip(body.len())
+            .map(|item| converter.module_item(item)),
+    );
+
+    converter.node(
+        "Program",
+        module.span,
+        json!({ "sourceType": "module", "body": body }),
+    )
+}
+
+/// Converts a parsed program into an ESTree/Babel-compatible JSON AST
+///
+/// Scripts get `"sourceType": "script"`; see [`module_to_estree`] for the format.
+pub fn program_to_estree(source_map: &SourceMap, program: &Program) -> Value {
+    match program {
+        Program::Module(module) => module_to_estree(source_map, module),
+        Program::Script(script) => {
+            let converter = EstreeConverter::new(source_map, script.span);
+            let mut body = converter.directives(script.body.iter());
+            body.extend(
+                script
+                    .body
+                    .iter()
+                    .skip(body.len())
+                    .map(|stmt| converter.stmt(stmt)),
+            );
+
+            converter.node(
+                "Program",
+                script.span,
+                json!({ "sourceType": "script", "body": body }),
+            )
+        }
+    }
+}
+
+/// Maps SWC byte positions of one source file to ESTree positions
+struct EstreeConverter {
+    file: Option<Lrc<SourceFile>>,
+    /// UTF-16 offset of every byte offset, empty when the source is ASCII
+    utf16_offsets: Vec<u32>,
+    /// Byte offsets at which each line starts
+    line_starts: Vec<usize>,
+}
+
+impl EstreeConverter {
+    fn new(source_map: &SourceMap, span: Span) -> Self {
+        let file = source_map.try_lookup_source_file(span.lo).ok();
+        let src = file.as_ref().map(|file| file.src.as_str()).unwrap_or("");
+
+        let mut utf16_offsets = vec![];
+        if !src.is_ascii() {
+            utf16_offsets.reserve(src.len() + 1);
+            let mut offset = 0u32;
+            for c in src.chars() {
+                utf16_offsets.extend(std::iter::repeat_n(offset, c.len_utf8()));
+                offset += c.len_utf16() as u32;
+            }
+            utf16_offsets.push(offset);
+        }
+
+        let mut line_starts = vec![0];
+        let bytes = src.as_bytes();
+        for (i, b) in bytes.iter().enumerate() {
+            match b {
+                b'\n' => line_starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => line_starts.push(i + 1),
+                _ => {}
+            }
+        }
+
+        Self {
+            file,
+            utf16_offsets,
+            line_starts,
+        }
+    }
+
+    /// Byte offset of `pos` within the file, if it belongs to it
+    fn byte_offset(&self, pos: BytePos) -> Option<usize> {
+        let file = self.file.as_ref()?;
+        if pos < file.start_pos || pos > file.end_pos {
+            return None;
+        }
+        Some((pos - file.start_pos).0 as usize)
+    }
+
+    fn utf16(&self, byte_offset: usize) -> usize {
+        if self.utf16_offsets.is_empty() {
+            byte_offset
+        } else {
+            self.utf16_offsets[byte_offset.min(self.utf16_offsets.len() - 1)] as usize
+        }
+    }
+
+    fn position(&self, byte_offset: usize) -> Value {
+        let line = self.line_starts.partition_point(|&start| start <= byte_offset);
+        let line_start = self.line_starts[line - 1];
+        json!({
+            "line": line,
+            "column": self.utf16(byte_offset) - self.utf16(line_start),
+        })
+    }
+
+    /// Builds a node with its type and location fields
+    fn node(&self, ty: &str, span: Span, fields: Value) -> Value {
+        let mut map = match fields {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        map.insert("type".into(), ty.into());
+        let mut value = Value::Object(map);
+        self.set_span(&mut value, span);
+        value
+    }
+
+    /// Sets the `start`, `end`, `range` and `loc` of a node to `span`
+    fn set_span(&self, value: &mut Value, span: Span) {
+        if let (Some(lo), Some(hi)) = (self.byte_offset(span.lo), self.byte_offset(span.hi)) {
+            let (start, end) = (self.utf16(lo), self.utf16(hi));
+            value["start"] = start.into();
+            value["end"] = end.into();
+            value["range"] = json!([start, end]);
+            value["loc"] = json!({ "start": self.position(lo), "end": self.position(hi) });
+        }
+    }
+
+    fn opt<T>(&self, value: Option<T>, f: impl FnOnce(T) -> Value) -> Value {
+        value.map(f).unwrap_or(Value::Null)
+    }
+
+    /// Converts the leading string-literal statements of a body into directives
+    fn directives<'a>(&self, stmts: impl Iterator<Item = &'a Stmt>) -> Vec<Value> {
+        stmts
+            .map_while(|stmt| match stmt {
+                Stmt::Expr(ExprStmt { expr, span }) => match &**expr {
+                    Expr::Lit(Lit::Str(s)) => {
+                        let raw = s.raw.as_deref().unwrap_or(&s.value);
+                        let directive = raw.get(1..raw.len().saturating_sub(1)).unwrap_or("");
+                        Some(self.node(
+                            "ExpressionStatement",
+                            *span,
+                            json!({ "expression": self.str_lit(s), "directive": directive }),
+                        ))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn block_body(&self, stmts: &[Stmt]) -> Vec<Value> {
+        let mut body = self.directives(stmts.iter());
+        body.extend(stmts.iter().skip(body.len()).map(|stmt| self.stmt(stmt)));
+        body
+    }
+
+    // Module declarations
+
+    fn module_item(&self, item: &ModuleItem) -> Value {
+        match item {
+            ModuleItem::Stmt(stmt) => self.stmt(stmt),
+            ModuleItem::ModuleDecl(decl) => self.module_decl(decl),
+        }
+    }
+
+    fn module_decl(&self, decl: &ModuleDecl) -> Value {
+        match decl {
+            ModuleDecl::Import(import) => self.node(
+                "ImportDeclaration",
+                import.span,
+                json!({
+                    "specifiers": import.specifiers.iter().map(|s| self.import_specifier(s)).collect::<Vec<_>>(),
+                    "source": self.str_lit(&import.src),
+                    "attributes": self.import_attributes(import.with.as_deref()),
+                    "importKind": if import.type_only { "type" } else { "value" },
+                }),
+            ),
+            ModuleDecl::ExportDecl(export) => self.node(
+                "ExportNamedDeclaration",
+                export.span,
+                json!({
+                    "declaration": self.decl(&export.decl),
+                    "specifiers": [],
+                    "source": null,
+                    "attributes": [],
+                    "exportKind": "value",
+                }),
+            ),
+            ModuleDecl::ExportNamed(export) => {
+                if let [ExportSpecifier::Namespace(ns)] = export.specifiers.as_slice() {
+                    return self.node(
+                        "ExportAllDeclaration",
+                        export.span,
+                        json!({
+                            "exported": self.module_export_name(&ns.name),
+                            "source": self.opt(export.src.as_deref(), |s| self.str_lit(s)),
+                            "attributes": self.import_attributes(export.with.as_deref()),
+                            "exportKind": if export.type_only { "type" } else { "value" },
+                        }),
+                    );
+                }
+                self.node(
+                    "ExportNamedDeclaration",
+                    export.span,
+                    json!({
+                        "declaration": null,
+                        "specifiers": export.specifiers.iter().map(|s| self.export_specifier(s)).collect::<Vec<_>>(),
+                        "source": self.opt(export.src.as_deref(), |s| self.str_lit(s)),
+                        "attributes": self.import_attributes(export.with.as_deref()),
+                        "exportKind": if export.type_only { "type" } else { "value" },
+                    }),
+                )
+            }
+            ModuleDecl::ExportDefaultDecl(export) => {
+                let declaration = match &export.decl {
+                    DefaultDecl::Class(class) => {
+                        self.class("ClassDeclaration", class.ident.as_ref(), &class.class)
+                    }
+                    DefaultDecl::Fn(func) => {
+                        self.function("FunctionDeclaration", func.ident.as_ref(), &func.function)
+                    }
+                    DefaultDecl::TsInterfaceDecl(interface) => self.ts_interface(interface),
+                };
+                self.node(
+                    "ExportDefaultDeclaration",
+                    export.span,
+                    json!({ "declaration": declaration, "exportKind": "value" }),
+                )
+            }
+            ModuleDecl::ExportDefaultExpr(export) => self.node(
+                "ExportDefaultDeclaration",
+                export.span,
+                json!({ "declaration": self.expr(&export.expr), "exportKind": "value" }),
+            ),
+            ModuleDecl::ExportAll(export) => self.node(
+                "ExportAllDeclaration",
+                export.span,
+                json!({
+                    "exported": null,
+                    "source": self.str_lit(&export.src),
+                    "attributes": self.import_attributes(export.with.as_deref()),
+                    "exportKind": if export.type_only { "type" } else { "value" },
+                }),
+            ),
+            ModuleDecl::TsImportEquals(import) => {
+                let module_reference = match &import.module_ref {
+                    TsModuleRef::TsEntityName(name) => self.ts_entity_name(name),
+                    TsModuleRef::TsExternalModuleRef(external) => self.node(
+                        "TSExternalModuleReference",
+                        external.span,
+                        json!({ "expression": self.str_lit(&external.expr) }),
+                    ),
+                };
+                self.node(
+                    "TSImportEqualsDeclaration",
+                    import.span,
+                    json!({
+                        "id": self.ident(&import.id),
+                        "moduleReference": module_reference,
+                        "importKind": if import.is_type_only { "type" } else { "value" },
+                        "isExport": import.is_export,
+                    }),
+                )
+            }
+            ModuleDecl::TsExportAssignment(export) => self.node(
+                "TSExportAssignment",
+                export.span,
+                json!({ "expression": self.expr(&export.expr) }),
+            ),
+            ModuleDecl::TsNamespaceExport(export) => self.node(
+                "TSNamespaceExportDeclaration",
+                export.span,
+                json!({ "id": self.ident(&export.id) }),
+            ),
+        }
+    }
+
+    fn import_specifier(&self, specifier: &ImportSpecifier) -> Value {
+        match specifier {
+            ImportSpecifier::Named(named) => self.node(
+                "ImportSpecifier",
+                named.span,
+                json!({
+                    "imported": named
+                        .imported
+                        .as_ref()
+                        .map(|name| self.module_export_name(name))
+                        .unwrap_or_else(|| self.ident(&named.local)),
+                    "local": self.ident(&named.local),
+                    "importKind": if named.is_type_only { "type" } else { "value" },
+                }),
+            ),
+            ImportSpecifier::Default(default) => self.node(
+                "ImportDefaultSpecifier",
+                default.span,
+                json!({ "local": self.ident(&default.local) }),
+            ),
+            ImportSpecifier::Namespace(namespace) => self.node(
+                "ImportNamespaceSpecifier",
+                namespace.span,
+                json!({ "local": self.ident(&namespace.local) }),
+            ),
+        }
+    }
+
+    fn export_specifier(&self, specifier: &ExportSpecifier) -> Value {
+        match specifier {
+            ExportSpecifier::Named(named) => self.node(
+                "ExportSpecifier",
+                named.span,
+                json!({
+                    "local": self.module_export_name(&named.orig),
+                    "exported": self.module_export_name(named.exported.as_ref().unwrap_or(&named.orig)),
+                    "exportKind": if named.is_type_only { "type" } else { "value" },
+                }),
+            ),
+            ExportSpecifier::Default(default) => self.node(
+                "ExportDefaultSpecifier",
+                default.exported.span,
+                json!({ "exported": self.ident(&default.exported) }),
+            ),
+            ExportSpecifier::Namespace(namespace) => self.node(
+                "ExportNamespaceSpecifier",
+                namespace.span,
+                json!({ "exported": self.module_export_name(&namespace.name) }),
+            ),
+        }
+    }
+
+    fn module_export_name(&self, name: &ModuleExportName) -> Value {
+        match name {
+            ModuleExportName::Ident(ident) => self.ident(ident),
+            ModuleExportName::Str(s) => self.str_lit(s),
+        }
+    }
+
+    fn import_attributes(&self, with: Option<&ObjectLit>) -> Vec<Value> {
+        let Some(with) = with else {
+            return vec![];
+        };
+        with.props
+            .iter()
+            .filter_map(|prop| match prop {
+                PropOrSpread::Prop(prop) => match &**prop {
+                    Prop::KeyValue(kv) => Some(self.node(
+                        "ImportAttribute",
+                        Span::new(kv.key.span_lo(), kv.value.span_hi()),
+                        json!({ "key": self.prop_name(&kv.key), "value": self.expr(&kv.value) }),
+                    )),
+                    _ => None,
+                },
+                PropOrSpread::Spread(_) => None,
+            })
+            .collect()
+    }
+
+    // Statements
+
+    fn stmt(&self, stmt: &Stmt) -> Value {
+        match stmt {
+            Stmt::Block(block) => self.block(block),
+            Stmt::Empty(empty) => self.node("EmptyStatement", empty.span, json!({})),
+            Stmt::Debugger(debugger) => self.node("DebuggerStatement", debugger.span, json!({})),
+            Stmt::With(with) => self.node(
+                "WithStatement",
+                with.span,
+                json!({ "object": self.expr(&with.obj), "body": self.stmt(&with.body) }),
+            ),
+            Stmt::Return(ret) => self.node(
+                "ReturnStatement",
+                ret.span,
+                json!({ "argument": self.opt(ret.arg.as_deref(), |e| self.expr(e)) }),
+            ),
+            Stmt::Labeled(labeled) => self.node(
+                "LabeledStatement",
+                labeled.span,
+                json!({ "label": self.ident(&labeled.label), "body": self.stmt(&labeled.body) }),
+            ),
+            Stmt::Break(brk) => self.node(
+                "BreakStatement",
+                brk.span,
+                json!({ "label": self.opt(brk.label.as_ref(), |l| self.ident(l)) }),
+            ),
+            Stmt::Continue(cont) => self.node(
+                "ContinueStatement",
+                cont.span,
+                json!({ "label": self.opt(cont.label.as_ref(), |l| self.ident(l)) }),
+            ),
+            Stmt::If(stmt) => self.node(
+                "IfStatement",
+                stmt.span,
+                json!({
+                    "test": self.expr(&stmt.test),
+                    "consequent": self.stmt(&stmt.cons),
+                    "alternate": self.opt(stmt.alt.as_deref(), |s| self.stmt(s)),
+                }),
+            ),
+            Stmt::Switch(switch) => self.node(
+                "SwitchStatement",
+                switch.span,
+                json!({
+                    "discriminant": self.expr(&switch.discriminant),
+                    "cases": switch.cases.iter().map(|case| self.node(
+                        "SwitchCase",
+                        case.span,
+                        json!({
+                            "test": self.opt(case.test.as_deref(), |e| self.expr(e)),
+                            "consequent": case.cons.iter().map(|s| self.stmt(s)).collect::<Vec<_>>(),
+                        }),
+                    )).collect::<Vec<_>>(),
+                }),
+            ),
+            Stmt::Throw(throw) => self.node(
+                "ThrowStatement",
+                throw.span,
+                json!({ "argument": self.expr(&throw.arg) }),
+            ),
+            Stmt::Try(stmt) => self.node(
+                "TryStatement",
+                stmt.span,
+                json!({
+                    "block": self.block(&stmt.block),
+                    "handler": self.opt(stmt.handler.as_ref(), |handler| self.node(
+                        "CatchClause",
+                        handler.span,
+                        json!({
+                            "param": self.opt(handler.param.as_ref(), |p| self.pat(p)),
+                            "body": self.block(&handler.body),
+                        }),
+                    )),
+                    "finalizer": self.opt(stmt.finalizer.as_ref(), |b| self.block(b)),
+                }),
+            ),
+            Stmt::While(stmt) => self.node(
+                "WhileStatement",
+                stmt.span,
+                json!({ "test": self.expr(&stmt.test), "body": self.stmt(&stmt.body) }),
+            ),
+            Stmt::DoWhile(stmt) => self.node(
+                "DoWhileStatement",
+                stmt.span,
+                json!({ "body": self.stmt(&stmt.body), "test": self.expr(&stmt.test) }),
+            ),
+            Stmt::For(stmt) => self.node(
+                "ForStatement",
+                stmt.span,
+                json!({
+                    "init": self.opt(stmt.init.as_ref(), |init| match init {
+                        VarDeclOrExpr::VarDecl(decl) => self.var_decl(decl),
+                        VarDeclOrExpr::Expr(expr) => self.expr(expr),
+                    }),
+                    "test": self.opt(stmt.test.as_deref(), |e| self.expr(e)),
+                    "update": self.opt(stmt.update.as_deref(), |e| self.expr(e)),
+                    "body": self.stmt(&stmt.body),
+                }),
+            ),
+            Stmt::ForIn(stmt) => self.node(
+                "ForInStatement",
+                stmt.span,
+                json!({
+                    "left": self.for_head(&stmt.left),
+                    "right": self.expr(&stmt.right),
+                    "body": self.stmt(&stmt.body),
+                }),
+            ),
+            Stmt::ForOf(stmt) => self.node(
+                "ForOfStatement",
+                stmt.span,
+                json!({
+                    "await": stmt.is_await,
+                    "left": self.for_head(&stmt.left),
+                    "right": self.expr(&stmt.right),
+                    "body": self.stmt(&stmt.body),
+                }),
+            ),
+            Stmt::Decl(decl) => self.decl(decl),
+            Stmt::Expr(stmt) => self.node(
+                "ExpressionStatement",
+                stmt.span,
+                json!({ "expression": self.expr(&stmt.expr) }),
+            ),
+        }
+    }
+
+    fn block(&self, block: &BlockStmt) -> Value {
+        self.node(
+            "BlockStatement",
+            block.span,
+            json!({ "body": self.block_body(&block.stmts) }),
+        )
+    }
+
+    fn for_head(&self, head: &ForHead) -> Value {
+        match head {
+            ForHead::VarDecl(decl) => self.var_decl(decl),
+            ForHead::UsingDecl(decl) => self.using_decl(decl),
+            ForHead::Pat(pat) => self.pat(pat),
+        }
+    }
+
+    // Declarations
+
+    fn decl(&self, decl: &Decl) -> Value {
+        match decl {
+            Decl::Class(class) => self.class("ClassDeclaration", Some(&class.ident), &class.class),
+            Decl::Fn(func) => {
+                let mut value =
+                    self.function("FunctionDeclaration", Some(&func.ident), &func.function);
+                if func.declare {
+                    value["declare"] = true.into();
+                }
+                value
+            }
+            Decl::Var(decl) => self.var_decl(decl),
+            Decl::Using(decl) => self.using_decl(decl),
+            Decl::TsInterface(interface) => self.ts_interface(interface),
+            Decl::TsTypeAlias(alias) => self.node(
+                "TSTypeAliasDeclaration",
+                alias.span,
+                json!({
+                    "id": self.ident(&alias.id),
+                    "typeParameters": self.opt(alias.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                    "typeAnnotation": self.ts_type(&alias.type_ann),
+                    "declare": alias.declare,
+                }),
+            ),
+            Decl::TsEnum(decl) => self.node(
+                "TSEnumDeclaration",
+                decl.span,
+                json!({
+                    "id": self.ident(&decl.id),
+                    "const": decl.is_const,
+                    "declare": decl.declare,
+                    "members": decl.members.iter().map(|member| self.node(
+                        "TSEnumMember",
+                        member.span,
+                        json!({
+                            "id": match &member.id {
+                                TsEnumMemberId::Ident(ident) => self.ident(ident),
+                                TsEnumMemberId::Str(s) => self.str_lit(s),
+                            },
+                            "initializer": self.opt(member.init.as_deref(), |e| self.expr(e)),
+                        }),
+                    )).collect::<Vec<_>>(),
+                }),
+            ),
+            Decl::TsModule(decl) => self.node(
+                "TSModuleDeclaration",
+                decl.span,
+                json!({
+                    "id": match &decl.id {
+                        TsModuleName::Ident(ident) => self.ident(ident),
+                        TsModuleName::Str(s) => self.str_lit(s),
+                    },
+                    "body": self.opt(decl.body.as_ref(), |body| self.ts_namespace_body(body)),
+                    "declare": decl.declare,
+                    "global": decl.global,
+                    "kind": if decl.global { "global" } else if decl.namespace { "namespace" } else { "module" },
+                }),
+            ),
+        }
+    }
+
+    fn var_decl(&self, decl: &VarDecl) -> Value {
+        let kind = match decl.kind {
+            VarDeclKind::Var => "var",
+            VarDeclKind::Let => "let",
+            VarDeclKind::Const => "const",
+        };
+        self.node(
+            "VariableDeclaration",
+            decl.span,
+            json!({
+                "kind": kind,
+                "declare": decl.declare,
+                "declarations": decl.decls.iter().map(|d| self.var_declarator(d)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn using_decl(&self, decl: &UsingDecl) -> Value {
+        self.node(
+            "VariableDeclaration",
+            decl.span,
+            json!({
+                "kind": if decl.is_await { "await using" } else { "using" },
+                "declarations": decl.decls.iter().map(|d| self.var_declarator(d)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn var_declarator(&self, declarator: &VarDeclarator) -> Value {
+        self.node(
+            "VariableDeclarator",
+            declarator.span,
+            json!({
+                "id": self.pat(&declarator.name),
+                "init": self.opt(declarator.init.as_deref(), |e| self.expr(e)),
+                "definite": declarator.definite,
+            }),
+        )
+    }
+
+    fn function(&self, ty: &str, ident: Option<&Ident>, function: &Function) -> Value {
+        let mut value = self.node(
+            ty,
+            function.span,
+            json!({
+                "id": self.opt(ident, |i| self.ident(i)),
+                "params": function.params.iter().map(|p| self.param(p)).collect::<Vec<_>>(),
+                "body": self.opt(function.body.as_ref(), |b| self.block(b)),
+                "async": function.is_async,
+                "generator": function.is_generator,
+                "expression": false,
+            }),
+        );
+        if let Some(type_params) = &function.type_params {
+            value["typeParameters"] = self.ts_type_param_decl(type_params);
+        }
+        if let Some(return_type) = &function.return_type {
+            value["returnType"] = self.ts_type_ann(return_type);
+        }
+        if function.body.is_none() {
+            value["type"] = "TSDeclareFunction".into();
+        }
+        value
+    }
+
+    fn param(&self, param: &Param) -> Value {
+        let mut value = self.pat(&param.pat);
+        if !param.decorators.is_empty() {
+            value["decorators"] = self.decorators(&param.decorators);
+        }
+        value
+    }
+
+    fn decorators(&self, decorators: &[Decorator]) -> Value {
+        decorators
+            .iter()
+            .map(|d| self.node("Decorator", d.span, json!({ "expression": self.expr(&d.expr) })))
+            .collect()
+    }
+
+    fn class(&self, ty: &str, ident: Option<&Ident>, class: &Class) -> Value {
+        let mut value = self.node(
+            ty,
+            class.span,
+            json!({
+                "id": self.opt(ident, |i| self.ident(i)),
+                "superClass": self.opt(class.super_class.as_deref(), |e| self.expr(e)),
+                "body": self.node(
+                    "ClassBody",
+                    class.span,
+                    json!({
+                        "body": class.body.iter().filter_map(|m| self.class_member(m)).collect::<Vec<_>>(),
+                    }),
+                ),
+                "decorators": self.decorators(&class.decorators),
+            }),
+        );
+        if class.is_abstract {
+            value["abstract"] = true.into();
+        }
+        if let Some(type_params) = &class.type_params {
+            value["typeParameters"] = self.ts_type_param_decl(type_params);
+        }
+        if let Some(super_type_params) = &class.super_type_params {
+            value["superTypeArguments"] = self.ts_type_param_instantiation(super_type_params);
+        }
+        if !class.implements.is_empty() {
+            value["implements"] = class
+                .implements
+                .iter()
+                .map(|i| self.ts_expr_with_type_args("TSClassImplements", i))
+                .collect();
+        }
+        value
+    }
+
+    fn class_member(&self, member: &ClassMember) -> Option<Value> {
+        let value = match member {
+            ClassMember::Constructor(ctor) => {
+                let function = self.node(
+                    "FunctionExpression",
+                    ctor.span,
+                    json!({
+                        "id": null,
+                        "params": ctor.params.iter().map(|p| match p {
+                            ParamOrTsParamProp::Param(param) => self.param(param),
+                            ParamOrTsParamProp::TsParamProp(prop) => self.node(
+                                "TSParameterProperty",
+                                prop.span,
+                                json!({
+                                    "accessibility": prop.accessibility.map(accessibility),
+                                    "readonly": prop.readonly,
+                                    "override": prop.is_override,
+                                    "parameter": match &prop.param {
+                                        TsParamPropParam::Ident(ident) => self.binding_ident(ident),
+                                        TsParamPropParam::Assign(assign) => self.assign_pat(assign),
+                                    },
+                                    "decorators": self.decorators(&prop.decorators),
+                                }),
+                            ),
+                        }).collect::<Vec<_>>(),
+                        "body": self.opt(ctor.body.as_ref(), |b| self.block(b)),
+                        "async": false,
+                        "generator": false,
+                        "expression": false,
+                    }),
+                );
+                self.node(
+                    "MethodDefinition",
+                    ctor.span,
+                    json!({
+                        "key": self.prop_name(&ctor.key),
+                        "value": function,
+                        "kind": "constructor",
+                        "computed": false,
+                        "static": false,
+                        "accessibility": ctor.accessibility.map(accessibility),
+                    }),
+                )
+            }
+            ClassMember::Method(method) => self.method_definition(
+                method.span,
+                self.prop_name(&method.key),
+                matches!(method.key, PropName::Computed(_)),
+                &method.function,
+                method.kind,
+                method.is_static,
+                method.accessibility,
+                method.is_abstract,
+            ),
+            ClassMember::PrivateMethod(method) => self.method_definition(
+                method.span,
+                self.private_name(&method.key),
+                false,
+                &method.function,
+                method.kind,
+                method.is_static,
+                method.accessibility,
+                method.is_abstract,
+            ),
+            ClassMember::ClassProp(prop) => self.node(
+                if prop.is_abstract {
+                    "TSAbstractPropertyDefinition"
+                } else {
+                    "PropertyDefinition"
+                },
+                prop.span,
+                json!({
+                    "key": self.prop_name(&prop.key),
+                    "value": self.opt(prop.value.as_deref(), |e| self.expr(e)),
+                    "computed": matches!(prop.key, PropName::Computed(_)),
+                    "static": prop.is_static,
+                    "declare": prop.declare,
+                    "readonly": prop.readonly,
+                    "optional": prop.is_optional,
+                    "definite": prop.definite,
+                    "override": prop.is_override,
+                    "accessibility": prop.accessibility.map(accessibility),
+                    "typeAnnotation": self.opt(prop.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "decorators": self.decorators(&prop.decorators),
+                }),
+            ),
+            ClassMember::PrivateProp(prop) => self.node(
+                "PropertyDefinition",
+                prop.span,
+                json!({
+                    "key": self.private_name(&prop.key),
+                    "value": self.opt(prop.value.as_deref(), |e| self.expr(e)),
+                    "computed": false,
+                    "static": prop.is_static,
+                    "readonly": prop.readonly,
+                    "optional": prop.is_optional,
+                    "definite": prop.definite,
+                    "override": prop.is_override,
+                    "accessibility": prop.accessibility.map(accessibility),
+                    "typeAnnotation": self.opt(prop.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "decorators": self.decorators(&prop.decorators),
+                }),
+            ),
+            ClassMember::TsIndexSignature(signature) => self.ts_index_signature(signature),
+            ClassMember::Empty(_) => return None,
+            ClassMember::StaticBlock(block) => self.node(
+                "StaticBlock",
+                block.span,
+                json!({ "body": self.block_body(&block.body.stmts) }),
+            ),
+            ClassMember::AutoAccessor(accessor) => self.node(
+                "AccessorProperty",
+                accessor.span,
+                json!({
+                    "key": match &accessor.key {
+                        Key::Private(name) => self.private_name(name),
+                        Key::Public(name) => self.prop_name(name),
+                    },
+                    "value": self.opt(accessor.value.as_deref(), |e| self.expr(e)),
+                    "computed": matches!(accessor.key, Key::Public(PropName::Computed(_))),
+                    "static": accessor.is_static,
+                    "typeAnnotation": self.opt(accessor.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "decorators": self.decorators(&accessor.decorators),
+                }),
+            ),
+        };
+        Some(value)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn method_definition(
+        &self,
+        span: Span,
+        key: Value,
+        computed: bool,
+        function: &Function,
+        kind: MethodKind,
+        is_static: bool,
+        access: Option<Accessibility>,
+        is_abstract: bool,
+    ) -> Value {
+        let kind = match kind {
+            MethodKind::Method => "method",
+            MethodKind::Getter => "get",
+            MethodKind::Setter => "set",
+        };
+        self.node(
+            if is_abstract {
+                "TSAbstractMethodDefinition"
+            } else {
+                "MethodDefinition"
+            },
+            span,
+            json!({
+                "key": key,
+                "value": self.function("FunctionExpression", None, function),
+                "kind": kind,
+                "computed": computed,
+                "static": is_static,
+                "accessibility": access.map(accessibility),
+            }),
+        )
+    }
+
+    // Expressions
+
+    fn expr(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::This(this) => self.node("ThisExpression", this.span, json!({})),
+            Expr::Array(array) => self.node(
+                "ArrayExpression",
+                array.span,
+                json!({
+                    "elements": array.elems.iter().map(|e| self.opt(e.as_ref(), |e| self.expr_or_spread(e))).collect::<Vec<_>>(),
+                }),
+            ),
+            Expr::Object(object) => self.node(
+                "ObjectExpression",
+                object.span,
+                json!({
+                    "properties": object.props.iter().map(|p| self.prop_or_spread(p)).collect::<Vec<_>>(),
+                }),
+            ),
+            Expr::Fn(func) => self.function("FunctionExpression", func.ident.as_ref(), &func.function),
+            Expr::Unary(unary) => self.node(
+                "UnaryExpression",
+                unary.span,
+                json!({
+                    "operator": unary.op.as_str(),
+                    "prefix": true,
+                    "argument": self.expr(&unary.arg),
+                }),
+            ),
+            Expr::Update(update) => self.node(
+                "UpdateExpression",
+                update.span,
+                json!({
+                    "operator": update.op.as_str(),
+                    "prefix": update.prefix,
+                    "argument": self.expr(&update.arg),
+                }),
+            ),
+            Expr::Bin(bin) => {
+                let ty = match bin.op {
+                    BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing => {
+                        "LogicalExpression"
+                    }
+                    _ => "BinaryExpression",
+                };
+                self.node(
+                    ty,
+                    bin.span,
+                    json!({
+                        "operator": bin.op.as_str(),
+                        "left": self.expr(&bin.left),
+                        "right": self.expr(&bin.right),
+                    }),
+                )
+            }
+            Expr::Assign(assign) => self.node(
+                "AssignmentExpression",
+                assign.span,
+                json!({
+                    "operator": assign.op.as_str(),
+                    "left": self.assign_target(&assign.left),
+                    "right": self.expr(&assign.right),
+                }),
+            ),
+            Expr::Member(member) => self.member(member, false),
+            Expr::SuperProp(super_prop) => self.super_prop(super_prop),
+            Expr::Cond(cond) => self.node(
+                "ConditionalExpression",
+                cond.span,
+                json!({
+                    "test": self.expr(&cond.test),
+                    "consequent": self.expr(&cond.cons),
+                    "alternate": self.expr(&cond.alt),
+                }),
+            ),
+            Expr::Call(call) => match &call.callee {
+                Callee::Import(_) => self.node(
+                    "ImportExpression",
+                    call.span,
+                    json!({
+                        "source": self.opt(call.args.first(), |a| self.expr(&a.expr)),
+                        "options": self.opt(call.args.get(1), |a| self.expr(&a.expr)),
+                    }),
+                ),
+                callee => {
+                    let callee = match callee {
+                        Callee::Super(sup) => self.node("Super", sup.span, json!({})),
+                        Callee::Expr(expr) => self.expr(expr),
+                        Callee::Import(_) => unreachable!(),
+                    };
+                    self.call("CallExpression", call.span, callee, &call.args, call.type_args.as_deref(), false)
+                }
+            },
+            Expr::New(new) => self.call(
+                "NewExpression",
+                new.span,
+                self.expr(&new.callee),
+                new.args.as_deref().unwrap_or_default(),
+                new.type_args.as_deref(),
+                false,
+            ),
+            Expr::Seq(seq) => self.node(
+                "SequenceExpression",
+                seq.span,
+                json!({ "expressions": seq.exprs.iter().map(|e| self.expr(e)).collect::<Vec<_>>() }),
+            ),
+            Expr::Ident(ident) => self.ident(ident),
+            Expr::Lit(lit) => self.lit(lit),
+            Expr::Tpl(tpl) => self.tpl(tpl),
+            Expr::TaggedTpl(tagged) => {
+                let mut value = self.node(
+                    "TaggedTemplateExpression",
+                    tagged.span,
+                    json!({ "tag": self.expr(&tagged.tag), "quasi": self.tpl(&tagged.tpl) }),
+                );
+                if let Some(type_params) = &tagged.type_params {
+                    value["typeArguments"] = self.ts_type_param_instantiation(type_params);
+                }
+                value
+            }
+            Expr::Arrow(arrow) => {
+                let (body, expression) = match &*arrow.body {
+                    BlockStmtOrExpr::BlockStmt(block) => (self.block(block), false),
+                    BlockStmtOrExpr::Expr(expr) => (self.expr(expr), true),
+                };
+                let mut value = self.node(
+                    "ArrowFunctionExpression",
+                    arrow.span,
+                    json!({
+                        "id": null,
+                        "params": arrow.params.iter().map(|p| self.pat(p)).collect::<Vec<_>>(),
+                        "body": body,
+                        "async": arrow.is_async,
+                        "generator": arrow.is_generator,
+                        "expression": expression,
+                    }),
+                );
+                if let Some(type_params) = &arrow.type_params {
+                    value["typeParameters"] = self.ts_type_param_decl(type_params);
+                }
+                if let Some(return_type) = &arrow.return_type {
+                    value["returnType"] = self.ts_type_ann(return_type);
+                }
+                value
+            }
+            Expr::Class(class) => self.class("ClassExpression", class.ident.as_ref(), &class.class),
+            Expr::Yield(yield_expr) => self.node(
+                "YieldExpression",
+                yield_expr.span,
+                json!({
+                    "argument": self.opt(yield_expr.arg.as_deref(), |e| self.expr(e)),
+                    "delegate": yield_expr.delegate,
+                }),
+            ),
+            Expr::MetaProp(meta) => {
+                let (meta_name, property) = match meta.kind {
+                    MetaPropKind::NewTarget => ("new", "target"),
+                    MetaPropKind::ImportMeta => ("import", "meta"),
+                };
+                self.node(
+                    "MetaProperty",
+                    meta.span,
+                    json!({
+                        "meta": self.node("Identifier", Span::new(meta.span.lo, meta.span.lo + BytePos(meta_name.len() as u32)), json!({ "name": meta_name })),
+                        "property": self.node("Identifier", Span::new(meta.span.hi - BytePos(property.len() as u32), meta.span.hi), json!({ "name": property })),
+                    }),
+                )
+            }
+            Expr::Await(await_expr) => self.node(
+                "AwaitExpression",
+                await_expr.span,
+                json!({ "argument": self.expr(&await_expr.arg) }),
+            ),
+            // ESTree has no parenthesized expression node
+            Expr::Paren(paren) => self.expr(&paren.expr),
+            Expr::JSXMember(member) => self.jsx_member(member),
+            Expr::JSXNamespacedName(name) => self.jsx_namespaced_name(name),
+            Expr::JSXEmpty(empty) => self.node("JSXEmptyExpression", empty.span, json!({})),
+            Expr::JSXElement(element) => self.jsx_element(element),
+            Expr::JSXFragment(fragment) => self.jsx_fragment(fragment),
+            Expr::TsTypeAssertion(assertion) => self.node(
+                "TSTypeAssertion",
+                assertion.span,
+                json!({
+                    "expression": self.expr(&assertion.expr),
+                    "typeAnnotation": self.ts_type(&assertion.type_ann),
+                }),
+            ),
+            Expr::TsConstAssertion(assertion) => self.node(
+                "TSAsExpression",
+                assertion.span,
+                json!({
+                    "expression": self.expr(&assertion.expr),
+                    "typeAnnotation": self.node(
+                        "TSTypeReference",
+                        Span::new(assertion.span.hi - BytePos(5), assertion.span.hi),
+                        json!({ "typeName": { "type": "Identifier", "name": "const" } }),
+                    ),
+                }),
+            ),
+            Expr::TsNonNull(non_null) => self.node(
+                "TSNonNullExpression",
+                non_null.span,
+                json!({ "expression": self.expr(&non_null.expr) }),
+            ),
+            Expr::TsAs(as_expr) => self.node(
+                "TSAsExpression",
+                as_expr.span,
+                json!({
+                    "expression": self.expr(&as_expr.expr),
+                    "typeAnnotation": self.ts_type(&as_expr.type_ann),
+                }),
+            ),
+            Expr::TsInstantiation(instantiation) => self.node(
+                "TSInstantiationExpression",
+                instantiation.span,
+                json!({
+                    "expression": self.expr(&instantiation.expr),
+                    "typeArguments": self.ts_type_param_instantiation(&instantiation.type_args),
+                }),
+            ),
+            Expr::TsSatisfies(satisfies) => self.node(
+                "TSSatisfiesExpression",
+                satisfies.span,
+                json!({
+                    "expression": self.expr(&satisfies.expr),
+                    "typeAnnotation": self.ts_type(&satisfies.type_ann),
+                }),
+            ),
+            Expr::PrivateName(name) => self.private_name(name),
+            Expr::OptChain(chain) => self.node(
+                "ChainExpression",
+                chain.span,
+                json!({ "expression": self.opt_chain(chain) }),
+            ),
+            Expr::Invalid(invalid) => self.node("Invalid", invalid.span, json!({})),
+        }
+    }
+
+    /// Converts the inside of an optional chain without another `ChainExpression` wrapper
+    fn opt_chain(&self, chain: &OptChainExpr) -> Value {
+        let chain_expr = |expr: &Expr| match expr {
+            Expr::OptChain(inner) => self.opt_chain(inner),
+            expr => self.expr(expr),
+        };
+        match &*chain.base {
+            OptChainBase::Member(member) => {
+                let mut value = self.member(member, chain.optional);
+                value["object"] = chain_expr(&member.obj);
+                value
+            }
+            OptChainBase::Call(call) => self.call(
+                "CallExpression",
+                call.span,
+                chain_expr(&call.callee),
+                &call.args,
+                call.type_args.as_deref(),
+                chain.optional,
+            ),
+        }
+    }
+
+    fn member(&self, member: &MemberExpr, optional: bool) -> Value {
+        let (property, computed) = match &member.prop {
+            MemberProp::Ident(ident) => (self.ident_name(ident), false),
+            MemberProp::PrivateName(name) => (self.private_name(name), false),
+            MemberProp::Computed(computed) => (self.expr(&computed.expr), true),
+        };
+        self.node(
+            "MemberExpression",
+            member.span,
+            json!({
+                "object": self.expr(&member.obj),
+                "property": property,
+                "computed": computed,
+                "optional": optional,
+            }),
+        )
+    }
+
+    fn super_prop(&self, super_prop: &SuperPropExpr) -> Value {
+        let (property, computed) = match &super_prop.prop {
+            SuperProp::Ident(ident) => (self.ident_name(ident), false),
+            SuperProp::Computed(computed) => (self.expr(&computed.expr), true),
+        };
+        self.node(
+            "MemberExpression",
+            super_prop.span,
+            json!({
+                "object": self.node("Super", super_prop.obj.span, json!({})),
+                "property": property,
+                "computed": computed,
+                "optional": false,
+            }),
+        )
+    }
+
+    fn call(
+        &self,
+        ty: &str,
+        span: Span,
+        callee: Value,
+        args: &[ExprOrSpread],
+        type_args: Option<&TsTypeParamInstantiation>,
+        optional: bool,
+    ) -> Value {
+        let mut value = self.node(
+            ty,
+            span,
+            json!({
+                "callee": callee,
+                "arguments": args.iter().map(|a| self.expr_or_spread(a)).collect::<Vec<_>>(),
+            }),
+        );
+        if ty == "CallExpression" {
+            value["optional"] = optional.into();
+        }
+        if let Some(type_args) = type_args {
+            value["typeArguments"] = self.ts_type_param_instantiation(type_args);
+        }
+        value
+    }
+
+    fn expr_or_spread(&self, arg: &ExprOrSpread) -> Value {
+        match arg.spread {
+            Some(spread) => self.node(
+                "SpreadElement",
+                Span::new(spread.lo, arg.expr.span_hi()),
+                json!({ "argument": self.expr(&arg.expr) }),
+            ),
+            None => self.expr(&arg.expr),
+        }
+    }
+
+    fn prop_or_spread(&self, prop: &PropOrSpread) -> Value {
+        match prop {
+            PropOrSpread::Spread(spread) => self.node(
+                "SpreadElement",
+                Span::new(spread.dot3_token.lo, spread.expr.span_hi()),
+                json!({ "argument": self.expr(&spread.expr) }),
+            ),
+            PropOrSpread::Prop(prop) => self.prop(prop),
+        }
+    }
+
+    fn prop(&self, prop: &Prop) -> Value {
+        let property = |key: &PropName, value: Value, kind: &str, method: bool, shorthand: bool| {
+            self.node(
+                "Property",
+                Span::new(key.span_lo(), prop.span_hi()),
+                json!({
+                    "key": self.prop_name(key),
+                    "value": value,
+                    "kind": kind,
+                    "method": method,
+                    "shorthand": shorthand,
+                    "computed": matches!(key, PropName::Computed(_)),
+                }),
+            )
+        };
+        match prop {
+            Prop::Shorthand(ident) => self.node(
+                "Property",
+                ident.span,
+                json!({
+                    "key": self.ident(ident),
+                    "value": self.ident(ident),
+                    "kind": "init",
+                    "method": false,
+                    "shorthand": true,
+                    "computed": false,
+                }),
+            ),
+            Prop::KeyValue(kv) => property(&kv.key, self.expr(&kv.value), "init", false, false),
+            // only valid as a destructuring target, e.g. `({ a = 1 } = obj)`
+            Prop::Assign(assign) => self.node(
+                "Property",
+                assign.span,
+                json!({
+                    "key": self.ident(&assign.key),
+                    "value": self.node(
+                        "AssignmentPattern",
+                        assign.span,
+                        json!({ "left": self.ident(&assign.key), "right": self.expr(&assign.value) }),
+                    ),
+                    "kind": "init",
+                    "method": false,
+                    "shorthand": true,
+                    "computed": false,
+                }),
+            ),
+            Prop::Getter(getter) => {
+                let function = self.node(
+                    "FunctionExpression",
+                    getter.span,
+                    json!({
+                        "id": null,
+                        "params": [],
+                        "body": self.opt(getter.body.as_ref(), |b| self.block(b)),
+                        "async": false,
+                        "generator": false,
+                        "expression": false,
+                    }),
+                );
+                property(&getter.key, function, "get", false, false)
+            }
+            Prop::Setter(setter) => {
+                let function = self.node(
+                    "FunctionExpression",
+                    setter.span,
+                    json!({
+                        "id": null,
+                        "params": [self.pat(&setter.param)],
+                        "body": self.opt(setter.body.as_ref(), |b| self.block(b)),
+                        "async": false,
+                        "generator": false,
+                        "expression": false,
+                    }),
+                );
+                property(&setter.key, function, "set", false, false)
+            }
+            Prop::Method(method) => property(
+                &method.key,
+                self.function("FunctionExpression", None, &method.function),
+                "init",
+                true,
+                false,
+            ),
+        }
+    }
+
+    fn prop_name(&self, name: &PropName) -> Value {
+        match name {
+            PropName::Ident(ident) => self.ident_name(ident),
+            PropName::Str(s) => self.str_lit(s),
+            PropName::Num(num) => self.lit(&Lit::Num(num.clone())),
+            PropName::Computed(computed) => self.expr(&computed.expr),
+            PropName::BigInt(bigint) => self.lit(&Lit::BigInt(bigint.clone())),
+        }
+    }
+
+    fn tpl(&self, tpl: &Tpl) -> Value {
+        self.node(
+            "TemplateLiteral",
+            tpl.span,
+            json!({
+                "quasis": tpl.quasis.iter().map(|q| self.tpl_element(q)).collect::<Vec<_>>(),
+                "expressions": tpl.exprs.iter().map(|e| self.expr(e)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn tpl_element(&self, element: &TplElement) -> Value {
+        self.node(
+            "TemplateElement",
+            element.span,
+            json!({
+                "value": { "raw": &*element.raw, "cooked": element.cooked.as_deref() },
+                "tail": element.tail,
+            }),
+        )
+    }
+
+    fn assign_target(&self, target: &AssignTarget) -> Value {
+        match target {
+            AssignTarget::Simple(simple) => match simple {
+                SimpleAssignTarget::Ident(ident) => self.binding_ident(ident),
+                SimpleAssignTarget::Member(member) => self.member(member, false),
+                SimpleAssignTarget::SuperProp(super_prop) => self.super_prop(super_prop),
+                SimpleAssignTarget::Paren(paren) => self.expr(&paren.expr),
+                SimpleAssignTarget::OptChain(chain) => self.expr(&Expr::OptChain(chain.clone())),
+                SimpleAssignTarget::TsAs(e) => self.expr(&Expr::TsAs(e.clone())),
+                SimpleAssignTarget::TsSatisfies(e) => self.expr(&Expr::TsSatisfies(e.clone())),
+                SimpleAssignTarget::TsNonNull(e) => self.expr(&Expr::TsNonNull(e.clone())),
+                SimpleAssignTarget::TsTypeAssertion(e) => {
+                    self.expr(&Expr::TsTypeAssertion(e.clone()))
+                }
+                SimpleAssignTarget::TsInstantiation(e) => {
+                    self.expr(&Expr::TsInstantiation(e.clone()))
+                }
+                SimpleAssignTarget::Invalid(invalid) => self.node("Invalid", invalid.span, json!({})),
+            },
+            AssignTarget::Pat(pat) => match pat {
+                AssignTargetPat::Array(array) => self.pat(&Pat::Array(array.clone())),
+                AssignTargetPat::Object(object) => self.pat(&Pat::Object(object.clone())),
+                AssignTargetPat::Invalid(invalid) => self.node("Invalid", invalid.span, json!({})),
+            },
+        }
+    }
+
+    // Patterns
+
+    fn pat(&self, pat: &Pat) -> Value {
+        match pat {
+            Pat::Ident(ident) => self.binding_ident(ident),
+            Pat::Array(array) => {
+                let mut value = self.node(
+                    "ArrayPattern",
+                    array.span,
+                    json!({
+                        "elements": array.elems.iter().map(|e| self.opt(e.as_ref(), |p| self.pat(p))).collect::<Vec<_>>(),
+                    }),
+                );
+                self.pattern_type(&mut value, array.optional, array.type_ann.as_deref());
+                value
+            }
+            Pat::Rest(rest) => {
+                let mut value = self.node(
+                    "RestElement",
+                    rest.span,
+                    json!({ "argument": self.pat(&rest.arg) }),
+                );
+                self.pattern_type(&mut value, false, rest.type_ann.as_deref());
+                value
+            }
+            Pat::Object(object) => {
+                let mut value = self.node(
+                    "ObjectPattern",
+                    object.span,
+                    json!({
+                        "properties": object.props.iter().map(|p| self.object_pat_prop(p)).collect::<Vec<_>>(),
+                    }),
+                );
+                self.pattern_type(&mut value, object.optional, object.type_ann.as_deref());
+                value
+            }
+            Pat::Assign(assign) => self.assign_pat(assign),
+            Pat::Invalid(invalid) => self.node("Invalid", invalid.span, json!({})),
+            Pat::Expr(expr) => self.expr(expr),
+        }
+    }
+
+    fn pattern_type(&self, value: &mut Value, optional: bool, type_ann: Option<&TsTypeAnn>) {
+        if optional {
+            value["optional"] = true.into();
+        }
+        if let Some(type_ann) = type_ann {
+            value["typeAnnotation"] = self.ts_type_ann(type_ann);
+        }
+    }
+
+    fn assign_pat(&self, assign: &AssignPat) -> Value {
+        self.node(
+            "AssignmentPattern",
+            assign.span,
+            json!({ "left": self.pat(&assign.left), "right": self.expr(&assign.right) }),
+        )
+    }
+
+    fn object_pat_prop(&self, prop: &ObjectPatProp) -> Value {
+        match prop {
+            ObjectPatProp::KeyValue(kv) => self.node(
+                "Property",
+                Span::new(kv.key.span_lo(), kv.value.span_hi()),
+                json!({
+                    "key": self.prop_name(&kv.key),
+                    "value": self.pat(&kv.value),
+                    "kind": "init",
+                    "method": false,
+                    "shorthand": false,
+                    "computed": matches!(kv.key, PropName::Computed(_)),
+                }),
+            ),
+            ObjectPatProp::Assign(assign) => {
+                let value = match &assign.value {
+                    Some(default) => self.node(
+                        "AssignmentPattern",
+                        assign.span,
+                        json!({ "left": self.binding_ident(&assign.key), "right": self.expr(default) }),
+                    ),
+                    None => self.binding_ident(&assign.key),
+                };
+                self.node(
+                    "Property",
+                    assign.span,
+                    json!({
+                        "key": self.ident(&assign.key.id),
+                        "value": value,
+                        "kind": "init",
+                        "method": false,
+                        "shorthand": true,
+                        "computed": false,
+                    }),
+                )
+            }
+            ObjectPatProp::Rest(rest) => self.pat(&Pat::Rest(rest.clone())),
+        }
+    }
+
+    // Identifiers and literals
+
+    fn ident(&self, ident: &Ident) -> Value {
+        let mut value = self.node("Identifier", ident.span, json!({ "name": &*ident.sym }));
+        if ident.optional {
+            value["optional"] = true.into();
+        }
+        value
+    }
+
+    fn ident_name(&self, ident: &IdentName) -> Value {
+        self.node("Identifier", ident.span, json!({ "name": &*ident.sym }))
+    }
+
+    fn binding_ident(&self, ident: &BindingIdent) -> Value {
+        let mut value = self.ident(&ident.id);
+        if let Some(type_ann) = &ident.type_ann {
+            value["typeAnnotation"] = self.ts_type_ann(type_ann);
+            // the identifier span excludes the annotation, ESTree includes it
+            self.set_span(&mut value, ident.span());
+        }
+        value
+    }
+
+    fn private_name(&self, name: &PrivateName) -> Value {
+        self.node("PrivateIdentifier", name.span, json!({ "name": &*name.name }))
+    }
+
+    fn str_lit(&self, s: &Str) -> Value {
+        self.node(
+            "Literal",
+            s.span,
+            json!({
+                "value": &*s.value,
+                "raw": s.raw.as_deref().map(str::to_string).unwrap_or_else(|| format!("{:?}", &*s.value)),
+            }),
+        )
+    }
+
+    fn lit(&self, lit: &Lit) -> Value {
+        match lit {
+            Lit::Str(s) => self.str_lit(s),
+            Lit::Bool(b) => self.node(
+                "Literal",
+                b.span,
+                json!({ "value": b.value, "raw": b.value.to_string() }),
+            ),
+            Lit::Null(null) => self.node("Literal", null.span, json!({ "value": null, "raw": "null" })),
+            Lit::Num(num) => self.node(
+                "Literal",
+                num.span,
+                json!({
+                    "value": num.value,
+                    "raw": num.raw.as_deref().map(str::to_string).unwrap_or_else(|| num.value.to_string()),
+                }),
+            ),
+            Lit::BigInt(bigint) => {
+                let digits = bigint.value.to_string();
+                self.node(
+                    "Literal",
+                    bigint.span,
+                    json!({
+                        "value": null,
+                        "raw": bigint.raw.as_deref().map(str::to_string).unwrap_or_else(|| format!("{digits}n")),
+                        "bigint": digits,
+                    }),
+                )
+            }
+            Lit::Regex(regex) => self.node(
+                "Literal",
+                regex.span,
+                json!({
+                    "value": null,
+                    "raw": format!("/{}/{}", regex.exp, regex.flags),
+                    "regex": { "pattern": &*regex.exp, "flags": &*regex.flags },
+                }),
+            ),
+            Lit::JSXText(text) => self.jsx_text(text),
+        }
+    }
+
+    // JSX
+
+    fn jsx_element(&self, element: &JSXElement) -> Value {
+        let opening = &element.opening;
+        let mut opening_value = self.node(
+            "JSXOpeningElement",
+            opening.span,
+            json!({
+                "name": self.jsx_element_name(&opening.name),
+                "attributes": opening.attrs.iter().map(|a| self.jsx_attr_or_spread(a)).collect::<Vec<_>>(),
+                "selfClosing": opening.self_closing,
+            }),
+        );
+        if let Some(type_args) = &opening.type_args {
+            opening_value["typeArguments"] = self.ts_type_param_instantiation(type_args);
+        }
+        self.node(
+            "JSXElement",
+            element.span,
+            json!({
+                "openingElement": opening_value,
+                "closingElement": self.opt(element.closing.as_ref(), |closing| self.node(
+                    "JSXClosingElement",
+                    closing.span,
+                    json!({ "name": self.jsx_element_name(&closing.name) }),
+                )),
+                "children": element.children.iter().map(|c| self.jsx_child(c)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn jsx_fragment(&self, fragment: &JSXFragment) -> Value {
+        self.node(
+            "JSXFragment",
+            fragment.span,
+            json!({
+                "openingFragment": self.node("JSXOpeningFragment", fragment.opening.span, json!({})),
+                "closingFragment": self.node("JSXClosingFragment", fragment.closing.span, json!({})),
+                "children": fragment.children.iter().map(|c| self.jsx_child(c)).collect::<Vec<_>>(),
+            }),
+        )
+    }
+
+    fn jsx_child(&self, child: &JSXElementChild) -> Value {
+        match child {
+            JSXElementChild::JSXText(text) => self.jsx_text(text),
+            JSXElementChild::JSXExprContainer(container) => self.jsx_expr_container(container),
+            JSXElementChild::JSXSpreadChild(spread) => self.node(
+                "JSXSpreadChild",
+                spread.span,
+                json!({ "expression": self.expr(&spread.expr) }),
+            ),
+            JSXElementChild::JSXElement(element) => self.jsx_element(element),
+            JSXElementChild::JSXFragment(fragment) => self.jsx_fragment(fragment),
+        }
+    }
+
+    fn jsx_text(&self, text: &JSXText) -> Value {
+        self.node(
+            "JSXText",
+            text.span,
+            json!({ "value": &*text.value, "raw": &*text.raw }),
+        )
+    }
+
+    fn jsx_expr_container(&self, container: &JSXExprContainer) -> Value {
+        let expression = match &container.expr {
+            JSXExpr::JSXEmptyExpr(empty) => self.node("JSXEmptyExpression", empty.span, json!({})),
+            JSXExpr::Expr(expr) => self.expr(expr),
+        };
+        self.node(
+            "JSXExpressionContainer",
+            container.span,
+            json!({ "expression": expression }),
+        )
+    }
+
+    fn jsx_attr_or_spread(&self, attr: &JSXAttrOrSpread) -> Value {
+        match attr {
+            JSXAttrOrSpread::JSXAttr(attr) => self.node(
+                "JSXAttribute",
+                attr.span,
+                json!({
+                    "name": match &attr.name {
+                        JSXAttrName::Ident(ident) => self.jsx_identifier(ident.span, &ident.sym),
+                        JSXAttrName::JSXNamespacedName(name) => self.jsx_namespaced_name(name),
+                    },
+                    "value": self.opt(attr.value.as_ref(), |value| match value {
+                        JSXAttrValue::Lit(lit) => self.lit(lit),
+                        JSXAttrValue::JSXExprContainer(container) => self.jsx_expr_container(container),
+                        JSXAttrValue::JSXElement(element) => self.jsx_element(element),
+                        JSXAttrValue::JSXFragment(fragment) => self.jsx_fragment(fragment),
+                    }),
+                }),
+            ),
+            JSXAttrOrSpread::SpreadElement(spread) => self.node(
+                "JSXSpreadAttribute",
+                Span::new(spread.dot3_token.lo, spread.expr.span_hi()),
+                json!({ "argument": self.expr(&spread.expr) }),
+            ),
+        }
+    }
+
+    fn jsx_element_name(&self, name: &JSXElementName) -> Value {
+        match name {
+            JSXElementName::Ident(ident) => self.jsx_identifier(ident.span, &ident.sym),
+            JSXElementName::JSXMemberExpr(member) => self.jsx_member(member),
+            JSXElementName::JSXNamespacedName(name) => self.jsx_namespaced_name(name),
+        }
+    }
+
+    fn jsx_identifier(&self, span: Span, name: &str) -> Value {
+        self.node("JSXIdentifier", span, json!({ "name": name }))
+    }
+
+    fn jsx_member(&self, member: &JSXMemberExpr) -> Value {
+        let object = match &member.obj {
+            JSXObject::JSXMemberExpr(inner) => self.jsx_member(inner),
+            JSXObject::Ident(ident) => self.jsx_identifier(ident.span, &ident.sym),
+        };
+        self.node(
+            "JSXMemberExpression",
+            member.span,
+            json!({
+                "object": object,
+                "property": self.jsx_identifier(member.prop.span, &member.prop.sym),
+            }),
+        )
+    }
+
+    fn jsx_namespaced_name(&self, name: &JSXNamespacedName) -> Value {
+        self.node(
+            "JSXNamespacedName",
+            name.span,
+            json!({
+                "namespace": self.jsx_identifier(name.ns.span, &name.ns.sym),
+                "name": self.jsx_identifier(name.name.span, &name.name.sym),
+            }),
+        )
+    }
+
+    // TypeScript
+
+    fn ts_type_ann(&self, type_ann: &TsTypeAnn) -> Value {
+        self.node(
+            "TSTypeAnnotation",
+            type_ann.span,
+            json!({ "typeAnnotation": self.ts_type(&type_ann.type_ann) }),
+        )
+    }
+
+    fn ts_type_param_decl(&self, decl: &TsTypeParamDecl) -> Value {
+        self.node(
+            "TSTypeParameterDeclaration",
+            decl.span,
+            json!({ "params": decl.params.iter().map(|p| self.ts_type_param(p)).collect::<Vec<_>>() }),
+        )
+    }
+
+    fn ts_type_param(&self, param: &TsTypeParam) -> Value {
+        self.node(
+            "TSTypeParameter",
+            param.span,
+            json!({
+                "name": self.ident(&param.name),
+                "constraint": self.opt(param.constraint.as_deref(), |t| self.ts_type(t)),
+                "default": self.opt(param.default.as_deref(), |t| self.ts_type(t)),
+                "in": param.is_in,
+                "out": param.is_out,
+                "const": param.is_const,
+            }),
+        )
+    }
+
+    fn ts_type_param_instantiation(&self, instantiation: &TsTypeParamInstantiation) -> Value {
+        self.node(
+            "TSTypeParameterInstantiation",
+            instantiation.span,
+            json!({ "params": instantiation.params.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+        )
+    }
+
+    fn ts_expr_with_type_args(&self, ty: &str, expr: &TsExprWithTypeArgs) -> Value {
+        self.node(
+            ty,
+            expr.span,
+            json!({
+                "expression": self.expr(&expr.expr),
+                "typeArguments": self.opt(expr.type_args.as_deref(), |t| self.ts_type_param_instantiation(t)),
+            }),
+        )
+    }
+
+    fn ts_entity_name(&self, name: &TsEntityName) -> Value {
+        match name {
+            TsEntityName::Ident(ident) => self.ident(ident),
+            TsEntityName::TsQualifiedName(qualified) => self.node(
+                "TSQualifiedName",
+                qualified.span,
+                json!({
+                    "left": self.ts_entity_name(&qualified.left),
+                    "right": self.ident_name(&qualified.right),
+                }),
+            ),
+        }
+    }
+
+    fn ts_interface(&self, interface: &TsInterfaceDecl) -> Value {
+        self.node(
+            "TSInterfaceDeclaration",
+            interface.span,
+            json!({
+                "id": self.ident(&interface.id),
+                "declare": interface.declare,
+                "typeParameters": self.opt(interface.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                "extends": interface.extends.iter().map(|e| self.ts_expr_with_type_args("TSInterfaceHeritage", e)).collect::<Vec<_>>(),
+                "body": self.node(
+                    "TSInterfaceBody",
+                    interface.body.span,
+                    json!({ "body": interface.body.body.iter().map(|e| self.ts_type_element(e)).collect::<Vec<_>>() }),
+                ),
+            }),
+        )
+    }
+
+    fn ts_namespace_body(&self, body: &TsNamespaceBody) -> Value {
+        match body {
+            TsNamespaceBody::TsModuleBlock(block) => self.node(
+                "TSModuleBlock",
+                block.span,
+                json!({ "body": block.body.iter().map(|i| self.module_item(i)).collect::<Vec<_>>() }),
+            ),
+            TsNamespaceBody::TsNamespaceDecl(decl) => self.node(
+                "TSModuleDeclaration",
+                decl.span,
+                json!({
+                    "id": self.ident(&decl.id),
+                    "body": self.ts_namespace_body(&decl.body),
+                    "declare": decl.declare,
+                    "global": decl.global,
+                    "kind": "namespace",
+                }),
+            ),
+        }
+    }
+
+    fn ts_fn_param(&self, param: &TsFnParam) -> Value {
+        match param {
+            TsFnParam::Ident(ident) => self.binding_ident(ident),
+            TsFnParam::Array(array) => self.pat(&Pat::Array(array.clone())),
+            TsFnParam::Rest(rest) => self.pat(&Pat::Rest(rest.clone())),
+            TsFnParam::Object(object) => self.pat(&Pat::Object(object.clone())),
+        }
+    }
+
+    fn ts_fn_params(&self, params: &[TsFnParam]) -> Vec<Value> {
+        params.iter().map(|p| self.ts_fn_param(p)).collect()
+    }
+
+    fn ts_index_signature(&self, signature: &TsIndexSignature) -> Value {
+        self.node(
+            "TSIndexSignature",
+            signature.span,
+            json!({
+                "parameters": self.ts_fn_params(&signature.params),
+                "typeAnnotation": self.opt(signature.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                "readonly": signature.readonly,
+                "static": signature.is_static,
+            }),
+        )
+    }
+
+    fn ts_type_element(&self, element: &TsTypeElement) -> Value {
+        match element {
+            TsTypeElement::TsCallSignatureDecl(sig) => self.node(
+                "TSCallSignatureDeclaration",
+                sig.span,
+                json!({
+                    "params": self.ts_fn_params(&sig.params),
+                    "returnType": self.opt(sig.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "typeParameters": self.opt(sig.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                }),
+            ),
+            TsTypeElement::TsConstructSignatureDecl(sig) => self.node(
+                "TSConstructSignatureDeclaration",
+                sig.span,
+                json!({
+                    "params": self.ts_fn_params(&sig.params),
+                    "returnType": self.opt(sig.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "typeParameters": self.opt(sig.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                }),
+            ),
+            TsTypeElement::TsPropertySignature(sig) => self.node(
+                "TSPropertySignature",
+                sig.span,
+                json!({
+                    "key": self.expr(&sig.key),
+                    "computed": sig.computed,
+                    "optional": sig.optional,
+                    "readonly": sig.readonly,
+                    "typeAnnotation": self.opt(sig.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                }),
+            ),
+            TsTypeElement::TsGetterSignature(sig) => self.node(
+                "TSMethodSignature",
+                sig.span,
+                json!({
+                    "key": self.expr(&sig.key),
+                    "computed": sig.computed,
+                    "kind": "get",
+                    "params": [],
+                    "returnType": self.opt(sig.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                }),
+            ),
+            TsTypeElement::TsSetterSignature(sig) => self.node(
+                "TSMethodSignature",
+                sig.span,
+                json!({
+                    "key": self.expr(&sig.key),
+                    "computed": sig.computed,
+                    "kind": "set",
+                    "params": [self.ts_fn_param(&sig.param)],
+                }),
+            ),
+            TsTypeElement::TsMethodSignature(sig) => self.node(
+                "TSMethodSignature",
+                sig.span,
+                json!({
+                    "key": self.expr(&sig.key),
+                    "computed": sig.computed,
+                    "optional": sig.optional,
+                    "kind": "method",
+                    "params": self.ts_fn_params(&sig.params),
+                    "returnType": self.opt(sig.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                    "typeParameters": self.opt(sig.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                }),
+            ),
+            TsTypeElement::TsIndexSignature(sig) => self.ts_index_signature(sig),
+        }
+    }
+
+    fn ts_type(&self, ty: &TsType) -> Value {
+        match ty {
+            TsType::TsKeywordType(keyword) => {
+                let name = match keyword.kind {
+                    TsKeywordTypeKind::TsAnyKeyword => "TSAnyKeyword",
+                    TsKeywordTypeKind::TsUnknownKeyword => "TSUnknownKeyword",
+                    TsKeywordTypeKind::TsNumberKeyword => "TSNumberKeyword",
+                    TsKeywordTypeKind::TsObjectKeyword => "TSObjectKeyword",
+                    TsKeywordTypeKind::TsBooleanKeyword => "TSBooleanKeyword",
+                    TsKeywordTypeKind::TsBigIntKeyword => "TSBigIntKeyword",
+                    TsKeywordTypeKind::TsStringKeyword => "TSStringKeyword",
+                    TsKeywordTypeKind::TsSymbolKeyword => "TSSymbolKeyword",
+                    TsKeywordTypeKind::TsVoidKeyword => "TSVoidKeyword",
+                    TsKeywordTypeKind::TsUndefinedKeyword => "TSUndefinedKeyword",
+                    TsKeywordTypeKind::TsNullKeyword => "TSNullKeyword",
+                    TsKeywordTypeKind::TsNeverKeyword => "TSNeverKeyword",
+                    TsKeywordTypeKind::TsIntrinsicKeyword => "TSIntrinsicKeyword",
+                };
+                self.node(name, keyword.span, json!({}))
+            }
+            TsType::TsThisType(this) => self.node("TSThisType", this.span, json!({})),
+            TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsFnType(fn_type)) => self.node(
+                "TSFunctionType",
+                fn_type.span,
+                json!({
+                    "params": self.ts_fn_params(&fn_type.params),
+                    "returnType": self.ts_type_ann(&fn_type.type_ann),
+                    "typeParameters": self.opt(fn_type.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                }),
+            ),
+            TsType::TsFnOrConstructorType(TsFnOrConstructorType::TsConstructorType(ctor)) => self.node(
+                "TSConstructorType",
+                ctor.span,
+                json!({
+                    "params": self.ts_fn_params(&ctor.params),
+                    "returnType": self.ts_type_ann(&ctor.type_ann),
+                    "typeParameters": self.opt(ctor.type_params.as_deref(), |p| self.ts_type_param_decl(p)),
+                    "abstract": ctor.is_abstract,
+                }),
+            ),
+            TsType::TsTypeRef(type_ref) => self.node(
+                "TSTypeReference",
+                type_ref.span,
+                json!({
+                    "typeName": self.ts_entity_name(&type_ref.type_name),
+                    "typeArguments": self.opt(type_ref.type_params.as_deref(), |t| self.ts_type_param_instantiation(t)),
+                }),
+            ),
+            TsType::TsTypeQuery(query) => self.node(
+                "TSTypeQuery",
+                query.span,
+                json!({
+                    "exprName": match &query.expr_name {
+                        TsTypeQueryExpr::TsEntityName(name) => self.ts_entity_name(name),
+                        TsTypeQueryExpr::Import(import) => self.ts_import_type(import),
+                    },
+                    "typeArguments": self.opt(query.type_args.as_deref(), |t| self.ts_type_param_instantiation(t)),
+                }),
+            ),
+            TsType::TsTypeLit(lit) => self.node(
+                "TSTypeLiteral",
+                lit.span,
+                json!({ "members": lit.members.iter().map(|m| self.ts_type_element(m)).collect::<Vec<_>>() }),
+            ),
+            TsType::TsArrayType(array) => self.node(
+                "TSArrayType",
+                array.span,
+                json!({ "elementType": self.ts_type(&array.elem_type) }),
+            ),
+            TsType::TsTupleType(tuple) => self.node(
+                "TSTupleType",
+                tuple.span,
+                json!({
+                    "elementTypes": tuple.elem_types.iter().map(|element| match &element.label {
+                        Some(label) => self.node(
+                            "TSNamedTupleMember",
+                            element.span,
+                            json!({ "label": self.pat(label), "elementType": self.ts_type(&element.ty) }),
+                        ),
+                        None => self.ts_type(&element.ty),
+                    }).collect::<Vec<_>>(),
+                }),
+            ),
+            TsType::TsOptionalType(optional) => self.node(
+                "TSOptionalType",
+                optional.span,
+                json!({ "typeAnnotation": self.ts_type(&optional.type_ann) }),
+            ),
+            TsType::TsRestType(rest) => self.node(
+                "TSRestType",
+                rest.span,
+                json!({ "typeAnnotation": self.ts_type(&rest.type_ann) }),
+            ),
+            TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsUnionType(union)) => self.node(
+                "TSUnionType",
+                union.span,
+                json!({ "types": union.types.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+            ),
+            TsType::TsUnionOrIntersectionType(TsUnionOrIntersectionType::TsIntersectionType(intersection)) => self.node(
+                "TSIntersectionType",
+                intersection.span,
+                json!({ "types": intersection.types.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>() }),
+            ),
+            TsType::TsConditionalType(cond) => self.node(
+                "TSConditionalType",
+                cond.span,
+                json!({
+                    "checkType": self.ts_type(&cond.check_type),
+                    "extendsType": self.ts_type(&cond.extends_type),
+                    "trueType": self.ts_type(&cond.true_type),
+                    "falseType": self.ts_type(&cond.false_type),
+                }),
+            ),
+            TsType::TsInferType(infer) => self.node(
+                "TSInferType",
+                infer.span,
+                json!({ "typeParameter": self.ts_type_param(&infer.type_param) }),
+            ),
+            // ESTree has no parenthesized type node
+            TsType::TsParenthesizedType(paren) => self.ts_type(&paren.type_ann),
+            TsType::TsTypeOperator(op) => self.node(
+                "TSTypeOperator",
+                op.span,
+                json!({
+                    "operator": match op.op {
+                        TsTypeOperatorOp::KeyOf => "keyof",
+                        TsTypeOperatorOp::Unique => "unique",
+                        TsTypeOperatorOp::ReadOnly => "readonly",
+                    },
+                    "typeAnnotation": self.ts_type(&op.type_ann),
+                }),
+            ),
+            TsType::TsIndexedAccessType(access) => self.node(
+                "TSIndexedAccessType",
+                access.span,
+                json!({
+                    "objectType": self.ts_type(&access.obj_type),
+                    "indexType": self.ts_type(&access.index_type),
+                }),
+            ),
+            TsType::TsMappedType(mapped) => {
+                let modifier = |m: Option<TruePlusMinus>| match m {
+                    None => Value::Bool(false),
+                    Some(TruePlusMinus::True) => Value::Bool(true),
+                    Some(TruePlusMinus::Plus) => "+".into(),
+                    Some(TruePlusMinus::Minus) => "-".into(),
+                };
+                self.node(
+                    "TSMappedType",
+                    mapped.span,
+                    json!({
+                        "typeParameter": self.ts_type_param(&mapped.type_param),
+                        "nameType": self.opt(mapped.name_type.as_deref(), |t| self.ts_type(t)),
+                        "typeAnnotation": self.opt(mapped.type_ann.as_deref(), |t| self.ts_type(t)),
+                        "readonly": modifier(mapped.readonly),
+                        "optional": modifier(mapped.optional),
+                    }),
+                )
+            }
+            TsType::TsLitType(lit) => {
+                let literal = match &lit.lit {
+                    TsLit::Number(num) => self.lit(&Lit::Num(num.clone())),
+                    TsLit::Str(s) => self.str_lit(s),
+                    TsLit::Bool(b) => self.lit(&Lit::Bool(*b)),
+                    TsLit::BigInt(bigint) => self.lit(&Lit::BigInt(bigint.clone())),
+                    TsLit::Tpl(tpl) => {
+                        return self.node(
+                            "TSTemplateLiteralType",
+                            tpl.span,
+                            json!({
+                                "quasis": tpl.quasis.iter().map(|q| self.tpl_element(q)).collect::<Vec<_>>(),
+                                "types": tpl.types.iter().map(|t| self.ts_type(t)).collect::<Vec<_>>(),
+                            }),
+                        );
+                    }
+                };
+                self.node("TSLiteralType", lit.span, json!({ "literal": literal }))
+            }
+            TsType::TsTypePredicate(predicate) => self.node(
+                "TSTypePredicate",
+                predicate.span,
+                json!({
+                    "asserts": predicate.asserts,
+                    "parameterName": match &predicate.param_name {
+                        TsThisTypeOrIdent::TsThisType(this) => self.node("TSThisType", this.span, json!({})),
+                        TsThisTypeOrIdent::Ident(ident) => self.ident(ident),
+                    },
+                    "typeAnnotation": self.opt(predicate.type_ann.as_deref(), |t| self.ts_type_ann(t)),
+                }),
+            ),
+            TsType::TsImportType(import) => self.ts_import_type(import),
+        }
+    }
+
+    fn ts_import_type(&self, import: &TsImportType) -> Value {
+        self.node(
+            "TSImportType",
+            import.span,
+            json!({
+                "argument": self.node(
+                    "TSLiteralType",
+                    import.arg.span,
+                    json!({ "literal": self.str_lit(&import.arg) }),
+                ),
+                "qualifier": self.opt(import.qualifier.as_ref(), |q| self.ts_entity_name(q)),
+                "typeArguments": self.opt(import.type_args.as_deref(), |t| self.ts_type_param_instantiation(t)),
+            }),
+        )
+    }
+}
+
+fn accessibility(access: Accessibility) -> &'static str {
+    match access {
+        Accessibility::Public => "public",
+        Accessibility::Protected => "protected",
+        Accessibility::Private => "private",
+    }
+}
@@ -0,0 +1,11 @@
+// Example request processor served by the `/js` routes
+function escapeHtml(text) {
+    return text.replace(/[&<>"']/g, (c) => `&#${c.charCodeAt(0)};`);
+}
+
+function Process(request) {
+    output.headers = { "Content-Type": "text/html; charset=utf-8" };
+    output.body = `<html><body><h1>JavaScript Processing</h1>` +
+        `<p>Path: ${escapeHtml(request.path)}</p>` +
+        `<p>Host: ${escapeHtml(request.host)}</p></body></html>`;
+}
@@ -1,48 +1,54 @@
 use super::create_script_origin;
+use super::module_loader::resolve_module_callback;
 use super::JsHttpRequestProcessor;
+use crate::runtime_error::{JsError, RuntimeError};
 use ssr_rs::v8;
 
+/// Name the processor's script is compiled and reported under
+pub const SCRIPT_NAME: &str = "in.js";
+
 impl<'s, 'i> JsHttpRequestProcessor<'s, 'i>
 where
     's: 'i,
 {
-    pub fn execute_script(&mut self, script: v8::Local<'s, v8::String>) {
+    pub fn execute_script(&mut self, script: v8::Local<'s, v8::String>) -> Result<(), RuntimeError> {
         let scope = &mut v8::HandleScope::new(&mut *self.context_scope);
         let try_catch = &mut v8::TryCatch::new(scope);
 
-        let script =
-            v8::Script::compile(try_catch, script, None).expect("failed to compile script");
+        let origin = create_script_origin(try_catch, SCRIPT_NAME, false);
+        let script = v8::Script::compile(try_catch, script, Some(&origin))
+            .ok_or_else(|| RuntimeError::Compile(JsError::from_try_catch(try_catch)))?;
 
         if script.run(try_catch).is_none() {
-            let exception = try_catch.exception().unwrap();
-            let exception_string = exception.to_rust_string_lossy(try_catch);
-            panic!("{exception_string}");
+            return Err(RuntimeError::Evaluate(JsError::from_try_catch(try_catch)));
         }
+        Ok(())
     }
 
-    pub fn execute_module(&mut self, script: v8::Local<'s, v8::String>) {
+    pub fn execute_module(&mut self, script: v8::Local<'s, v8::String>) -> Result<(), RuntimeError> {
         let scope = &mut v8::HandleScope::new(&mut *self.context_scope);
         let try_catch = &mut v8::TryCatch::new(scope);
 
-        let origin = create_script_origin(try_catch, "file.js", true);
+        let origin = create_script_origin(try_catch, SCRIPT_NAME, true);
         let mut source = v8::script_compiler::Source::new(script, Some(&origin));
 
         let module = v8::script_compiler::compile_module(try_catch, &mut source)
-            .expect("failed to compile module");
+            .ok_or_else(|| RuntimeError::Compile(JsError::from_try_catch(try_catch)))?;
 
-        if module.instantiate_module(try_catch, |_, _, _, _| None).is_none() {
-            let exception = try_catch.exception().unwrap();
-            let exception_string = exception.to_rust_string_lossy(try_catch);
-            panic!("{exception_string}");
+        if module.instantiate_module(try_catch, resolve_module_callback).is_none() {
+            return Err(RuntimeError::Instantiate(JsError::from_try_catch(try_catch)));
         };
 
-        if module.evaluate(try_catch).is_none() {
-            let exception = try_catch.exception().unwrap();
-            let exception_string = exception.to_rust_string_lossy(try_catch);
-            panic!("{exception_string}");
+        let Some(result) = module.evaluate(try_catch) else {
+            return Err(RuntimeError::Evaluate(JsError::from_try_catch(try_catch)));
+        };
+        // a module throwing at the top level rejects the promise `evaluate` returns
+        if let Ok(promise) = v8::Local::<v8::Promise>::try_from(result)
+            && promise.state() == v8::PromiseState::Rejected
+        {
+            let reason = promise.result(try_catch);
+            return Err(RuntimeError::Evaluate(JsError::from_exception(try_catch, reason)));
         }
-
-        let ns = module.get_module_namespace();
-        let _ = ns.to_object(try_catch).unwrap();
+        Ok(())
     }
 }
@@ -0,0 +1,1699 @@
+use crate::js_parser::JavaScriptParser;
+use crate::react_compiler::snapshot_comments;
+use anyhow::Result;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use swc_common::comments::{CommentKind, SingleThreadedComments};
+use swc_common::sync::Lrc;
+use swc_common::{BytePos, DUMMY_SP, SourceMap, Span, Spanned};
+use swc_ecma_ast::*;
+use swc_ecma_codegen::text_writer::WriteJs;
+use swc_ecma_codegen::Emitter;
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+/// Which quote string literals are printed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuoteStyle {
+    Double,
+    Single,
+}
+
+/// Where a comma is added after the last item of a list that is broken over lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TrailingComma {
+    None,
+    /// Objects, arrays, imports and exports
+    Es5,
+    /// Also function parameters and call arguments
+    All,
+}
+
+/// Style the [`Formatter`] prints code in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Columns per indentation level
+    pub indent_width: usize,
+    pub use_tabs: bool,
+    pub quote_style: QuoteStyle,
+    /// Whether statements end with `;`
+    pub semicolons: bool,
+    pub trailing_comma: TrailingComma,
+    /// Column that lists are broken over lines to stay within
+    pub line_width: usize,
+}
+
+impl FormatOptions {
+    pub fn new() -> Self {
+        Self {
+            indent_width: 2,
+            use_tabs: false,
+            quote_style: QuoteStyle::Double,
+            semicolons: true,
+            trailing_comma: TrailingComma::All,
+            line_width: 80,
+        }
+    }
+
+    pub fn indent_width(mut self, indent_width: usize) -> Self {
+        self.indent_width = indent_width;
+        self
+    }
+
+    pub fn use_tabs(mut self, use_tabs: bool) -> Self {
+        self.use_tabs = use_tabs;
+        self
+    }
+
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    pub fn semicolons(mut self, semicolons: bool) -> Self {
+        self.semicolons = semicolons;
+        self
+    }
+
+    pub fn trailing_comma(mut self, trailing_comma: TrailingComma) -> Self {
+        self.trailing_comma = trailing_comma;
+        self
+    }
+
+    pub fn line_width(mut self, line_width: usize) -> Self {
+        self.line_width = line_width;
+        self
+    }
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pretty-prints parsed code in a consistent style, keeping comments and
+/// blank lines between statements
+///
+/// Code is printed by `swc_ecma_codegen`'s [`Emitter`] into a writer that
+/// records its tokens; comma-separated lists (arguments, parameters, arrays,
+/// objects, imports and exports) are then laid out to fit the line width, and
+/// JSX children written over several lines get a line each.
+pub struct Formatter {
+    options: FormatOptions,
+}
+
+impl Formatter {
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &FormatOptions {
+        &self.options
+    }
+
+    /// Formats a piece of code, picking the syntax from the file name
+    pub fn format_code(&self, code: &str, filename: Option<&str>) -> Result<String> {
+        let parser = JavaScriptParser::new();
+        let program = parser.parse_program(code, filename)?;
+        self.format_program(&parser, &program)
+    }
+
+    pub fn format_file<P: AsRef<Path>>(&self, path: P) -> Result<String> {
+        let code = std::fs::read_to_string(&path)?;
+        self.format_code(&code, Some(&path.as_ref().to_string_lossy()))
+    }
+
+    /// Formats a program parsed by `parser`, with the comments it collected
+    pub fn format_program(&self, parser: &JavaScriptParser, program: &Program) -> Result<String> {
+        let source_map = parser.source_map();
+
+        let mut program = program.clone();
+        program.visit_mut_with(&mut JsxWhitespace);
+        program.visit_mut_with(&mut Requote(self.options.quote_style));
+
+        let mut layout = LayoutCollector::new(source_map);
+        program.visit_with(&mut layout);
+        layout.comments(parser.comments());
+
+        // the emitter consumes the comments it prints
+        let comments = snapshot_comments(parser.comments());
+        let mut writer = TokenWriter::default();
+        {
+            let mut emitter = Emitter {
+                cfg: swc_ecma_codegen::Config::default(),
+                cm: source_map.clone(),
+                comments: Some(&comments),
+                wr: &mut writer,
+            };
+            emitter
+                .emit_program(&program)
+                .map_err(|e| anyhow::anyhow!("Emit error: {:?}", e))?;
+        }
+
+        let doc = Doc::build(&writer.events, &layout, &self.options);
+        Ok(doc.render(&self.options))
+    }
+
+    /// Formats a module parsed by `parser`
+    pub fn format_module(&self, parser: &JavaScriptParser, module: &Module) -> Result<String> {
+        self.format_program(parser, &Program::Module(module.clone()))
+    }
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self::new(FormatOptions::new())
+    }
+}
+
+/// Rewrites string literals with the preferred quote, unless that needs more escapes
+///
+/// JSX attributes keep their quotes.
+struct Requote(QuoteStyle);
+
+impl VisitMut for Requote {
+    fn visit_mut_str(&mut self, s: &mut Str) {
+        if let Some(raw) = &s.raw {
+            s.raw = Some(requote(raw, self.0).into());
+        }
+    }
+
+    fn visit_mut_jsx_attr_value(&mut self, value: &mut JSXAttrValue) {
+        if !matches!(value, JSXAttrValue::Lit(_)) {
+            value.visit_mut_children_with(self);
+        }
+    }
+}
+
+fn requote(raw: &str, style: QuoteStyle) -> String {
+    let Some(body) = raw
+        .get(1..raw.len().saturating_sub(1))
+        .filter(|_| raw.len() >= 2 && (raw.starts_with('"') || raw.starts_with('\'')))
+    else {
+        return raw.to_string();
+    };
+
+    let (preferred, alternate) = match style {
+        QuoteStyle::Double => ('"', '\''),
+        QuoteStyle::Single => ('\'', '"'),
+    };
+    let count = |quote: char| body.chars().filter(|&c| c == quote).count();
+    let quote = if count(preferred) > count(alternate) {
+        alternate
+    } else {
+        preferred
+    };
+
+    let mut out = String::with_capacity(raw.len());
+    out.push(quote);
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                // drop escapes of the quote that no longer needs one
+                Some(escaped) if (escaped == '"' || escaped == '\'') && escaped != quote => {
+                    out.push(escaped)
+                }
+                Some(escaped) => {
+                    out.push('\\');
+                    out.push(escaped);
+                }
+                None => out.push('\\'),
+            },
+            c if c == quote => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push(quote);
+    out
+}
+
+/// Rewrites the whitespace between JSX children the way JSX reads it, so the
+/// layout decides the indentation
+///
+/// Whitespace holding a line break means nothing in JSX; it becomes a text of
+/// only line breaks, [`is_jsx_break`], that the layout prints as a line of its
+/// own indentation. Text spanning lines is joined into one line, as JSX does.
+struct JsxWhitespace;
+
+impl VisitMut for JsxWhitespace {
+    fn visit_mut_jsx_element(&mut self, element: &mut JSXElement) {
+        element.visit_mut_children_with(self);
+        jsx_children(&mut element.children);
+    }
+
+    fn visit_mut_jsx_fragment(&mut self, fragment: &mut JSXFragment) {
+        fragment.visit_mut_children_with(self);
+        jsx_children(&mut fragment.children);
+    }
+}
+
+fn jsx_text(raw: &str) -> JSXElementChild {
+    JSXElementChild::JSXText(JSXText {
+        span: DUMMY_SP,
+        value: raw.into(),
+        raw: raw.into(),
+    })
+}
+
+/// Whether a JSX child is a line break left by [`JsxWhitespace`]
+fn is_jsx_break(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c == '\n')
+}
+
+fn jsx_children(children: &mut Vec<JSXElementChild>) {
+    let is_space = |c: char| matches!(c, ' ' | '\t' | '\r');
+    let mut laid_out = vec![];
+    for child in children.drain(..) {
+        let JSXElementChild::JSXText(text) = &child else {
+            laid_out.push(child);
+            continue;
+        };
+        if !text.raw.contains('\n') {
+            laid_out.push(child);
+            continue;
+        }
+
+        let lines: Vec<&str> = text.raw.split('\n').collect();
+        let blank = |line: &&str| line.trim_matches(is_space).is_empty();
+        let Some(first) = lines.iter().position(|line| !blank(line)) else {
+            laid_out.push(jsx_text(if lines.len() > 2 { "\n\n" } else { "\n" }));
+            continue;
+        };
+        let last = lines.iter().rposition(|line| !blank(line)).unwrap_or(first);
+
+        // the first line keeps its leading spaces and the last its trailing ones
+        let mut words = vec![];
+        for (index, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+            let mut line = *line;
+            if index > 0 {
+                line = line.trim_start_matches(is_space);
+            }
+            if index + 1 < lines.len() {
+                line = line.trim_end_matches(is_space);
+            }
+            if !line.is_empty() {
+                words.push(line);
+            }
+        }
+        if first > 0 {
+            laid_out.push(jsx_text(if first > 1 { "\n\n" } else { "\n" }));
+        }
+        laid_out.push(jsx_text(&words.join(" ")));
+        let after = lines.len() - 1 - last;
+        if after > 0 {
+            laid_out.push(jsx_text(if after > 1 { "\n\n" } else { "\n" }));
+        }
+    }
+
+    // children moved onto their own lines keep the spaces next to the tags
+    if has_jsx_break(&laid_out) {
+        let space = || {
+            JSXElementChild::JSXExprContainer(JSXExprContainer {
+                span: DUMMY_SP,
+                expr: JSXExpr::Expr(Box::new(Expr::Lit(Lit::Str(Str {
+                    span: DUMMY_SP,
+                    value: " ".into(),
+                    raw: Some("\" \"".into()),
+                })))),
+            })
+        };
+        if let Some(JSXElementChild::JSXText(text)) = laid_out.first_mut()
+            && text.raw.starts_with(is_space)
+        {
+            text.raw = text.raw.trim_start_matches(is_space).into();
+            laid_out.insert(0, space());
+        }
+        if let Some(JSXElementChild::JSXText(text)) = laid_out.last_mut()
+            && text.raw.ends_with(is_space)
+        {
+            text.raw = text.raw.trim_end_matches(is_space).into();
+            laid_out.push(space());
+        }
+    }
+    laid_out.retain(|child| {
+        !matches!(child, JSXElementChild::JSXText(text) if text.raw.is_empty())
+    });
+    *children = laid_out;
+}
+
+/// How a bracketed list may be laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ListInfo {
+    /// Whether a flat list has spaces inside its brackets, as in `{ a }`
+    spaced: bool,
+    /// Lowest [`TrailingComma`] setting that adds a trailing comma, if any does
+    trailing: Option<TrailingComma>,
+    /// Whether the list is broken even when it fits, like objects written over
+    /// several lines
+    force_break: bool,
+    /// Whether the items are JSX children, separated by [`is_jsx_break`] texts
+    jsx: bool,
+}
+
+/// Source facts the layout needs: where lists start and where blank lines were
+struct LayoutCollector<'a> {
+    source_map: &'a SourceMap,
+    /// Lists keyed by the position of the node printed just before the bracket
+    by_open: HashMap<BytePos, ListInfo>,
+    /// Lists keyed by the position of their first item
+    by_first: HashMap<BytePos, ListInfo>,
+    /// Positions that were preceded by a blank line
+    blank_before: BTreeSet<BytePos>,
+    /// Block comments that ended their line
+    own_line_comments: HashSet<BytePos>,
+    /// Opening braces of statement blocks
+    block_starts: HashSet<BytePos>,
+    /// Names of the opening and closing tags of JSX elements whose children
+    /// are laid out over lines
+    jsx_opens: HashSet<BytePos>,
+    jsx_closes: HashSet<BytePos>,
+    /// Whether each JSX fragment, in source order, is laid out over lines;
+    /// fragments have no name to key them by
+    jsx_fragments: Vec<bool>,
+    /// Parentheses around JSX laid out over lines
+    jsx_parens: HashSet<BytePos>,
+}
+
+impl<'a> LayoutCollector<'a> {
+    fn new(source_map: &'a SourceMap) -> Self {
+        Self {
+            source_map,
+            by_open: HashMap::new(),
+            by_first: HashMap::new(),
+            blank_before: BTreeSet::new(),
+            own_line_comments: HashSet::new(),
+            block_starts: HashSet::new(),
+            jsx_opens: HashSet::new(),
+            jsx_closes: HashSet::new(),
+            jsx_fragments: vec![],
+            jsx_parens: HashSet::new(),
+        }
+    }
+
+    /// Records the block comments that are followed by a line break, such as doc blocks
+    fn comments(&mut self, comments: &SingleThreadedComments) {
+        let (leading, trailing) = comments.borrow_all();
+        for comment in leading.values().chain(trailing.values()).flatten() {
+            if comment.kind != CommentKind::Block {
+                continue;
+            }
+            let file = self.source_map.lookup_byte_offset(comment.span.hi);
+            let rest = &file.sf.src[file.pos.0 as usize..];
+            let line = rest.split('\n').next().unwrap_or_default();
+            if line.trim().is_empty() && line.len() < rest.len() {
+                self.own_line_comments.insert(comment.span.lo);
+            }
+        }
+    }
+
+    fn snippet(&self, lo: BytePos, hi: BytePos) -> String {
+        if lo.is_dummy() || hi.is_dummy() || hi < lo {
+            return String::new();
+        }
+        let start = self.source_map.lookup_byte_offset(lo);
+        let end = self.source_map.lookup_byte_offset(hi);
+        if !Lrc::ptr_eq(&start.sf, &end.sf) {
+            return String::new();
+        }
+        start.sf.src[start.pos.0 as usize..end.pos.0 as usize].to_string()
+    }
+
+    /// Records blank lines between consecutive items of a statement-like list
+    ///
+    /// A blank line before a comment is kept before the comment.
+    fn siblings(&mut self, spans: impl Iterator<Item = Span>) {
+        let mut previous: Option<Span> = None;
+        for span in spans {
+            if let Some(previous) = previous {
+                let blanks = self.blank_lines_between(previous.hi, span.lo);
+                self.blank_before.extend(blanks);
+            }
+            previous = Some(span);
+        }
+    }
+
+    /// Positions of the comments or item in a gap that follow a blank line
+    fn blank_lines_between(&self, hi: BytePos, lo: BytePos) -> Vec<BytePos> {
+        let gap = self.snippet(hi, lo);
+        let mut blanks = vec![];
+        let mut offset = 0;
+        let mut seen_blank = false;
+        for (index, line) in gap.split_inclusive('\n').enumerate() {
+            let trimmed = line.trim_start();
+            // the first line continues the previous item, e.g. its trailing comment
+            if index > 0 {
+                if trimmed.is_empty() && line.ends_with('\n') {
+                    seen_blank = true;
+                } else if !trimmed.is_empty() && std::mem::take(&mut seen_blank) {
+                    let start = offset + (line.len() - trimmed.len());
+                    blanks.push(hi + BytePos(start as u32));
+                }
+            }
+            offset += line.len();
+        }
+        if seen_blank {
+            blanks.push(lo);
+        }
+        blanks
+    }
+
+    fn list(&mut self, first: Option<BytePos>, info: ListInfo) {
+        if let Some(first) = first.filter(|pos| !pos.is_dummy()) {
+            self.by_first.insert(first, info);
+        }
+    }
+}
+
+const OBJECT: ListInfo = ListInfo {
+    spaced: true,
+    trailing: Some(TrailingComma::Es5),
+    force_break: false,
+    jsx: false,
+};
+
+const ARRAY: ListInfo = ListInfo {
+    spaced: false,
+    trailing: Some(TrailingComma::Es5),
+    force_break: false,
+    jsx: false,
+};
+
+const ARGUMENTS: ListInfo = ListInfo {
+    spaced: false,
+    trailing: Some(TrailingComma::All),
+    force_break: false,
+    jsx: false,
+};
+
+const JSX_CHILDREN: ListInfo = ListInfo {
+    spaced: false,
+    trailing: None,
+    force_break: true,
+    jsx: true,
+};
+
+/// Parentheses around JSX laid out over lines, which hold it on lines of its own
+const JSX_PARENS: ListInfo = ListInfo {
+    jsx: false,
+    ..JSX_CHILDREN
+};
+
+/// Whether JSX children hold a line break, so they are laid out over lines
+fn has_jsx_break(children: &[JSXElementChild]) -> bool {
+    children
+        .iter()
+        .any(|child| matches!(child, JSXElementChild::JSXText(text) if is_jsx_break(&text.raw)))
+}
+
+impl Visit for LayoutCollector<'_> {
+    fn visit_module(&mut self, module: &Module) {
+        self.siblings(module.body.iter().map(Spanned::span));
+        module.visit_children_with(self);
+    }
+
+    fn visit_script(&mut self, script: &Script) {
+        self.siblings(script.body.iter().map(Spanned::span));
+        script.visit_children_with(self);
+    }
+
+    fn visit_block_stmt(&mut self, block: &BlockStmt) {
+        self.block_starts.insert(block.span.lo);
+        self.siblings(block.stmts.iter().map(Spanned::span));
+        block.visit_children_with(self);
+    }
+
+    fn visit_switch_case(&mut self, case: &SwitchCase) {
+        self.siblings(case.cons.iter().map(Spanned::span));
+        case.visit_children_with(self);
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.siblings(class.body.iter().map(Spanned::span));
+        class.visit_children_with(self);
+    }
+
+    fn visit_object_lit(&mut self, object: &ObjectLit) {
+        let force_break = object
+            .props
+            .first()
+            .is_some_and(|prop| self.snippet(object.span.lo, prop.span().lo).contains('\n'));
+        self.by_open.insert(
+            object.span.lo,
+            ListInfo {
+                force_break,
+                ..OBJECT
+            },
+        );
+        self.siblings(object.props.iter().map(Spanned::span));
+        object.visit_children_with(self);
+    }
+
+    fn visit_array_lit(&mut self, array: &ArrayLit) {
+        // a trailing hole already ends with a comma that must stay
+        let trailing = match array.elems.last() {
+            Some(None) => None,
+            _ => ARRAY.trailing,
+        };
+        self.by_open
+            .insert(array.span.lo, ListInfo { trailing, ..ARRAY });
+        array.visit_children_with(self);
+    }
+
+    fn visit_object_pat(&mut self, pat: &ObjectPat) {
+        let rest = matches!(pat.props.last(), Some(ObjectPatProp::Rest(_)));
+        self.by_open.insert(
+            pat.span.lo,
+            ListInfo {
+                trailing: if rest { None } else { OBJECT.trailing },
+                ..OBJECT
+            },
+        );
+        pat.visit_children_with(self);
+    }
+
+    fn visit_array_pat(&mut self, pat: &ArrayPat) {
+        let rest = matches!(pat.elems.last(), Some(Some(Pat::Rest(_))) | Some(None));
+        self.by_open.insert(
+            pat.span.lo,
+            ListInfo {
+                trailing: if rest { None } else { ARRAY.trailing },
+                ..ARRAY
+            },
+        );
+        pat.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        self.list(call.args.first().map(|arg| arg.span().lo), ARGUMENTS);
+        call.visit_children_with(self);
+    }
+
+    fn visit_new_expr(&mut self, new: &NewExpr) {
+        if let Some(args) = &new.args {
+            self.list(args.first().map(|arg| arg.span().lo), ARGUMENTS);
+        }
+        new.visit_children_with(self);
+    }
+
+    fn visit_opt_call(&mut self, call: &OptCall) {
+        self.list(call.args.first().map(|arg| arg.span().lo), ARGUMENTS);
+        call.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        let rest = matches!(function.params.last(), Some(Param { pat: Pat::Rest(_), .. }));
+        self.list(
+            function.params.first().map(|param| param.pat.span().lo),
+            ListInfo {
+                trailing: if rest { None } else { ARGUMENTS.trailing },
+                ..ARGUMENTS
+            },
+        );
+        function.visit_children_with(self);
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        let rest = matches!(arrow.params.last(), Some(Pat::Rest(_)));
+        self.list(
+            arrow.params.first().map(|param| param.span().lo),
+            ListInfo {
+                trailing: if rest { None } else { ARGUMENTS.trailing },
+                ..ARGUMENTS
+            },
+        );
+        arrow.visit_children_with(self);
+    }
+
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        let first = import
+            .specifiers
+            .iter()
+            .find(|specifier| matches!(specifier, ImportSpecifier::Named(_)));
+        self.list(first.map(|specifier| specifier.span().lo), OBJECT);
+        import.visit_children_with(self);
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        self.list(export.specifiers.first().map(|specifier| specifier.span().lo), OBJECT);
+        export.visit_children_with(self);
+    }
+
+    fn visit_jsx_element(&mut self, element: &JSXElement) {
+        if has_jsx_break(&element.children)
+            && let Some(closing) = &element.closing
+        {
+            self.jsx_opens.insert(element.opening.name.span().lo);
+            self.jsx_closes.insert(closing.name.span().lo);
+        }
+        element.visit_children_with(self);
+    }
+
+    fn visit_jsx_fragment(&mut self, fragment: &JSXFragment) {
+        self.jsx_fragments.push(has_jsx_break(&fragment.children));
+        fragment.visit_children_with(self);
+    }
+
+    fn visit_paren_expr(&mut self, paren: &ParenExpr) {
+        let laid_out = match &*paren.expr {
+            Expr::JSXElement(element) => has_jsx_break(&element.children),
+            Expr::JSXFragment(fragment) => has_jsx_break(&fragment.children),
+            _ => false,
+        };
+        if laid_out {
+            self.jsx_parens.insert(paren.span.lo);
+        }
+        paren.visit_children_with(self);
+    }
+}
+
+/// What the emitter wrote, in order
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Text(String),
+    Punct(&'static str),
+    Comment(String),
+    Space,
+    Newline,
+    Semi,
+    Indent,
+    Dedent,
+    Srcmap(BytePos),
+}
+
+/// A [`WriteJs`] that records the emitter's output as tokens
+#[derive(Default)]
+struct TokenWriter {
+    events: Vec<Event>,
+}
+
+impl TokenWriter {
+    fn text(&mut self, s: &str) -> std::io::Result<()> {
+        if !s.is_empty() {
+            self.events.push(Event::Text(s.to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl WriteJs for TokenWriter {
+    fn increase_indent(&mut self) -> std::io::Result<()> {
+        self.events.push(Event::Indent);
+        Ok(())
+    }
+
+    fn decrease_indent(&mut self) -> std::io::Result<()> {
+        self.events.push(Event::Dedent);
+        Ok(())
+    }
+
+    fn write_semi(&mut self, _: Option<Span>) -> std::io::Result<()> {
+        self.events.push(Event::Semi);
+        Ok(())
+    }
+
+    fn write_space(&mut self) -> std::io::Result<()> {
+        self.events.push(Event::Space);
+        Ok(())
+    }
+
+    fn write_keyword(&mut self, _: Option<Span>, s: &'static str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_operator(&mut self, _: Option<Span>, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_param(&mut self, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_property(&mut self, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_line(&mut self) -> std::io::Result<()> {
+        self.events.push(Event::Newline);
+        Ok(())
+    }
+
+    fn write_lit(&mut self, _: Span, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_comment(&mut self, s: &str) -> std::io::Result<()> {
+        self.events.push(Event::Comment(s.to_string()));
+        Ok(())
+    }
+
+    fn write_str_lit(&mut self, _: Span, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_str(&mut self, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_symbol(&mut self, _: Span, s: &str) -> std::io::Result<()> {
+        self.text(s)
+    }
+
+    fn write_punct(&mut self, _: Option<Span>, s: &'static str) -> std::io::Result<()> {
+        self.events.push(Event::Punct(s));
+        Ok(())
+    }
+
+    fn care_about_srcmap(&self) -> bool {
+        true
+    }
+
+    fn add_srcmap(&mut self, pos: BytePos) -> std::io::Result<()> {
+        self.events.push(Event::Srcmap(pos));
+        Ok(())
+    }
+
+    fn commit_pending_semi(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A bracket pair of the output
+#[derive(Debug, Clone, Default)]
+struct Group {
+    /// Set for lists that may be broken over lines
+    list: Option<ListInfo>,
+    force_break: bool,
+    empty: bool,
+}
+
+/// The output laid out as text, line breaks and bracket groups
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Text(String),
+    Comment(String),
+    /// Separator inside a list: nothing (`soft`) or a space when flat, a line break otherwise
+    Line { group: usize, soft: bool, blank: bool },
+    /// Separator before the closing bracket of a list
+    CloseLine { group: usize },
+    HardLine { blank: bool, asi: bool },
+    Open(usize),
+    Close(usize),
+    Indent,
+    Dedent,
+}
+
+/// Characters that would continue the previous line when it has no semicolon
+const ASI_HAZARDS: &[char] = &['(', '[', '`', '+', '-', '/'];
+
+struct Doc {
+    items: Vec<Item>,
+    groups: Vec<Group>,
+}
+
+fn is_opener(punct: &str) -> bool {
+    matches!(punct, "(" | "[" | "{" | "${")
+}
+
+fn is_closer(punct: &str) -> bool {
+    matches!(punct, ")" | "]" | "}")
+}
+
+/// Keywords printed with a space before a following `(`
+const KEYWORDS_BEFORE_PAREN: &[&str] = &["if", "for", "while", "switch", "catch", "with", "function"];
+
+/// Keywords printed with a space after a preceding `}`
+const KEYWORDS_AFTER_BRACE: &[&str] = &["while", "else", "catch", "finally"];
+
+/// Words that keep their space before an argument or parameter list
+const KEYWORDS: &[&str] = &[
+    "async", "function", "return", "typeof", "await", "yield", "new", "in", "of", "case", "void",
+    "delete", "instanceof", "extends", "else", "do", "export", "default", "const", "let", "var",
+    "throw",
+];
+
+fn is_word(text: &str) -> bool {
+    text.ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Turns the emitter's events into a [`Doc`]
+struct Builder<'a> {
+    events: &'a [Event],
+    layout: &'a LayoutCollector<'a>,
+    options: &'a FormatOptions,
+    doc: Doc,
+    open_at: HashMap<usize, usize>,
+    close_at: HashMap<usize, usize>,
+    /// Blank lines not printed yet, in source order
+    blank_before: BTreeSet<BytePos>,
+    /// Groups enclosing the current event
+    stack: Vec<usize>,
+    /// Set after a comma that separates list items
+    separator: bool,
+    /// Set when the next line break must be kept, e.g. after a line comment
+    hard_line: bool,
+    /// Set when a semicolon was dropped before the next line break
+    asi: bool,
+    /// Set when the next text needs a space before it, e.g. after `=>`
+    space: bool,
+    last_srcmap: Option<BytePos>,
+    own_line_comment: bool,
+    line_after_comment: bool,
+    /// Depth of the `switch` whose body brace comes next
+    switch_depth: Option<usize>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(events: &'a [Event], layout: &'a LayoutCollector<'a>, options: &'a FormatOptions) -> Self {
+        let (groups, open_at, close_at) = Doc::match_brackets(events, layout);
+        Self {
+            events,
+            layout,
+            options,
+            doc: Doc {
+                items: vec![],
+                groups,
+            },
+            open_at,
+            close_at,
+            blank_before: layout.blank_before.clone(),
+            stack: vec![],
+            separator: false,
+            hard_line: false,
+            asi: false,
+            space: false,
+            last_srcmap: None,
+            own_line_comment: false,
+            line_after_comment: false,
+            switch_depth: None,
+        }
+    }
+
+    fn build(mut self) -> Doc {
+        for index in 0..self.events.len() {
+            self.event(index);
+        }
+        self.break_objects_with_hard_lines();
+        self.doc
+    }
+
+    /// Breaks objects, patterns and import lists that hold a line break, e.g. a method body
+    fn break_objects_with_hard_lines(&mut self) {
+        let mut open: Vec<usize> = vec![];
+        for item in &self.doc.items {
+            match item {
+                Item::Open(group) => open.push(*group),
+                Item::Close(_) => {
+                    open.pop();
+                }
+                Item::HardLine { .. } => {
+                    for &group in &open {
+                        if self.doc.groups[group].list.is_some_and(|info| info.spaced) {
+                            self.doc.groups[group].force_break = true;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The innermost group, if it is a list
+    fn list(&self) -> Option<usize> {
+        self.stack
+            .last()
+            .copied()
+            .filter(|&group| self.doc.groups[group].list.is_some())
+    }
+
+    /// The innermost group, if it holds JSX children
+    fn jsx_list(&self) -> Option<usize> {
+        self.list()
+            .filter(|&group| self.doc.groups[group].list.is_some_and(|info| info.jsx))
+    }
+
+    /// Index of the next event that prints something
+    fn next_significant(&self, from: usize) -> Option<usize> {
+        self.events[from..]
+            .iter()
+            .position(|event| {
+                !matches!(
+                    event,
+                    Event::Space | Event::Newline | Event::Srcmap(_) | Event::Indent | Event::Dedent
+                )
+            })
+            .map(|offset| from + offset)
+    }
+
+    fn closes_list(&self, at: Option<usize>) -> bool {
+        let list = self.list();
+        list.is_some() && at.and_then(|at| self.close_at.get(&at)).copied() == list
+    }
+
+    /// The text printed last on the current line
+    fn previous_text(&self) -> Option<&str> {
+        for item in self.doc.items.iter().rev() {
+            match item {
+                Item::Text(text) => return Some(text),
+                Item::Open(_) | Item::Close(_) | Item::Indent | Item::Dedent => {}
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let previous = self.previous_text();
+        let after_brace = previous == Some("}");
+        let needs_space = previous.is_some_and(|previous| !previous.ends_with(' '));
+        let space = std::mem::take(&mut self.space)
+            || text == "=>"
+            || (KEYWORDS_AFTER_BRACE.contains(&text) && after_brace);
+        if space && needs_space {
+            self.doc.items.push(Item::Text(" ".into()));
+        }
+        self.doc.items.push(Item::Text(text.to_string()));
+        if text == "=>" {
+            self.space = true;
+        }
+        if text == "switch" {
+            self.switch_depth = Some(self.stack.len());
+        }
+        self.separator = false;
+    }
+
+    fn push_line(&mut self, group: usize, soft: bool) {
+        self.doc.items.push(Item::Line {
+            group,
+            soft,
+            blank: false,
+        });
+        self.separator = false;
+    }
+
+    fn push_hard_line(&mut self) {
+        self.hard_line = false;
+        self.separator = false;
+        self.space = false;
+        let previous = self
+            .doc
+            .items
+            .iter()
+            .rev()
+            .find(|item| !matches!(item, Item::Indent | Item::Dedent));
+        if !matches!(previous, Some(Item::HardLine { .. }) | None) {
+            self.doc.items.push(Item::HardLine {
+                blank: false,
+                asi: std::mem::take(&mut self.asi),
+            });
+        }
+    }
+
+    fn open(&mut self, index: usize, punct: &str) {
+        let group = self.open_at[&index];
+        let previous = self.previous_text();
+        let spaced = match punct {
+            // blocks of statements
+            "{" => {
+                let block = matches!(
+                    index.checked_sub(1).map(|at| &self.events[at]),
+                    Some(Event::Srcmap(pos)) if self.layout.block_starts.contains(pos)
+                );
+                let switch = self.switch_depth == Some(self.stack.len());
+                if switch {
+                    self.switch_depth = None;
+                }
+                block || switch
+            }
+            "(" => previous.is_some_and(|previous| KEYWORDS_BEFORE_PAREN.contains(&previous)),
+            _ => false,
+        };
+        if spaced {
+            self.space = true;
+        }
+        self.push_text(punct);
+
+        self.doc.items.push(Item::Open(group));
+        if let Some(info) = self.doc.groups[group].list
+            && !self.doc.groups[group].empty
+        {
+            self.push_line(group, !info.spaced);
+        }
+        self.stack.push(group);
+    }
+
+    fn close(&mut self, index: usize, punct: &str) {
+        let group = self.close_at[&index];
+        if self.doc.groups[group].list.is_some() && !self.doc.groups[group].empty {
+            self.doc.items.push(Item::CloseLine { group });
+        }
+        self.doc.items.push(Item::Close(group));
+        self.space = false;
+        self.push_text(punct);
+        self.stack.pop();
+    }
+
+    fn event(&mut self, index: usize) {
+        let list = self.list();
+        match &self.events[index] {
+            Event::Punct(punct) if self.open_at.contains_key(&index) => self.open(index, punct),
+            Event::Punct(punct) if self.close_at.contains_key(&index) => self.close(index, punct),
+            Event::Punct(",") if list.is_some() => {
+                if self.closes_list(self.next_significant(index + 1)) {
+                    // a trailing comma after a hole is part of the array
+                    let hole = matches!(self.doc.items.last(), Some(Item::Text(text)) if text == ",")
+                        || matches!(self.doc.items.last(), Some(Item::Line { .. }));
+                    if !hole {
+                        return;
+                    }
+                }
+                self.push_text(",");
+                self.separator = true;
+            }
+            Event::Punct("/") if matches!(self.events.get(index + 1), Some(Event::Punct(">"))) => {
+                // `<br />`
+                self.space = true;
+                self.push_text("/");
+            }
+            Event::Punct(punct) => self.push_text(punct),
+            Event::Text(text) if is_jsx_break(text) && self.jsx_list().is_some() => {
+                self.jsx_break(index, text.len() > 1);
+            }
+            Event::Text(text) => self.push_text(text),
+            Event::Comment(text) => {
+                match text.as_str() {
+                    "/*" => {
+                        self.own_line_comment = self
+                            .last_srcmap
+                            .is_some_and(|pos| self.layout.own_line_comments.contains(&pos))
+                    }
+                    "*/" if self.own_line_comment => self.line_after_comment = true,
+                    _ => {}
+                }
+                if text == "//" || self.line_after_comment {
+                    self.hard_line = true;
+                    if let Some(group) = list {
+                        self.doc.groups[group].force_break = true;
+                    }
+                }
+                self.doc.items.push(Item::Comment(text.clone()));
+            }
+            Event::Space if self.line_after_comment => {
+                self.line_after_comment = false;
+                self.push_hard_line();
+            }
+            Event::Space => self.space(index, list),
+            Event::Newline => {
+                if self.hard_line || list.is_none() {
+                    self.push_hard_line();
+                } else if let Some(group) = list
+                    && self.separator
+                {
+                    self.push_line(group, false);
+                }
+            }
+            Event::Semi => self.semi(index),
+            Event::Indent | Event::Dedent if list.is_some() => {}
+            Event::Indent => self.doc.items.push(Item::Indent),
+            Event::Dedent => self.doc.items.push(Item::Dedent),
+            Event::Srcmap(pos) => {
+                self.last_srcmap = Some(*pos);
+                // some nodes, like TS `interface` and `type` declarations, map
+                // no position at their start, so every blank line passed counts
+                let later = self.blank_before.split_off(&(*pos + BytePos(1)));
+                let passed = std::mem::replace(&mut self.blank_before, later);
+                if !passed.is_empty()
+                    && let Some(Item::HardLine { blank, .. } | Item::Line { blank, .. }) = self
+                        .doc
+                        .items
+                        .iter_mut()
+                        .rev()
+                        .find(|item| matches!(item, Item::HardLine { .. } | Item::Line { .. }))
+                {
+                    *blank = true;
+                }
+            }
+        }
+    }
+
+    /// A line between JSX children, unless it would follow the opening tag's
+    /// line or precede the closing tag's
+    fn jsx_break(&mut self, index: usize, blank: bool) {
+        let Some(group) = self.jsx_list() else {
+            return;
+        };
+        let after_line = matches!(
+            self.doc.items.last(),
+            Some(Item::Line { group: g, .. }) if *g == group
+        );
+        if after_line || self.closes_list(self.next_significant(index + 1)) {
+            return;
+        }
+        self.doc.items.push(Item::Line {
+            group,
+            soft: true,
+            blank,
+        });
+        self.separator = false;
+    }
+
+    fn space(&mut self, index: usize, list: Option<usize>) {
+        if let Some(group) = list {
+            let after_line = matches!(
+                self.doc.items.last(),
+                Some(Item::Line { group: g, .. }) if *g == group
+            );
+            if after_line || self.closes_list(self.next_significant(index + 1)) {
+                return;
+            }
+            if self.separator {
+                self.push_line(group, false);
+                return;
+            }
+        }
+
+        // method names sit right next to their parameters, as calls do
+        let next = self.events[index + 1..]
+            .iter()
+            .position(|event| !matches!(event, Event::Srcmap(_)))
+            .map(|offset| index + 1 + offset);
+        let before_list = next.is_some_and(|next| self.events[next] == Event::Punct("("));
+        let after_name = self
+            .previous_text()
+            .is_some_and(|previous| is_word(previous) && !KEYWORDS.contains(&previous));
+        if before_list && after_name {
+            return;
+        }
+
+        self.space = false;
+        self.doc.items.push(Item::Text(" ".into()));
+    }
+
+    /// Where the statement before a semicolon ends, when the emitter already
+    /// printed its trailing line comment and the line break after it
+    fn before_trailing_comment(&self) -> Option<usize> {
+        let mut items = self
+            .doc
+            .items
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, item)| !matches!(item, Item::Indent | Item::Dedent));
+        if !matches!(items.next(), Some((_, Item::HardLine { .. }))) {
+            return None;
+        }
+        let mut comment = false;
+        for (at, item) in items {
+            match item {
+                Item::Comment(_) => comment = true,
+                _ => return comment.then_some(at + 1),
+            }
+        }
+        None
+    }
+
+    fn semi(&mut self, index: usize) {
+        if let Some(at) = self.before_trailing_comment() {
+            if self.options.semicolons {
+                self.doc.items.insert(at, Item::Text(";".into()));
+            } else if let Some(Item::HardLine { asi, .. }) = self
+                .doc
+                .items
+                .iter_mut()
+                .rev()
+                .find(|item| matches!(item, Item::HardLine { .. }))
+            {
+                *asi = true;
+            }
+            return;
+        }
+        if self.options.semicolons {
+            self.push_text(";");
+            return;
+        }
+        let next = self.events[index + 1..]
+            .iter()
+            .enumerate()
+            .find(|(_, event)| {
+                !matches!(
+                    event,
+                    Event::Srcmap(_) | Event::Space | Event::Comment(_) | Event::Indent | Event::Dedent
+                )
+            });
+        let ends_line = match next {
+            None | Some((_, Event::Newline)) => true,
+            Some((offset, Event::Punct("}"))) => self.close_at.contains_key(&(index + 1 + offset)),
+            _ => false,
+        };
+        if ends_line {
+            self.asi = true;
+        } else {
+            self.push_text(";");
+        }
+    }
+}
+
+impl Doc {
+    fn build(events: &[Event], layout: &LayoutCollector, options: &FormatOptions) -> Self {
+        Builder::new(events, layout, options).build()
+    }
+
+    /// Pairs up brackets and decides which of them delimit lists
+    #[allow(clippy::type_complexity)]
+    fn match_brackets(
+        events: &[Event],
+        layout: &LayoutCollector,
+    ) -> (Vec<Group>, HashMap<usize, usize>, HashMap<usize, usize>) {
+        let mut groups = vec![];
+        let mut open_at = HashMap::new();
+        let mut close_at = HashMap::new();
+        let mut stack: Vec<(usize, usize)> = vec![];
+        // laid out JSX tags whose `>` is still to come: the depth of the stack
+        // at their `<`, and how many type argument `<`s are open
+        let mut tags: Vec<(usize, usize)> = vec![];
+        let mut fragments = layout.jsx_fragments.iter();
+        let mut open_fragments: Vec<bool> = vec![];
+        let srcmap_after = |index: usize| match events.get(index + 1) {
+            Some(Event::Srcmap(pos)) => Some(*pos),
+            _ => None,
+        };
+
+        for (index, event) in events.iter().enumerate() {
+            let Event::Punct(punct) = event else {
+                continue;
+            };
+            let jsx_opener = match *punct {
+                "<" if srcmap_after(index).is_some_and(|pos| layout.jsx_opens.contains(&pos)) => {
+                    tags.push((stack.len(), 0));
+                    false
+                }
+                "<" => {
+                    if let Some((depth, angles)) = tags.last_mut()
+                        && *depth == stack.len()
+                    {
+                        *angles += 1;
+                    }
+                    false
+                }
+                ">" => match tags.last_mut() {
+                    Some((depth, angles)) if *depth == stack.len() && *angles > 0 => {
+                        *angles -= 1;
+                        false
+                    }
+                    Some((depth, _)) if *depth == stack.len() => {
+                        tags.pop();
+                        true
+                    }
+                    _ => false,
+                },
+                "<>" => {
+                    let laid_out = fragments.next() == Some(&true);
+                    open_fragments.push(laid_out);
+                    laid_out
+                }
+                _ => false,
+            };
+            let jsx_closer = match *punct {
+                "</" => srcmap_after(index).is_some_and(|pos| layout.jsx_closes.contains(&pos)),
+                "</>" => open_fragments.pop() == Some(true),
+                _ => false,
+            };
+            if jsx_opener {
+                open_at.insert(index, groups.len());
+                stack.push((groups.len(), index));
+                groups.push(Group {
+                    list: Some(JSX_CHILDREN),
+                    force_break: true,
+                    empty: false,
+                });
+                continue;
+            }
+            if jsx_closer && let Some((group, _)) = stack.pop() {
+                close_at.insert(index, group);
+                continue;
+            }
+
+            if is_opener(punct) {
+                let group = groups.len();
+                let mut info = None;
+                let jsx_parens = || {
+                    events[..index]
+                        .iter()
+                        .rev()
+                        .map_while(|event| match event {
+                            Event::Srcmap(pos) => Some(pos),
+                            _ => None,
+                        })
+                        .any(|pos| layout.jsx_parens.contains(pos))
+                };
+                if *punct == "(" && jsx_parens() {
+                    info = Some(JSX_PARENS);
+                } else if *punct != "${" {
+                    if *punct != "(" {
+                        info = events[..index]
+                            .iter()
+                            .rev()
+                            .map_while(|event| match event {
+                                Event::Srcmap(pos) => Some(pos),
+                                _ => None,
+                            })
+                            .find_map(|pos| layout.by_open.get(pos).copied());
+                    }
+                    if info.is_none() {
+                        info = events[index + 1..]
+                            .iter()
+                            .take_while(|event| {
+                                !matches!(event, Event::Text(_) | Event::Punct(_) | Event::Semi)
+                            })
+                            .find_map(|event| match event {
+                                Event::Srcmap(pos) => layout.by_first.get(pos).copied(),
+                                _ => None,
+                            });
+                    }
+                }
+                groups.push(Group {
+                    list: info,
+                    force_break: info.is_some_and(|info| info.force_break),
+                    empty: false,
+                });
+                open_at.insert(index, group);
+                stack.push((group, index));
+            } else if is_closer(punct)
+                && let Some((group, opened)) = stack.pop()
+            {
+                groups[group].empty = !events[opened + 1..index].iter().any(|event| {
+                    matches!(event, Event::Text(_) | Event::Punct(_) | Event::Comment(_))
+                });
+                close_at.insert(index, group);
+            }
+        }
+
+        (groups, open_at, close_at)
+    }
+
+    fn render(&self, options: &FormatOptions) -> String {
+        let mut printer = Printer {
+            doc: self,
+            options,
+            out: String::new(),
+            column: 0,
+            indent: 0,
+            line_start: true,
+            stack: vec![],
+            asi: false,
+        };
+        for (index, item) in self.items.iter().enumerate() {
+            printer.print(index, item);
+        }
+        let mut out = printer.out;
+        let trimmed = out.trim_end().len();
+        out.truncate(trimmed);
+        out.push('\n');
+        out
+    }
+}
+
+struct Printer<'a> {
+    doc: &'a Doc,
+    options: &'a FormatOptions,
+    out: String,
+    column: usize,
+    /// Indentation levels from the emitter
+    indent: usize,
+    /// Whether the indentation of the current line is still to be written
+    line_start: bool,
+    /// Open groups and whether each is broken over lines
+    stack: Vec<(usize, bool)>,
+    /// Whether the next line needs a `;` if it starts with an ASI hazard
+    asi: bool,
+}
+
+impl Printer<'_> {
+    fn print(&mut self, index: usize, item: &Item) {
+        match item {
+            Item::Text(text) => {
+                if std::mem::take(&mut self.asi) && text.starts_with(ASI_HAZARDS) {
+                    self.write(";");
+                }
+                self.write(text);
+            }
+            Item::Comment(text) => self.write(text),
+            Item::Open(group) => {
+                let info = &self.doc.groups[*group];
+                let broken = info.list.is_some()
+                    && !info.empty
+                    && (info.force_break || !self.fits(index + 1));
+                self.stack.push((*group, broken));
+            }
+            Item::Close(_) => {
+                self.stack.pop();
+            }
+            Item::Line { soft, blank, .. } => {
+                if self.is_broken() {
+                    self.newline(*blank);
+                } else if !soft {
+                    self.write(" ");
+                }
+            }
+            Item::CloseLine { group } => {
+                let info = self.doc.groups[*group].list.expect("close lines end lists");
+                if self.is_broken() {
+                    if info
+                        .trailing
+                        .is_some_and(|level| self.options.trailing_comma >= level)
+                    {
+                        self.write(",");
+                    }
+                    self.newline(false);
+                } else if info.spaced {
+                    self.write(" ");
+                }
+            }
+            Item::HardLine { blank, asi } => {
+                self.newline(*blank);
+                self.asi |= *asi;
+            }
+            Item::Indent => self.indent += 1,
+            Item::Dedent => self.indent = self.indent.saturating_sub(1),
+        }
+    }
+
+    fn is_broken(&self) -> bool {
+        self.stack.last().is_some_and(|&(_, broken)| broken)
+    }
+
+    fn current_indent(&self) -> usize {
+        self.indent + self.stack.iter().filter(|(_, broken)| *broken).count()
+    }
+
+    fn newline(&mut self, blank: bool) {
+        let trimmed = self.out.trim_end_matches([' ', '\t']).len();
+        self.out.truncate(trimmed);
+        self.out.push('\n');
+        if blank {
+            self.out.push('\n');
+        }
+        self.line_start = true;
+    }
+
+    /// Column of the next character, counting indentation not written yet
+    fn column(&self) -> usize {
+        if self.line_start {
+            self.current_indent() * self.options.indent_width
+        } else {
+            self.column
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if std::mem::take(&mut self.line_start) {
+            let indent = self.current_indent();
+            let unit = if self.options.use_tabs {
+                "\t".to_string()
+            } else {
+                " ".repeat(self.options.indent_width)
+            };
+            self.out.push_str(&unit.repeat(indent));
+            self.column = indent * self.options.indent_width;
+        }
+        self.out.push_str(text);
+        match text.rfind('\n') {
+            Some(newline) => self.column = text[newline + 1..].chars().count(),
+            None => self.column += text.chars().count(),
+        }
+    }
+
+    /// Whether the group opened just before `start` fits on the current line
+    fn fits(&self, start: usize) -> bool {
+        let Some(remaining) = self.options.line_width.checked_sub(self.column()) else {
+            return false;
+        };
+        let mut width = 0;
+        // groups opened while measuring, and whether each is forced to break
+        let mut opened: Vec<(usize, bool)> = vec![];
+        // once the group is closed, what follows can still be broken at its next line
+        let mut closed = false;
+
+        for item in &self.doc.items[start..] {
+            let flat = match item {
+                Item::Text(text) | Item::Comment(text) => {
+                    if let Some(newline) = text.find('\n') {
+                        return width + text[..newline].chars().count() <= remaining;
+                    }
+                    text.chars().count()
+                }
+                Item::Open(group) => {
+                    let info = &self.doc.groups[*group];
+                    opened.push((*group, info.list.is_some() && !info.empty && info.force_break));
+                    0
+                }
+                Item::Close(group) => {
+                    if opened.last().is_some_and(|(open, _)| open == group) {
+                        opened.pop();
+                    } else {
+                        closed = true;
+                    }
+                    0
+                }
+                Item::Line { group, soft, .. } => {
+                    if closed || self.breaks(*group, &opened) {
+                        return true;
+                    }
+                    usize::from(!soft)
+                }
+                Item::CloseLine { group } => {
+                    if closed || self.breaks(*group, &opened) {
+                        return true;
+                    }
+                    usize::from(self.doc.groups[*group].list.is_some_and(|info| info.spaced))
+                }
+                Item::HardLine { .. } => return true,
+                Item::Indent | Item::Dedent => 0,
+            };
+            width += flat;
+            if width > remaining {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether the lines of `group` are line breaks while measuring
+    fn breaks(&self, group: usize, opened: &[(usize, bool)]) -> bool {
+        if let Some(&(_, forced)) = opened.iter().find(|(open, _)| *open == group) {
+            return forced;
+        }
+        self.stack
+            .iter()
+            .find(|(open, _)| *open == group)
+            .is_some_and(|&(_, broken)| broken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(code: &str, options: FormatOptions) -> String {
+        Formatter::new(options)
+            .format_code(code, Some("input.tsx"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_format_style_options() {
+        let code = "// greeting\nconst greet = (name) => { return 'hi ' + name }\n\n\n/** Doc */\nexport const config = {port: 3000, \"host\": 'localhost'}\nlet items = [1,2,3]\n";
+
+        let formatted = format(code, FormatOptions::new());
+        assert_eq!(
+            formatted,
+            "// greeting\nconst greet = (name) => {\n  return \"hi \" + name;\n};\n\n/** Doc */\nexport const config = { port: 3000, \"host\": \"localhost\" };\nlet items = [1, 2, 3];\n"
+        );
+
+        let formatted = format(
+            code,
+            FormatOptions::new()
+                .indent_width(4)
+                .quote_style(QuoteStyle::Single)
+                .semicolons(false),
+        );
+        assert!(formatted.contains("\n    return 'hi ' + name\n}\n"));
+        assert!(formatted.contains("port: 3000, 'host': 'localhost' }\n"));
+    }
+
+    #[test]
+    fn test_format_breaks_long_lists() {
+        let code = "call(firstArgument, secondArgument, { nested: true, other: [alpha, beta] }, last);\nconst obj = {\n  a: 1, b: 2 };\n";
+        let options = FormatOptions::new().line_width(42);
+        let formatted = format(code, options.clone());
+        assert_eq!(
+            formatted,
+            "call(\n  firstArgument,\n  secondArgument,\n  { nested: true, other: [alpha, beta] },\n  last,\n);\nconst obj = {\n  a: 1,\n  b: 2,\n};\n"
+        );
+        assert_eq!(format(&formatted, options.clone()), formatted);
+
+        let formatted = format(code, options.trailing_comma(TrailingComma::Es5));
+        assert!(formatted.contains("  last\n);"));
+        assert!(formatted.contains("  b: 2,\n};"));
+    }
+
+    #[test]
+    fn test_format_without_semicolons_guards_asi() {
+        let code = "let a = b\n;[1, 2].forEach(log)\nfor (;;) {}\n";
+        let formatted = format(code, FormatOptions::new().semicolons(false));
+        assert_eq!(formatted, "let a = b\n;[1, 2].forEach(log)\nfor (;;) {}\n");
+    }
+
+    #[test]
+    fn test_format_semicolons_before_trailing_line_comments() {
+        let code = "const a = 1 // one\nconst b = 2\n;(run)() // two\nlet c = 3; // three\n";
+
+        let formatted = format(code, FormatOptions::new());
+        assert_eq!(
+            formatted,
+            "const a = 1; // one\nconst b = 2;\n(run)(); // two\nlet c = 3; // three\n"
+        );
+        assert_eq!(format(&formatted, FormatOptions::new()), formatted);
+
+        let code = "const a = 1 // one\n;[1].forEach(log) // two\nlet c = 3; // three\n";
+        let formatted = format(code, FormatOptions::new().semicolons(false));
+        assert_eq!(
+            formatted,
+            "const a = 1 // one\n;[1].forEach(log) // two\nlet c = 3 // three\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_blank_lines_before_ts_declarations() {
+        let code = "import a from \"a\";\n\ninterface P {\n  a: string;\n}\n\ntype Q = P;\n\nexport type R = Q;\n";
+        let formatted = format(code, FormatOptions::new());
+        assert_eq!(formatted, code);
+    }
+
+    #[test]
+    fn test_format_lays_out_jsx() {
+        let code = "function Card() {\n        return (\n                <div className=\"card\">\n                        <h2>hi</h2>\n\n                        <p>\n                            Hello {name},\n                            welcome\n                        </p>\n                        <> <b>a</b>\n                        </>\n                </div>\n        );\n}\n";
+        let formatted = format(code, FormatOptions::new());
+        assert_eq!(
+            formatted,
+            "function Card() {\n  return (\n    <div className=\"card\">\n      <h2>hi</h2>\n\n      <p>\n        Hello {name}, welcome\n      </p>\n      <>\n        {\" \"}<b>a</b>\n      </>\n    </div>\n  );\n}\n"
+        );
+        assert_eq!(format(&formatted, FormatOptions::new()), formatted);
+
+        let options = FormatOptions::new().indent_width(4);
+        let wide = format(&formatted, options.clone());
+        assert!(wide.contains("\n        <div className=\"card\">\n            <h2>hi</h2>\n"));
+        assert_eq!(format(&wide, options), wide);
+    }
+
+    #[test]
+    fn test_requote() {
+        assert_eq!(requote("'it\\'s'", QuoteStyle::Double), "\"it's\"");
+        assert_eq!(requote("\"say \\\"hi\\\"\"", QuoteStyle::Double), "'say \"hi\"'");
+        assert_eq!(requote("\"a'b\"", QuoteStyle::Single), "\"a'b\"");
+        assert_eq!(requote("'\\n'", QuoteStyle::Double), "\"\\n\"");
+    }
+}
@@ -1,33 +1,243 @@
+use crate::estree;
+use crate::jsdoc::{self, ExportDoc};
+use crate::diagnostics::{ParseDiagnostic, Severity};
 use anyhow::Result;
-use swc_common::errors::Handler;
+use swc_common::comments::SingleThreadedComments;
 use swc_common::sync::Lrc;
-use swc_common::{FileName, SourceMap};
-use swc_ecma_parser::{parse_file_as_module, Syntax, TsSyntax};
-use swc_ecma_ast::{Module, EsVersion};
+use swc_common::{BytePos, FileName, SourceFile, SourceMap, Span, Spanned, DUMMY_SP};
+use swc_ecma_parser::error::Error as SwcError;
+use swc_ecma_parser::{
+    parse_file_as_module, parse_file_as_program, parse_file_as_script, EsSyntax, PResult, Syntax,
+    TsSyntax,
+};
+use swc_ecma_ast::{EsVersion, Module, ModuleItem, Program, Script};
 use std::path::Path;
 
+/// Upper bound on repair-and-reparse rounds in [`JavaScriptParser::parse_code_recovering`]
+const MAX_RECOVERY_ATTEMPTS: usize = 32;
+
+/// The grammar goal the source is parsed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseGoal {
+    /// Classic script: sloppy mode, no `import`/`export`
+    Script,
+    /// ES module: strict mode, `import`/`export` allowed
+    #[default]
+    Module,
+    /// Module if the source contains `import`/`export`, script otherwise
+    Auto,
+}
+
+/// Options controlling how source text is parsed
+///
+/// Start from [`ParserOptions::from_filename`] to get the defaults for a file
+/// extension, then adjust with the builder methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Parse TypeScript syntax
+    pub typescript: bool,
+    /// Parse a TypeScript declaration file
+    pub dts: bool,
+    /// Accept JSX elements
+    pub jsx: bool,
+    /// Accept decorators
+    pub decorators: bool,
+    /// Accept `import ... with { type: "json" }`; TypeScript always accepts them
+    pub import_attributes: bool,
+    /// Language version the source is written in
+    pub target: EsVersion,
+    pub goal: ParseGoal,
+}
+
+impl ParserOptions {
+    /// TSX with decorators parsed as a module, which accepts the widest range of input
+    pub fn new() -> Self {
+        Self {
+            typescript: true,
+            dts: false,
+            jsx: true,
+            decorators: true,
+            import_attributes: true,
+            target: EsVersion::Es2022,
+            goal: ParseGoal::Module,
+        }
+    }
+
+    /// Default options for a file name, based on its extension
+    ///
+    /// Unknown extensions fall back to [`ParserOptions::new`].
+    pub fn from_filename(filename: &str) -> Self {
+        let base = Self::new();
+        let javascript = Self {
+            typescript: false,
+            decorators: false,
+            ..base.clone()
+        };
+        let typescript = Self {
+            jsx: false,
+            ..base.clone()
+        };
+
+        let filename = filename.to_ascii_lowercase();
+        if [".d.ts", ".d.mts", ".d.cts"]
+            .iter()
+            .any(|ext| filename.ends_with(ext))
+        {
+            return typescript.dts(true);
+        }
+
+        match Path::new(&filename).extension().and_then(|ext| ext.to_str()) {
+            Some("js") | Some("jsx") => javascript.goal(ParseGoal::Auto),
+            Some("mjs") => javascript.jsx(false),
+            Some("cjs") => javascript.jsx(false).goal(ParseGoal::Script),
+            Some("ts") | Some("mts") => typescript,
+            Some("cts") => typescript.goal(ParseGoal::Auto),
+            // .tsx and unknown extensions
+            _ => base,
+        }
+    }
+
+    pub fn typescript(mut self, typescript: bool) -> Self {
+        self.typescript = typescript;
+        self
+    }
+
+    pub fn dts(mut self, dts: bool) -> Self {
+        self.dts = dts;
+        self
+    }
+
+    pub fn jsx(mut self, jsx: bool) -> Self {
+        self.jsx = jsx;
+        self
+    }
+
+    pub fn decorators(mut self, decorators: bool) -> Self {
+        self.decorators = decorators;
+        self
+    }
+
+    pub fn import_attributes(mut self, import_attributes: bool) -> Self {
+        self.import_attributes = import_attributes;
+        self
+    }
+
+    pub fn target(mut self, target: EsVersion) -> Self {
+        self.target = target;
+        self
+    }
+
+    pub fn goal(mut self, goal: ParseGoal) -> Self {
+        self.goal = goal;
+        self
+    }
+
+    /// The SWC syntax these options describe
+    ///
+    /// `filename` decides whether the `.mts`/`.cts` restrictions on
+    /// `<T>expr` assertions apply.
+    pub fn syntax(&self, filename: &str) -> Syntax {
+        if self.typescript {
+            let filename = filename.to_ascii_lowercase();
+            Syntax::Typescript(TsSyntax {
+                tsx: self.jsx,
+                decorators: self.decorators,
+                dts: self.dts,
+                no_early_errors: false,
+                disallow_ambiguous_jsx_like: filename.ends_with(".mts")
+                    || filename.ends_with(".cts"),
+            })
+        } else {
+            Syntax::Es(EsSyntax {
+                jsx: self.jsx,
+                decorators: self.decorators,
+                import_attributes: self.import_attributes,
+                ..Default::default()
+            })
+        }
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The outcome of an error-recovering parse
+#[derive(Debug)]
+pub struct RecoveredParse {
+    /// Best-effort AST; empty if nothing could be salvaged
+    ///
+    /// When fatal errors were repaired, spans point into the repaired copy of
+    /// the source, which keeps the line and column layout of the original.
+    pub module: Module,
+    /// Errors the parser recovered from on its own
+    pub recoverable: Vec<ParseDiagnostic>,
+    /// Errors that stopped the parser, in the order they were repaired
+    pub fatal: Vec<ParseDiagnostic>,
+}
+
+impl RecoveredParse {
+    /// Returns true if the source parsed without any error
+    pub fn is_clean(&self) -> bool {
+        self.recoverable.is_empty() && self.fatal.is_empty()
+    }
+
+    /// Iterates over every error, recoverable ones first
+    pub fn diagnostics(&self) -> impl Iterator<Item = &ParseDiagnostic> {
+        self.recoverable.iter().chain(&self.fatal)
+    }
+}
+
 /// A basic JavaScript parser using SWC
 pub struct JavaScriptParser {
     source_map: Lrc<SourceMap>,
-    handler: Handler,
+    comments: SingleThreadedComments,
+    options: Option<ParserOptions>,
 }
 
 impl JavaScriptParser {
-    /// Create a new JavaScript parser
+    /// Create a new JavaScript parser that picks options from each file extension
     pub fn new() -> Self {
         let source_map: Lrc<SourceMap> = Default::default();
-        let handler = Handler::with_emitter_writer(
-            Box::new(std::io::stderr()),
-            Some(source_map.clone()),
-        );
-        
+
         Self {
             source_map,
-            handler,
+            comments: SingleThreadedComments::default(),
+            options: None,
+        }
+    }
+
+    /// Create a parser that uses the same options for every file
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            options: Some(options),
+            ..Self::new()
         }
     }
+
+    /// The options used to parse `filename`
+    pub fn options_for(&self, filename: &str) -> ParserOptions {
+        self.options
+            .clone()
+            .unwrap_or_else(|| ParserOptions::from_filename(filename))
+    }
+
+    /// The source map holding every file parsed so far
+    pub fn source_map(&self) -> &Lrc<SourceMap> {
+        &self.source_map
+    }
+
+    /// The comments of every file parsed so far, keyed by source position
+    pub fn comments(&self) -> &SingleThreadedComments {
+        &self.comments
+    }
     
     /// Parse JavaScript/TypeScript code from a string
+    ///
+    /// Scripts are returned as a module whose items are all statements.
+    /// On failure the error downcasts to a [`ParseDiagnostic`].
     pub fn parse_code(&self, code: &str, filename: Option<&str>) -> Result<Module> {
         let filename = filename.unwrap_or("input.js");
         let source_file = self.source_map.new_source_file(
@@ -35,31 +245,287 @@ impl JavaScriptParser {
             code.to_string(),
         );
         
-        let syntax = Syntax::Typescript(TsSyntax {
-            tsx: true,
-            decorators: true,
-            dts: filename.ends_with(".d.ts"),
-            no_early_errors: false,
-            disallow_ambiguous_jsx_like: true,
-        });
-        
-        let module = parse_file_as_module(
-            &source_file,
-            syntax,
-            EsVersion::Es2022,
-            None,
-            &mut vec![],
-        ).map_err(|err| anyhow::anyhow!("Parse error: {:?}", err))?;
+        let module = self
+            .parse_source_file(&source_file, filename, &mut vec![])
+            .map_err(|err| {
+                ParseDiagnostic::from_swc_error(&self.source_map, &err, Severity::Error)
+            })?;
         
         Ok(module)
     }
+
+    /// Parse JavaScript/TypeScript code from a string, keeping the script/module distinction
+    pub fn parse_program(&self, code: &str, filename: Option<&str>) -> Result<Program> {
+        let filename = filename.unwrap_or("input.js");
+        let source_file = self.source_map.new_source_file(
+            FileName::Custom(filename.to_string()).into(),
+            code.to_string(),
+        );
+        let options = self.options_for(filename);
+        let syntax = options.syntax(filename);
+
+        let program = match options.goal {
+            ParseGoal::Script => {
+                parse_file_as_script(&source_file, syntax, options.target, Some(&self.comments), &mut vec![])
+                    .map(Program::Script)
+            }
+            ParseGoal::Module => {
+                parse_file_as_module(&source_file, syntax, options.target, Some(&self.comments), &mut vec![])
+                    .map(Program::Module)
+            }
+            ParseGoal::Auto => {
+                parse_file_as_program(&source_file, syntax, options.target, Some(&self.comments), &mut vec![])
+            }
+        }
+        .map_err(|err| ParseDiagnostic::from_swc_error(&self.source_map, &err, Severity::Error))?;
+
+        Ok(program)
+    }
+
+    /// Parses an already registered source file according to its options
+    fn parse_source_file(
+        &self,
+        source_file: &SourceFile,
+        filename: &str,
+        errors: &mut Vec<SwcError>,
+    ) -> PResult<Module> {
+        let options = self.options_for(filename);
+        let syntax = options.syntax(filename);
+
+        match options.goal {
+            ParseGoal::Script => {
+                parse_file_as_script(source_file, syntax, options.target, Some(&self.comments), errors)
+                    .map(script_to_module)
+            }
+            ParseGoal::Module => {
+                parse_file_as_module(source_file, syntax, options.target, Some(&self.comments), errors)
+            }
+            ParseGoal::Auto => {
+                parse_file_as_program(source_file, syntax, options.target, Some(&self.comments), errors).map(
+                    |program| match program {
+                        Program::Module(module) => module,
+                        Program::Script(script) => script_to_module(script),
+                    },
+                )
+            }
+        }
+    }
     
+    /// Parse JavaScript/TypeScript code from a string, recovering from errors
+    ///
+    /// Recoverable errors are collected instead of discarded. When the parser
+    /// hits a fatal error, the offending lines are blanked out (or missing
+    /// closing brackets appended at end of input) and the code is parsed again,
+    /// so a file that is halfway through being edited still yields an AST.
+    pub fn parse_code_recovering(&self, code: &str, filename: Option<&str>) -> RecoveredParse {
+        let filename = filename.unwrap_or("input.js");
+        let original = self.source_map.new_source_file(
+            FileName::Custom(filename.to_string()).into(),
+            code.to_string(),
+        );
+
+        let mut recoverable: Vec<ParseDiagnostic> = vec![];
+        let mut fatal = vec![];
+        let mut source = code.to_string();
+        let mut source_file = original.clone();
+
+        for _ in 0..MAX_RECOVERY_ATTEMPTS {
+            let mut errors = vec![];
+            let result = self.parse_source_file(&source_file, filename, &mut errors);
+
+            for err in errors {
+                let err = rebase_error(&err, &source_file, &original);
+                let diagnostic =
+                    ParseDiagnostic::from_swc_error(&self.source_map, &err, Severity::Error);
+                if !recoverable.contains(&diagnostic) {
+                    recoverable.push(diagnostic);
+                }
+            }
+
+            let err = match result {
+                Ok(module) => {
+                    return RecoveredParse {
+                        module,
+                        recoverable,
+                        fatal,
+                    }
+                }
+                Err(err) => err,
+            };
+
+            let span = err.span();
+            let lo = (span.lo - source_file.start_pos).0 as usize;
+            let hi = (span.hi.max(span.lo) - source_file.start_pos).0 as usize;
+            let err = rebase_error(&err, &source_file, &original);
+            fatal.push(ParseDiagnostic::from_swc_error(
+                &self.source_map,
+                &err,
+                Severity::Error,
+            ));
+
+            let repaired = repair_source(&source, lo, hi);
+            if repaired == source {
+                break;
+            }
+            source = repaired;
+            source_file = self.source_map.new_source_file(
+                FileName::Custom(filename.to_string()).into(),
+                source.clone(),
+            );
+        }
+
+        RecoveredParse {
+            module: Module {
+                span: DUMMY_SP,
+                body: vec![],
+                shebang: None,
+            },
+            recoverable,
+            fatal,
+        }
+    }
+
     /// Parse JavaScript/TypeScript code from a file
     pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Module> {
         let code = std::fs::read_to_string(&path)?;
         let filename = path.as_ref().to_string_lossy();
         self.parse_code(&code, Some(&filename))
     }
+
+    /// Convert a module parsed by this parser into an ESTree/Babel-compatible JSON AST
+    pub fn to_estree(&self, module: &Module) -> serde_json::Value {
+        estree::module_to_estree(&self.source_map, module)
+    }
+
+    /// Parse code and serialize it as ESTree/Babel-compatible JSON
+    ///
+    /// Scripts are reported with `"sourceType": "script"`.
+    pub fn parse_to_estree_json(&self, code: &str, filename: Option<&str>) -> Result<String> {
+        let program = self.parse_program(code, filename)?;
+        let ast = estree::program_to_estree(&self.source_map, &program);
+        Ok(serde_json::to_string(&ast)?)
+    }
+
+    /// The JSDoc/TSDoc blocks attached to the exports of a module parsed by this parser
+    pub fn export_docs(&self, module: &Module) -> Vec<ExportDoc> {
+        jsdoc::extract_export_docs(&self.source_map, module, &self.comments)
+    }
+
+    /// Parse code and return the documentation of its exports
+    pub fn parse_export_docs(&self, code: &str, filename: Option<&str>) -> Result<Vec<ExportDoc>> {
+        let module = self.parse_code(code, filename)?;
+        Ok(self.export_docs(&module))
+    }
+}
+
+/// Wraps the statements of a script in a module
+fn script_to_module(script: Script) -> Module {
+    Module {
+        span: script.span,
+        body: script.body.into_iter().map(ModuleItem::Stmt).collect(),
+        shebang: script.shebang,
+    }
+}
+
+/// Moves an error from a repaired copy of a file onto the original file
+fn rebase_error(err: &SwcError, from: &SourceFile, to: &SourceFile) -> SwcError {
+    let rebase = |pos: BytePos| (pos - from.start_pos + to.start_pos).min(to.end_pos);
+    let span = err.span();
+    SwcError::new(
+        Span::new(rebase(span.lo), rebase(span.hi)),
+        err.kind().clone(),
+    )
+}
+
+/// Makes the smallest edit that gets the parser past the error at `lo..hi`
+///
+/// The lines covered by the error are replaced with spaces so every other
+/// byte keeps its offset. If that changes nothing (the error is at end of
+/// input), the brackets that are still open are closed instead.
+fn repair_source(source: &str, lo: usize, hi: usize) -> String {
+    let lo = lo.min(source.len());
+    let hi = hi.clamp(lo, source.len());
+    let line_start = source[..lo].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[hi..].find('\n').map_or(source.len(), |i| hi + i);
+
+    let mut repaired = String::with_capacity(source.len());
+    repaired.push_str(&source[..line_start]);
+    repaired.extend(source[line_start..line_end].chars().map(|c| match c {
+        '\n' | '\r' => c,
+        // keep the byte length so later offsets stay valid
+        _ => ' ',
+    }));
+    // multi-byte characters are blanked with one space per byte
+    let blanked_len = repaired.len() - line_start;
+    let original_len = line_end - line_start;
+    repaired.extend(std::iter::repeat_n(' ', original_len - blanked_len));
+    repaired.push_str(&source[line_end..]);
+
+    if repaired != source {
+        return repaired;
+    }
+
+    let mut closed = source.to_string();
+    if !closed.ends_with('\n') {
+        closed.push('\n');
+    }
+    closed.extend(unclosed_brackets(source).iter().rev().map(|open| match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }));
+    if closed.trim_end() == source.trim_end() {
+        return source.to_string();
+    }
+    closed
+}
+
+/// Returns the brackets still open at the end of `source`, outermost first
+fn unclosed_brackets(source: &str) -> Vec<char> {
+    let mut stack = vec![];
+    let mut chars = source.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            match c {
+                '\\' => {
+                    chars.next();
+                }
+                _ if c == q => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' | '\'' | '`' => quote = Some(c),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '(' | '[' | '{' => stack.push(c),
+            ')' | ']' | '}' => {
+                stack.pop();
+            }
+            _ => {}
+        }
+    }
+
+    stack
 }
 
 impl Default for JavaScriptParser {
@@ -124,4 +590,230 @@ mod tests {
         let result = parser.parse_code(code, Some("component.tsx"));
         assert!(result.is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_options_from_extension() {
+        let cjs = ParserOptions::from_filename("server.cjs");
+        assert!(!cjs.typescript);
+        assert!(!cjs.jsx);
+        assert_eq!(cjs.goal, ParseGoal::Script);
+
+        let mts = ParserOptions::from_filename("lib.mts");
+        assert!(mts.typescript);
+        assert!(!mts.jsx);
+        assert_eq!(mts.goal, ParseGoal::Module);
+
+        assert!(ParserOptions::from_filename("types.d.ts").dts);
+        assert!(ParserOptions::from_filename("App.tsx").jsx);
+        assert!(ParserOptions::from_filename("App.jsx").jsx);
+    }
+
+    #[test]
+    fn test_parse_sloppy_cjs_script() {
+        let parser = JavaScriptParser::new();
+        let code = r#"
+            var config = { debug: true };
+            with (config) { console.log(debug); }
+            module.exports = config;
+        "#;
+
+        assert!(parser.parse_code_recovering(code, Some("config.cjs")).is_clean());
+        assert!(!parser.parse_code_recovering(code, Some("config.mjs")).is_clean());
+    }
+
+    #[test]
+    fn test_parse_ts_type_assertion() {
+        let parser = JavaScriptParser::new();
+        let code = "const value = <number>input;";
+
+        assert!(parser.parse_code(code, Some("cast.ts")).is_ok());
+        assert!(parser.parse_code(code, Some("cast.tsx")).is_err());
+    }
+
+    #[test]
+    fn test_parse_program_auto_goal() {
+        let parser = JavaScriptParser::new();
+
+        let script = parser.parse_program("var a = 1;", Some("a.js")).unwrap();
+        assert!(matches!(script, Program::Script(_)));
+
+        let module = parser.parse_program("export const a = 1;", Some("a.js")).unwrap();
+        assert!(matches!(module, Program::Module(_)));
+    }
+
+    #[test]
+    fn test_parser_with_explicit_options() {
+        let options = ParserOptions::new()
+            .typescript(false)
+            .jsx(false)
+            .goal(ParseGoal::Script)
+            .target(EsVersion::Es2015);
+        let parser = JavaScriptParser::with_options(options);
+
+        assert!(parser.parse_code("let x = 1;", Some("anything.tsx")).is_ok());
+        assert!(parser.parse_code("import x from 'x';", Some("anything.tsx")).is_err());
+    }
+
+    #[test]
+    fn test_parse_error_diagnostic() {
+        let parser = JavaScriptParser::new();
+        let code = "const a = 1;\nconst b = ;\nconst c = 3;\n";
+
+        let err = parser.parse_code(code, Some("broken.ts")).unwrap_err();
+        let diagnostic = err.downcast_ref::<ParseDiagnostic>().unwrap();
+
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.file_name, "broken.ts");
+        assert_eq!(diagnostic.start_line, 2);
+        assert_eq!(diagnostic.start_column, 11);
+        assert!(!diagnostic.code.is_empty());
+        assert!(diagnostic.code_frame.contains("> 2 | const b = ;"));
+        assert!(diagnostic.code_frame.contains("^"));
+
+        let json: serde_json::Value = serde_json::from_str(&diagnostic.to_json()).unwrap();
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["startLine"], 2);
+        assert_eq!(json["fileName"], "broken.ts");
+    }
+
+    #[test]
+    fn test_recovering_parse_skips_broken_line() {
+        let parser = JavaScriptParser::new();
+        let code = "const a = 1;\nconst b = ;\nconsole.log(a);\n";
+
+        let result = parser.parse_code_recovering(code, Some("editing.ts"));
+
+        assert_eq!(result.module.body.len(), 2);
+        assert_eq!(result.fatal.len(), 1);
+        assert_eq!(result.fatal[0].start_line, 2);
+        assert!(!result.is_clean());
+    }
+
+    #[test]
+    fn test_recovering_parse_closes_open_blocks() {
+        let parser = JavaScriptParser::new();
+        let code = "const a = 1;\nconst config = { items: [1, 2\n";
+
+        let result = parser.parse_code_recovering(code, Some("editing.js"));
+
+        assert_eq!(result.module.body.len(), 2);
+        assert_eq!(result.fatal.len(), 1);
+        assert_eq!(result.fatal[0].code, "Eof");
+    }
+
+    #[test]
+    fn test_recovering_parse_keeps_recoverable_errors() {
+        let parser = JavaScriptParser::new();
+        let code = "const s = \"\\07\";\nexport default s;\n";
+
+        let result = parser.parse_code_recovering(code, Some("octal.js"));
+
+        assert_eq!(result.module.body.len(), 2);
+        assert!(result.fatal.is_empty());
+        assert_eq!(result.recoverable.len(), 1);
+    }
+
+    #[test]
+    fn test_estree_locations() {
+        let parser = JavaScriptParser::new();
+        let code = "const s = \"é\";\nlet n = a?.b.c();\n";
+
+        let json = parser.parse_to_estree_json(code, Some("loc.mjs")).unwrap();
+        let ast: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(ast["type"], "Program");
+        assert_eq!(ast["sourceType"], "module");
+        let second = &ast["body"][1];
+        assert_eq!(second["type"], "VariableDeclaration");
+        // "é" is one UTF-16 unit but two bytes
+        assert_eq!(second["start"], 15);
+        assert_eq!(second["loc"]["start"], serde_json::json!({ "line": 2, "column": 0 }));
+
+        let init = &second["declarations"][0]["init"];
+        assert_eq!(init["type"], "ChainExpression");
+        assert_eq!(init["expression"]["type"], "CallExpression");
+        assert_eq!(init["expression"]["callee"]["object"]["optional"], true);
+    }
+
+    #[test]
+    fn test_estree_jsx_and_typescript() {
+        let parser = JavaScriptParser::new();
+        let code = "const el = <App title={x as string} />;\nlet n: number = 1;";
+
+        let module = parser.parse_code(code, Some("app.tsx")).unwrap();
+        let ast = parser.to_estree(&module);
+
+        let init = &ast["body"][0]["declarations"][0]["init"];
+        assert_eq!(init["type"], "JSXElement");
+        let attr = &init["openingElement"]["attributes"][0];
+        assert_eq!(attr["name"]["name"], "title");
+        assert_eq!(attr["value"]["expression"]["type"], "TSAsExpression");
+        assert_eq!(
+            attr["value"]["expression"]["typeAnnotation"]["type"],
+            "TSStringKeyword"
+        );
+        assert_eq!(init["range"], serde_json::json!([11, 38]));
+
+        // a typed binding spans its annotation
+        let id = &ast["body"][1]["declarations"][0]["id"];
+        assert_eq!(id["typeAnnotation"]["typeAnnotation"]["type"], "TSNumberKeyword");
+        assert_eq!(id["range"], serde_json::json!([44, 53]));
+        assert_eq!(id["loc"]["end"], serde_json::json!({ "line": 2, "column": 13 }));
+    }
+
+    #[test]
+    fn test_export_docs() {
+        let parser = JavaScriptParser::new();
+        let code = r#"
+            /**
+             * A clickable button.
+             *
+             * @param {ButtonProps} props - the button props
+             * @param {string} [props.size="md"] Size of the button
+             * @returns {JSX.Element} the rendered button
+             * @deprecated Use `IconButton` instead
+             * @category inputs
+             */
+            export function Button(props: ButtonProps) {
+                return <button>{props.label}</button>;
+            }
+
+            /** Props of {@link Button} */
+            interface ButtonProps {
+                label: string;
+            }
+
+            // not a doc comment
+            export const undocumented = 1;
+
+            export type { ButtonProps as Props };
+        "#;
+
+        let docs = parser.parse_export_docs(code, Some("button.tsx")).unwrap();
+        assert_eq!(docs.len(), 2);
+
+        let button = &docs[0];
+        assert_eq!(button.name, "Button");
+        assert_eq!(button.kind, jsdoc::ExportKind::Function);
+        assert_eq!(button.doc.description, "A clickable button.");
+
+        let params: Vec<_> = button.doc.params().collect();
+        assert_eq!(params.len(), 2);
+        assert_eq!(params[0].name.as_deref(), Some("props"));
+        assert_eq!(params[0].type_expr.as_deref(), Some("ButtonProps"));
+        assert_eq!(params[0].description, "the button props");
+        assert_eq!(params[1].name.as_deref(), Some("props.size"));
+        assert!(params[1].optional);
+        assert_eq!(params[1].default.as_deref(), Some("\"md\""));
+        assert_eq!(
+            button.doc.returns().and_then(|t| t.type_expr.as_deref()),
+            Some("JSX.Element")
+        );
+        assert_eq!(button.doc.deprecated(), Some("Use `IconButton` instead"));
+        assert_eq!(button.doc.tags_named("category").next().unwrap().description, "inputs");
+
+        assert_eq!(docs[1].name, "Props");
+        assert_eq!(docs[1].local_name.as_deref(), Some("ButtonProps"));
+        assert_eq!(docs[1].kind, jsdoc::ExportKind::Interface);
+    }
+}
@@ -0,0 +1,372 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use swc_common::comments::{CommentKind, Comments, SingleThreadedComments};
+use swc_common::{BytePos, SourceMap};
+use swc_ecma_ast::*;
+
+/// Tags whose first word after the type is the name of the documented thing
+const NAMED_TAGS: &[&str] = &[
+    "param", "arg", "argument", "prop", "property", "typeParam", "template", "typedef",
+    "callback",
+];
+
+/// A parsed JSDoc/TSDoc block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct JsDoc {
+    /// Free text before the first tag
+    pub description: String,
+    pub tags: Vec<JsDocTag>,
+}
+
+/// A single `@tag` of a doc block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsDocTag {
+    /// Tag name without the `@`, e.g. `param`
+    pub tag: String,
+    /// Type expression between braces, e.g. `string` for `{string}`
+    pub type_expr: Option<String>,
+    /// Documented name for `@param`-like tags, without brackets or default
+    pub name: Option<String>,
+    /// Whether the name was written in brackets, e.g. `[size]`
+    pub optional: bool,
+    /// Default value from `[name=default]`
+    pub default: Option<String>,
+    pub description: String,
+}
+
+impl JsDoc {
+    /// Parses the text of a `/** ... */` comment, with or without the delimiters
+    pub fn parse(comment: &str) -> Self {
+        let body = comment.trim();
+        let body = body.strip_prefix("/*").unwrap_or(body);
+        let body = body.strip_suffix("*/").unwrap_or(body);
+        let body = body.strip_prefix('*').unwrap_or(body);
+
+        let lines: Vec<&str> = body
+            .lines()
+            .map(|line| {
+                let line = line.trim_start();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line).trim_end()
+            })
+            .collect();
+
+        let mut doc = JsDoc::default();
+        let mut description = vec![];
+        let mut current: Option<(String, Vec<&str>)> = None;
+
+        for line in lines {
+            if let Some(rest) = line.strip_prefix('@') {
+                if let Some((header, text)) = current.take() {
+                    doc.tags.push(JsDocTag::parse(&header, &text));
+                }
+                current = Some((rest.to_string(), vec![]));
+            } else if let Some((_, text)) = current.as_mut() {
+                text.push(line);
+            } else {
+                description.push(line);
+            }
+        }
+        if let Some((header, text)) = current {
+            doc.tags.push(JsDocTag::parse(&header, &text));
+        }
+
+        doc.description = join_lines(&description);
+        doc
+    }
+
+    /// Tags with the given name, e.g. `"param"`
+    pub fn tags_named<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a JsDocTag> {
+        self.tags.iter().filter(move |t| t.tag == tag)
+    }
+
+    /// `@param` tags, in declaration order
+    pub fn params(&self) -> impl Iterator<Item = &JsDocTag> {
+        self.tags
+            .iter()
+            .filter(|t| matches!(t.tag.as_str(), "param" | "arg" | "argument"))
+    }
+
+    /// The `@returns` (or `@return`) tag
+    pub fn returns(&self) -> Option<&JsDocTag> {
+        self.tags
+            .iter()
+            .find(|t| matches!(t.tag.as_str(), "returns" | "return"))
+    }
+
+    /// The deprecation notice if the block has a `@deprecated` tag
+    pub fn deprecated(&self) -> Option<&str> {
+        self.tags_named("deprecated")
+            .next()
+            .map(|t| t.description.as_str())
+    }
+}
+
+impl JsDocTag {
+    /// Parses a tag from its first line (after the `@`) and its continuation lines
+    fn parse(header: &str, continuation: &[&str]) -> Self {
+        let (tag, mut rest) = header
+            .split_once(char::is_whitespace)
+            .map(|(tag, rest)| (tag, rest.trim_start()))
+            .unwrap_or((header, ""));
+
+        let mut type_expr = None;
+        if let Some(end) = rest.starts_with('{').then(|| matching_brace(rest)).flatten() {
+            type_expr = Some(rest[1..end].trim().to_string());
+            rest = rest[end + 1..].trim_start();
+        }
+
+        let (mut name, mut optional, mut default) = (None, false, None);
+        if NAMED_TAGS.contains(&tag) && !rest.is_empty() {
+            let word_end = if rest.starts_with('[') {
+                rest.find(']').map(|i| i + 1).unwrap_or(rest.len())
+            } else {
+                rest.find(char::is_whitespace).unwrap_or(rest.len())
+            };
+            let word = &rest[..word_end];
+            rest = rest[word_end..].trim_start();
+
+            if let Some(inner) = word.strip_prefix('[').and_then(|w| w.strip_suffix(']')) {
+                optional = true;
+                match inner.split_once('=') {
+                    Some((n, d)) => {
+                        name = Some(n.trim().to_string());
+                        default = Some(d.trim().to_string());
+                    }
+                    None => name = Some(inner.trim().to_string()),
+                }
+            } else {
+                name = Some(word.to_string());
+            }
+        }
+
+        // TSDoc separates the name from the description with a hyphen
+        let rest = rest.strip_prefix("- ").unwrap_or(rest);
+        let mut lines = vec![rest];
+        lines.extend_from_slice(continuation);
+
+        Self {
+            tag: tag.to_string(),
+            type_expr,
+            name,
+            optional,
+            default,
+            description: join_lines(&lines),
+        }
+    }
+}
+
+/// What kind of declaration an export is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportKind {
+    Function,
+    Class,
+    Variable,
+    Interface,
+    TypeAlias,
+    Enum,
+    Namespace,
+    /// `export default <expression>`
+    Expression,
+}
+
+/// A documented export of a module
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportDoc {
+    /// Exported name, `default` for the default export
+    pub name: String,
+    /// Local name of the declaration, if different from the exported one
+    pub local_name: Option<String>,
+    pub kind: ExportKind,
+    /// 1-based line of the declaration
+    pub line: usize,
+    pub doc: JsDoc,
+}
+
+/// Collects the JSDoc block attached to each exported declaration of a module
+///
+/// Exports through `export { a as b }` and `export default a` pick up the doc
+/// block of the local declaration. Exports without a doc block are skipped.
+pub fn extract_export_docs(
+    source_map: &SourceMap,
+    module: &Module,
+    comments: &SingleThreadedComments,
+) -> Vec<ExportDoc> {
+    let doc_at = |pos: BytePos| jsdoc_before(comments, pos);
+    let line_of = |pos: BytePos| source_map.lookup_char_pos(pos).line;
+
+    // Docs of local declarations, for exports that refer to them by name
+    let mut locals: HashMap<&str, (ExportKind, BytePos, JsDoc)> = HashMap::new();
+    for item in &module.body {
+        if let ModuleItem::Stmt(Stmt::Decl(decl)) = item {
+            let pos = decl_start(decl);
+            if let Some(doc) = doc_at(pos) {
+                for (name, kind) in decl_names(decl) {
+                    locals.insert(name, (kind, pos, doc.clone()));
+                }
+            }
+        }
+    }
+
+    let mut docs = vec![];
+    let push_local = |exported: &str, local: &str, docs: &mut Vec<ExportDoc>| {
+        if let Some((kind, pos, doc)) = locals.get(local) {
+            docs.push(ExportDoc {
+                name: exported.to_string(),
+                local_name: (exported != local).then(|| local.to_string()),
+                kind: *kind,
+                line: line_of(*pos),
+                doc: doc.clone(),
+            });
+        }
+    };
+
+    for item in &module.body {
+        let ModuleItem::ModuleDecl(decl) = item else {
+            continue;
+        };
+        match decl {
+            ModuleDecl::ExportDecl(export) => {
+                // the comment may sit before `export` or between `export` and the declaration
+                let Some(doc) = doc_at(export.span.lo).or_else(|| doc_at(decl_start(&export.decl)))
+                else {
+                    continue;
+                };
+                for (name, kind) in decl_names(&export.decl) {
+                    docs.push(ExportDoc {
+                        name: name.to_string(),
+                        local_name: None,
+                        kind,
+                        line: line_of(export.span.lo),
+                        doc: doc.clone(),
+                    });
+                }
+            }
+            ModuleDecl::ExportDefaultDecl(export) => {
+                let Some(doc) = doc_at(export.span.lo) else {
+                    continue;
+                };
+                let (local, kind) = match &export.decl {
+                    DefaultDecl::Class(class) => (class.ident.as_ref(), ExportKind::Class),
+                    DefaultDecl::Fn(func) => (func.ident.as_ref(), ExportKind::Function),
+                    DefaultDecl::TsInterfaceDecl(interface) => {
+                        (Some(&interface.id), ExportKind::Interface)
+                    }
+                };
+                docs.push(ExportDoc {
+                    name: "default".to_string(),
+                    local_name: local.map(|ident| ident.sym.to_string()),
+                    kind,
+                    line: line_of(export.span.lo),
+                    doc,
+                });
+            }
+            ModuleDecl::ExportDefaultExpr(export) => match (doc_at(export.span.lo), &*export.expr) {
+                (Some(doc), _) => docs.push(ExportDoc {
+                    name: "default".to_string(),
+                    local_name: None,
+                    kind: ExportKind::Expression,
+                    line: line_of(export.span.lo),
+                    doc,
+                }),
+                (None, Expr::Ident(ident)) => push_local("default", &ident.sym, &mut docs),
+                _ => {}
+            },
+            ModuleDecl::ExportNamed(export) if export.src.is_none() => {
+                for specifier in &export.specifiers {
+                    if let ExportSpecifier::Named(named) = specifier {
+                        let local = export_name(&named.orig);
+                        let exported = named.exported.as_ref().map(export_name).unwrap_or(local);
+                        push_local(exported, local, &mut docs);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    docs
+}
+
+/// The last `/** */` block directly before `pos`
+fn jsdoc_before(comments: &SingleThreadedComments, pos: BytePos) -> Option<JsDoc> {
+    comments
+        .get_leading(pos)?
+        .iter()
+        .rev()
+        .find(|c| c.kind == CommentKind::Block && c.text.starts_with('*') && c.text.len() > 1)
+        .map(|c| JsDoc::parse(&c.text))
+}
+
+fn decl_start(decl: &Decl) -> BytePos {
+    match decl {
+        Decl::Class(class) => class
+            .class
+            .decorators
+            .first()
+            .map(|d| d.span.lo)
+            .unwrap_or(class.class.span.lo),
+        Decl::Fn(func) => func.function.span.lo,
+        Decl::Var(var) => var.span.lo,
+        Decl::Using(using) => using.span.lo,
+        Decl::TsInterface(interface) => interface.span.lo,
+        Decl::TsTypeAlias(alias) => alias.span.lo,
+        Decl::TsEnum(decl) => decl.span.lo,
+        Decl::TsModule(decl) => decl.span.lo,
+    }
+}
+
+/// Names bound by a declaration; destructuring patterns are skipped
+fn decl_names(decl: &Decl) -> Vec<(&str, ExportKind)> {
+    match decl {
+        Decl::Class(class) => vec![(&*class.ident.sym, ExportKind::Class)],
+        Decl::Fn(func) => vec![(&*func.ident.sym, ExportKind::Function)],
+        Decl::Var(var) => var
+            .decls
+            .iter()
+            .filter_map(|d| d.name.as_ident())
+            .map(|ident| (&*ident.id.sym, ExportKind::Variable))
+            .collect(),
+        Decl::Using(_) => vec![],
+        Decl::TsInterface(interface) => vec![(&*interface.id.sym, ExportKind::Interface)],
+        Decl::TsTypeAlias(alias) => vec![(&*alias.id.sym, ExportKind::TypeAlias)],
+        Decl::TsEnum(decl) => vec![(&*decl.id.sym, ExportKind::Enum)],
+        Decl::TsModule(decl) => match &decl.id {
+            TsModuleName::Ident(ident) => vec![(&*ident.sym, ExportKind::Namespace)],
+            TsModuleName::Str(_) => vec![],
+        },
+    }
+}
+
+fn export_name(name: &ModuleExportName) -> &str {
+    match name {
+        ModuleExportName::Ident(ident) => &ident.sym,
+        ModuleExportName::Str(s) => &s.value,
+    }
+}
+
+/// Index of the brace closing the one `s` starts with
+fn matching_brace(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Joins doc lines, dropping blank lines at both ends
+fn join_lines(lines: &[&str]) -> String {
+    lines.join("\n").trim().to_string()
+}
@@ -1,19 +1,39 @@
 use ssr_rs::v8;
-use std::collections::HashMap;
 
 pub mod actix_integration;
+pub mod bundler;
+pub mod cli;
+pub mod compile_cache;
 pub mod create_script_origin;
+pub mod define;
+pub mod dev_server;
+pub mod diagnostics;
+pub mod estree;
 pub mod examples;
 pub mod execute_script;
+pub mod formatter;
 pub mod js_parser;
+pub mod jsdoc;
+pub mod lint;
+pub mod minify;
+pub mod module_format;
+pub mod module_graph;
+pub mod module_loader;
 pub mod new;
 pub mod print_output;
 pub mod process;
+pub mod processor_response;
 pub mod react_compiler;
 pub mod request_prop_handler;
+pub mod resolver;
+pub mod runtime_error;
+pub mod script_pool;
 pub mod simple_tests;
+pub mod source_maps;
 pub mod ssr;
+pub mod target;
 pub mod unwrap_request;
+pub mod watcher;
 pub mod wrap_map;
 pub mod wrap_request;
 pub mod send_wrapper;
@@ -50,9 +70,21 @@ pub fn require_callback(
     mut retval: v8::ReturnValue,
 ) {
     let module_name = args.get(0).to_rust_string_lossy(scope);
-    
-    // Basic module resolution for common modules
-    let module_exports = match module_name.as_str() {
+    let loader = module_loader::ModuleLoader::get(scope);
+    let root = loader.root().to_path_buf();
+
+    match loader.require(scope, &module_name, &root) {
+        Ok(module_exports) => retval.set(module_exports),
+        Err(message) => module_loader::throw_error(scope, &message),
+    }
+}
+
+/// Built-in stand-ins for modules that are not installed
+pub fn mock_module<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    module_name: &str,
+) -> Option<v8::Local<'s, v8::Object>> {
+    match module_name {
         "react" => {
             // Simple React mock
             let react_obj = v8::Object::new(scope);
@@ -79,15 +111,39 @@ pub fn require_callback(
             
             let create_element_key = v8::String::new(scope, "createElement").unwrap();
             react_obj.set(scope, create_element_key.into(), create_element_fn.into());
-            react_obj
+            Some(react_obj)
         },
-        _ => {
-            // Return empty object for unknown modules
-            v8::Object::new(scope)
-        }
-    };
-    
-    retval.set(module_exports.into());
+        "react/jsx-runtime" | "react/jsx-dev-runtime" => {
+            // Simple automatic runtime mock, children stay in `props`
+            let runtime_obj = v8::Object::new(scope);
+            let jsx_fn = v8::Function::new(
+                scope,
+                |scope: &mut v8::HandleScope,
+                 args: v8::FunctionCallbackArguments,
+                 mut retval: v8::ReturnValue| {
+                    let obj = v8::Object::new(scope);
+                    let type_key = v8::String::new(scope, "type").unwrap();
+                    let props_key = v8::String::new(scope, "props").unwrap();
+                    let key_key = v8::String::new(scope, "key").unwrap();
+
+                    obj.set(scope, type_key.into(), args.get(0));
+                    obj.set(scope, props_key.into(), args.get(1));
+                    obj.set(scope, key_key.into(), args.get(2));
+
+                    retval.set(obj.into());
+                },
+            ).unwrap();
+
+            for name in ["jsx", "jsxs", "jsxDEV"] {
+                let key = v8::String::new(scope, name).unwrap();
+                runtime_obj.set(scope, key.into(), jsx_fn.into());
+            }
+            let fragment_key = v8::String::new(scope, "Fragment").unwrap();
+            runtime_obj.set(scope, fragment_key.into(), fragment_key.into());
+            Some(runtime_obj)
+        },
+        _ => None,
+    }
 }
 
 use send_wrapper::SendWrapper;
@@ -99,27 +155,77 @@ pub struct JsHttpRequestProcessor<'s, 'i> {
     pub process_fn: Option<v8::Local<'s, v8::Function>>,
     pub request_template: v8::Global<v8::ObjectTemplate>,
     pub _map_template: Option<v8::Global<v8::ObjectTemplate>>,
+    /// The requests passed to `Process` that JavaScript may still reference
+    pub requests: std::rc::Rc<WrappedRequests>,
 }
 
 #[derive(Debug, Clone)]
 pub struct StringHttpRequest {
+    pub method: String,
     pub path: String,
-    pub headers: HashMap<String, String>,
-    pub body: String,
+    /// The query string, without the leading `?`
+    pub query: String,
+    /// Lowercase header names and their values, in order and with repeats
+    pub headers: Vec<(String, String)>,
+    /// The raw body, read as text through [`SimpleHttpRequest::text`]
+    ///
+    /// [`SimpleHttpRequest::text`]: crate::ssr::http_request::SimpleHttpRequest::text
+    pub body: Vec<u8>,
+    pub remote_addr: Option<String>,
+    pub protocol: String,
 }
 
 impl StringHttpRequest {
+    /// A `GET` request; a query string in `path` is split off into [`Self::query`]
+    ///
+    /// Empty header values are left out, as if the request had no such header.
     pub fn new(path: &str, host: &str, user_agent: &str, referer: &str) -> Self {
-        let mut headers = HashMap::new();
-        headers.insert("host".to_string(), host.to_string());
-        headers.insert("user-agent".to_string(), user_agent.to_string());
-        headers.insert("referer".to_string(), referer.to_string());
-
-        Self {
+        let (path, query) = path.split_once('?').unwrap_or((path, ""));
+        let request = Self {
+            method: "GET".to_string(),
             path: path.to_string(),
-            headers,
-            body: "".to_string(),
-        }
+            query: query.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            remote_addr: None,
+            protocol: "http".to_string(),
+        };
+
+        [("host", host), ("user-agent", user_agent), ("referer", referer)]
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .fold(request, |request, (name, value)| request.with_header(name, value))
+    }
+
+    pub fn with_method(mut self, method: &str) -> Self {
+        self.method = method.to_string();
+        self
+    }
+
+    /// Adds a value of the header `name`, keeping any earlier ones
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    pub fn with_query(mut self, query: &str) -> Self {
+        self.query = query.trim_start_matches('?').to_string();
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn with_remote_addr(mut self, remote_addr: &str) -> Self {
+        self.remote_addr = Some(remote_addr.to_string());
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: &str) -> Self {
+        self.protocol = protocol.to_string();
+        self
     }
 }
 
@@ -129,14 +235,41 @@ impl crate::ssr::http_request::SimpleHttpRequest for StringHttpRequest {
     }
     
     fn user_agent(&self) -> &str {
-        self.headers.get("user-agent").map(|s| s.as_str()).unwrap_or("")
+        self.header("user-agent").unwrap_or("")
     }
     
     fn referrer(&self) -> &str {
-        self.headers.get("referer").map(|s| s.as_str()).unwrap_or("")
+        self.header("referer").unwrap_or("")
     }
     
     fn host(&self) -> &str {
-        self.headers.get("host").map(|s| s.as_str()).unwrap_or("")
+        self.header("host").unwrap_or("")
+    }
+
+    fn method(&self) -> &str {
+        &self.method
+    }
+
+    fn headers(&self) -> Vec<(&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect()
+    }
+
+    fn query_string(&self) -> &str {
+        &self.query
+    }
+
+    fn remote_addr(&self) -> Option<&str> {
+        self.remote_addr.as_deref()
+    }
+
+    fn protocol(&self) -> &str {
+        &self.protocol
+    }
+
+    fn body(&self) -> &[u8] {
+        &self.body
     }
 }
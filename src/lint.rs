@@ -0,0 +1,981 @@
+use crate::diagnostics::{render_code_frame, Severity};
+use crate::js_parser::JavaScriptParser;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use swc_common::{Globals, Mark, SourceMap, Span, GLOBALS};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitMutWith, VisitWith};
+
+/// How a rule is reported, as configured per rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Warn,
+    Error,
+}
+
+impl RuleLevel {
+    /// Reads an ESLint-style level: `"off"`/`"warn"`/`"error"`, `0`/`1`/`2`,
+    /// or an array whose first element is one of those
+    pub fn from_json(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(level) => match level.as_str() {
+                "off" => Some(Self::Off),
+                "warn" | "warning" => Some(Self::Warn),
+                "error" => Some(Self::Error),
+                _ => None,
+            },
+            Value::Number(level) => match level.as_u64()? {
+                0 => Some(Self::Off),
+                1 => Some(Self::Warn),
+                2 => Some(Self::Error),
+                _ => None,
+            },
+            Value::Array(items) => Self::from_json(items.first()?),
+            _ => None,
+        }
+    }
+
+    fn severity(self) -> Option<Severity> {
+        match self {
+            Self::Off => None,
+            Self::Warn => Some(Severity::Warning),
+            Self::Error => Some(Severity::Error),
+        }
+    }
+}
+
+/// A check over a parsed module
+///
+/// Rules walk the module with their own [`Visit`] implementation and report
+/// problems through the [`LintContext`]. Identifiers carry the syntax contexts
+/// assigned by swc's resolver, so bindings can be compared with [`Ident::to_id`].
+pub trait Rule: Send + Sync {
+    /// Name used in configs and diagnostics, e.g. `no-debugger`
+    fn name(&self) -> &'static str;
+
+    /// Level used when the config does not mention the rule
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext);
+}
+
+/// Collects the problems reported by one rule
+#[derive(Debug, Default)]
+pub struct LintContext {
+    reports: Vec<(Span, String)>,
+}
+
+impl LintContext {
+    pub fn report(&mut self, span: Span, message: impl Into<String>) {
+        self.reports.push((span, message.into()));
+    }
+}
+
+/// A problem found by a lint rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{file_name}:{start_line}:{start_column}: {message} ({rule})")]
+pub struct LintDiagnostic {
+    pub rule: String,
+    pub severity: Severity,
+    pub message: String,
+    pub file_name: String,
+    /// 1-based line of the first character of the span
+    pub start_line: usize,
+    /// 1-based column (in characters) of the first character of the span
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    /// Source excerpt with the span underlined
+    pub code_frame: String,
+}
+
+impl LintDiagnostic {
+    fn new(source_map: &SourceMap, rule: &str, severity: Severity, span: Span, message: String) -> Self {
+        let lo = source_map.lookup_char_pos(span.lo);
+        let hi = source_map.lookup_char_pos(span.hi.max(span.lo));
+
+        Self {
+            rule: rule.to_string(),
+            severity,
+            message,
+            file_name: lo.file.name.to_string(),
+            start_line: lo.line,
+            start_column: lo.col.0 + 1,
+            end_line: hi.line,
+            end_column: hi.col.0 + 1,
+            code_frame: render_code_frame(&lo.file, lo.line, lo.col.0, hi.line, hi.col.0),
+        }
+    }
+}
+
+/// The set of rules a [`Linter`] can run
+pub struct RuleRegistry {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl RuleRegistry {
+    /// An empty registry
+    pub fn new() -> Self {
+        Self { rules: vec![] }
+    }
+
+    /// A registry with every built-in rule
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(NoUnusedVars);
+        registry.register(NoDebugger);
+        registry.register(Eqeqeq);
+        registry.register(RulesOfHooks);
+        registry.register(JsxKey);
+        registry
+    }
+
+    /// Adds a rule, replacing any rule with the same name
+    pub fn register(&mut self, rule: impl Rule + 'static) {
+        self.rules.retain(|existing| existing.name() != rule.name());
+        self.rules.push(Box::new(rule));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.name() == name)
+            .map(|rule| rule.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+}
+
+impl Default for RuleRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Per-rule levels overriding the rules' defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `{"rules": {"eqeqeq": "error", "no-debugger": "off"}}`, as in an
+    /// `.eslintrc.json`
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let mut config = Self::new();
+        let Some(rules) = value.get("rules") else {
+            return Ok(config);
+        };
+        let rules = rules
+            .as_object()
+            .ok_or_else(|| anyhow!("\"rules\" must be an object"))?;
+        for (name, level) in rules {
+            let level = RuleLevel::from_json(level)
+                .ok_or_else(|| anyhow!("invalid level for rule '{name}': {level}"))?;
+            config.rules.insert(name.clone(), level);
+        }
+        Ok(config)
+    }
+
+    /// Sets the level of one rule
+    pub fn rule(mut self, name: &str, level: RuleLevel) -> Self {
+        self.rules.insert(name.to_string(), level);
+        self
+    }
+
+    pub fn level(&self, rule: &dyn Rule) -> RuleLevel {
+        self.rules
+            .get(rule.name())
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// Runs the enabled rules of a registry over parsed modules
+pub struct Linter {
+    registry: RuleRegistry,
+    config: LintConfig,
+}
+
+impl Linter {
+    /// A linter with the built-in rules at their default levels
+    pub fn new() -> Self {
+        Self::with_registry(RuleRegistry::builtin())
+    }
+
+    pub fn with_registry(registry: RuleRegistry) -> Self {
+        Self {
+            registry,
+            config: LintConfig::new(),
+        }
+    }
+
+    /// Applies per-rule levels, failing on rules the registry does not know
+    pub fn config(mut self, config: LintConfig) -> Result<Self> {
+        if let Some(name) = config.rules.keys().find(|name| self.registry.get(name).is_none()) {
+            return Err(anyhow!("unknown lint rule '{name}'"));
+        }
+        self.config = config;
+        Ok(self)
+    }
+
+    /// Lints a module parsed into `source_map`, sorted by position
+    pub fn lint_module(&self, source_map: &SourceMap, module: &Module) -> Vec<LintDiagnostic> {
+        let rules: Vec<(&dyn Rule, Severity)> = self
+            .registry
+            .iter()
+            .filter_map(|rule| Some((rule, self.config.level(rule).severity()?)))
+            .collect();
+        if rules.is_empty() {
+            return vec![];
+        }
+
+        let mut module = module.clone();
+        GLOBALS.set(&Globals::new(), || {
+            module.visit_mut_with(&mut swc_ecma_transforms_base::resolver(
+                Mark::new(),
+                Mark::new(),
+                true,
+            ));
+        });
+
+        let mut diagnostics = vec![];
+        for (rule, severity) in rules {
+            let mut cx = LintContext::default();
+            rule.check(&module, &mut cx);
+            diagnostics.extend(cx.reports.into_iter().map(|(span, message)| {
+                LintDiagnostic::new(source_map, rule.name(), severity, span, message)
+            }));
+        }
+        diagnostics.sort_by_key(|d| (d.start_line, d.start_column));
+        diagnostics
+    }
+
+    /// Parses and lints a piece of code
+    pub fn lint_code(
+        &self,
+        parser: &JavaScriptParser,
+        code: &str,
+        filename: Option<&str>,
+    ) -> Result<Vec<LintDiagnostic>> {
+        let module = parser.parse_code(code, filename)?;
+        Ok(self.lint_module(parser.source_map(), &module))
+    }
+
+    pub fn lint_file<P: AsRef<Path>>(
+        &self,
+        parser: &JavaScriptParser,
+        path: P,
+    ) -> Result<Vec<LintDiagnostic>> {
+        let module = parser.parse_file(path)?;
+        Ok(self.lint_module(parser.source_map(), &module))
+    }
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `no-debugger`: disallows `debugger` statements
+pub struct NoDebugger;
+
+impl Rule for NoDebugger {
+    fn name(&self) -> &'static str {
+        "no-debugger"
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext) {
+        struct Finder<'a>(&'a mut LintContext);
+        impl Visit for Finder<'_> {
+            fn visit_debugger_stmt(&mut self, stmt: &DebuggerStmt) {
+                self.0.report(stmt.span, "Unexpected 'debugger' statement.");
+            }
+        }
+        module.visit_with(&mut Finder(cx));
+    }
+}
+
+/// `eqeqeq`: requires `===` and `!==`
+pub struct Eqeqeq;
+
+impl Rule for Eqeqeq {
+    fn name(&self) -> &'static str {
+        "eqeqeq"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warn
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext) {
+        struct Finder<'a>(&'a mut LintContext);
+        impl Visit for Finder<'_> {
+            fn visit_bin_expr(&mut self, expr: &BinExpr) {
+                let expected = match expr.op {
+                    BinaryOp::EqEq => "===",
+                    BinaryOp::NotEq => "!==",
+                    _ => "",
+                };
+                if !expected.is_empty() {
+                    self.0.report(
+                        expr.span,
+                        format!("Expected '{expected}' and instead saw '{}'.", expr.op.as_str()),
+                    );
+                }
+                expr.visit_children_with(self);
+            }
+        }
+        module.visit_with(&mut Finder(cx));
+    }
+}
+
+/// `no-unused-vars`: reports variables, functions, classes and imports that
+/// are never referenced
+///
+/// Exported bindings and names starting with `_` are skipped, and `React` is
+/// considered used by any JSX in the module.
+pub struct NoUnusedVars;
+
+impl Rule for NoUnusedVars {
+    fn name(&self) -> &'static str {
+        "no-unused-vars"
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext) {
+        let mut collector = BindingCollector::default();
+        module.visit_with(&mut collector);
+
+        for (id, span, assigned) in collector.declared {
+            let name = &id.0;
+            if collector.used.contains(&id)
+                || collector.exported.contains(&id)
+                || name.starts_with('_')
+                || (collector.has_jsx && name == "React")
+            {
+                continue;
+            }
+            let message = if assigned {
+                format!("'{name}' is assigned a value but never used.")
+            } else {
+                format!("'{name}' is defined but never used.")
+            };
+            cx.report(span, message);
+        }
+    }
+}
+
+/// Declared bindings and every other identifier of a module
+#[derive(Default)]
+struct BindingCollector {
+    /// Binding, where it is declared, and whether it is given a value there
+    declared: Vec<(Id, Span, bool)>,
+    used: HashSet<Id>,
+    exported: HashSet<Id>,
+    has_jsx: bool,
+}
+
+impl BindingCollector {
+    fn declare(&mut self, ident: &Ident, assigned: bool) {
+        let id = ident.to_id();
+        if !self.declared.iter().any(|(declared, ..)| *declared == id) {
+            self.declared.push((id, ident.span, assigned));
+        }
+    }
+
+    /// Declares the names bound by a pattern and visits the expressions inside it
+    fn declare_pat(&mut self, pat: &Pat, assigned: bool) {
+        match pat {
+            Pat::Ident(binding) => {
+                self.declare(&binding.id, assigned);
+                binding.type_ann.visit_with(self);
+            }
+            Pat::Array(array) => {
+                for elem in array.elems.iter().flatten() {
+                    self.declare_pat(elem, assigned);
+                }
+            }
+            Pat::Rest(rest) => self.declare_pat(&rest.arg, assigned),
+            Pat::Object(object) => {
+                for prop in &object.props {
+                    match prop {
+                        ObjectPatProp::KeyValue(prop) => {
+                            prop.key.visit_with(self);
+                            self.declare_pat(&prop.value, assigned);
+                        }
+                        ObjectPatProp::Assign(prop) => {
+                            self.declare(&prop.key.id, assigned);
+                            prop.value.visit_with(self);
+                        }
+                        ObjectPatProp::Rest(rest) => self.declare_pat(&rest.arg, assigned),
+                    }
+                }
+            }
+            Pat::Assign(assign) => {
+                self.declare_pat(&assign.left, assigned);
+                assign.right.visit_with(self);
+            }
+            Pat::Expr(expr) => expr.visit_with(self),
+            Pat::Invalid(_) => {}
+        }
+    }
+}
+
+impl Visit for BindingCollector {
+    fn visit_ident(&mut self, ident: &Ident) {
+        self.used.insert(ident.to_id());
+    }
+
+    fn visit_var_declarator(&mut self, declarator: &VarDeclarator) {
+        self.declare_pat(&declarator.name, declarator.init.is_some());
+        declarator.init.visit_with(self);
+    }
+
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.declare(&decl.ident, false);
+        decl.function.visit_with(self);
+    }
+
+    fn visit_class_decl(&mut self, decl: &ClassDecl) {
+        self.declare(&decl.ident, false);
+        decl.class.visit_with(self);
+    }
+
+    fn visit_import_decl(&mut self, decl: &ImportDecl) {
+        for specifier in &decl.specifiers {
+            let local = match specifier {
+                ImportSpecifier::Named(named) => &named.local,
+                ImportSpecifier::Default(default) => &default.local,
+                ImportSpecifier::Namespace(namespace) => &namespace.local,
+            };
+            self.declare(local, false);
+        }
+    }
+
+    fn visit_export_decl(&mut self, export: &ExportDecl) {
+        let mut exported = vec![];
+        match &export.decl {
+            Decl::Fn(decl) => exported.push(decl.ident.to_id()),
+            Decl::Class(decl) => exported.push(decl.ident.to_id()),
+            Decl::Var(decl) => {
+                let mut names = BindingCollector::default();
+                for declarator in &decl.decls {
+                    names.declare_pat(&declarator.name, false);
+                }
+                exported.extend(names.declared.into_iter().map(|(id, ..)| id));
+            }
+            _ => {}
+        }
+        self.exported.extend(exported);
+        export.decl.visit_with(self);
+    }
+
+    fn visit_jsx_element(&mut self, element: &JSXElement) {
+        self.has_jsx = true;
+        element.visit_children_with(self);
+    }
+
+    fn visit_jsx_fragment(&mut self, fragment: &JSXFragment) {
+        self.has_jsx = true;
+        fragment.visit_children_with(self);
+    }
+}
+
+/// `react-hooks/rules-of-hooks`: hooks may only be called unconditionally at
+/// the top level of components and custom hooks
+pub struct RulesOfHooks;
+
+impl Rule for RulesOfHooks {
+    fn name(&self) -> &'static str {
+        "react-hooks/rules-of-hooks"
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext) {
+        let mut checker = HookChecker {
+            cx,
+            functions: vec![],
+            next_name: None,
+        };
+        module.visit_with(&mut checker);
+    }
+}
+
+/// Whether `name` follows the `useSomething` hook naming convention
+fn is_hook_name(name: &str) -> bool {
+    name == "use"
+        || name
+            .strip_prefix("use")
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_uppercase()))
+}
+
+fn is_component_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// A function body the hook checker is inside of
+struct HookScope {
+    name: Option<String>,
+    /// Nesting depth of conditional branches within this function
+    conditional: usize,
+    /// Nesting depth of loops within this function
+    loops: usize,
+}
+
+struct HookChecker<'a> {
+    cx: &'a mut LintContext,
+    functions: Vec<HookScope>,
+    /// Name given to the next function by its declaration or variable
+    next_name: Option<String>,
+}
+
+impl HookChecker<'_> {
+    fn in_function(&mut self, visit: impl FnOnce(&mut Self)) {
+        let name = self.next_name.take();
+        self.functions.push(HookScope {
+            name,
+            conditional: 0,
+            loops: 0,
+        });
+        visit(self);
+        self.functions.pop();
+    }
+
+    fn conditional(&mut self, visit: impl FnOnce(&mut Self)) {
+        if let Some(scope) = self.functions.last_mut() {
+            scope.conditional += 1;
+        }
+        visit(self);
+        if let Some(scope) = self.functions.last_mut() {
+            scope.conditional -= 1;
+        }
+    }
+
+    fn looping(&mut self, visit: impl FnOnce(&mut Self)) {
+        if let Some(scope) = self.functions.last_mut() {
+            scope.loops += 1;
+        }
+        visit(self);
+        if let Some(scope) = self.functions.last_mut() {
+            scope.loops -= 1;
+        }
+    }
+
+    fn check_hook_call(&mut self, hook: &str, span: Span) {
+        let Some(scope) = self.functions.last() else {
+            self.cx.report(
+                span,
+                format!("React Hook \"{hook}\" cannot be called at the top level. React Hooks must be called in a React function component or a custom React Hook function."),
+            );
+            return;
+        };
+
+        match scope.name.as_deref() {
+            Some(name) if is_component_name(name) || is_hook_name(name) => {}
+            Some(name) => {
+                self.cx.report(
+                    span,
+                    format!("React Hook \"{hook}\" is called in function \"{name}\" that is neither a React function component nor a custom React Hook function."),
+                );
+                return;
+            }
+            None => {
+                self.cx.report(
+                    span,
+                    format!("React Hook \"{hook}\" cannot be called inside a callback. React Hooks must be called in a React function component or a custom React Hook function."),
+                );
+                return;
+            }
+        }
+
+        // `use` is allowed in conditions and loops
+        if hook == "use" {
+            return;
+        }
+        if scope.loops > 0 {
+            self.cx.report(
+                span,
+                format!("React Hook \"{hook}\" may be executed more than once. Possibly because it is called in a loop. React Hooks must be called in the exact same order in every component render."),
+            );
+        } else if scope.conditional > 0 {
+            self.cx.report(
+                span,
+                format!("React Hook \"{hook}\" is called conditionally. React Hooks must be called in the exact same order in every component render."),
+            );
+        }
+    }
+
+    /// Names the function about to be visited when `expr` is one
+    fn name_next(&mut self, name: &str, expr: &Expr) {
+        if matches!(unparen(expr), Expr::Fn(_) | Expr::Arrow(_)) {
+            self.next_name = Some(name.to_string());
+        }
+    }
+}
+
+/// The name of a called hook, for `useThing()` and `React.useThing()`
+fn hook_callee(callee: &Callee) -> Option<&str> {
+    let Callee::Expr(expr) = callee else {
+        return None;
+    };
+    let name: &str = match unparen(expr) {
+        Expr::Ident(ident) => &ident.sym,
+        Expr::Member(MemberExpr {
+            obj,
+            prop: MemberProp::Ident(prop),
+            ..
+        }) if matches!(&**obj, Expr::Ident(_)) => &prop.sym,
+        _ => return None,
+    };
+    is_hook_name(name).then_some(name)
+}
+
+fn unparen(mut expr: &Expr) -> &Expr {
+    while let Expr::Paren(paren) = expr {
+        expr = &paren.expr;
+    }
+    expr
+}
+
+impl Visit for HookChecker<'_> {
+    fn visit_fn_decl(&mut self, decl: &FnDecl) {
+        self.next_name = Some(decl.ident.sym.to_string());
+        decl.function.visit_with(self);
+    }
+
+    fn visit_var_declarator(&mut self, declarator: &VarDeclarator) {
+        declarator.name.visit_with(self);
+        if let (Pat::Ident(binding), Some(init)) = (&declarator.name, &declarator.init) {
+            self.name_next(&binding.id.sym, init);
+        }
+        declarator.init.visit_with(self);
+    }
+
+    fn visit_export_default_decl(&mut self, export: &ExportDefaultDecl) {
+        if let DefaultDecl::Fn(FnExpr { ident, .. }) = &export.decl {
+            // an anonymous default export is treated like a component
+            let name = ident.as_ref().map_or("Component".into(), |ident| ident.sym.to_string());
+            self.next_name = Some(name);
+        }
+        export.decl.visit_children_with(self);
+    }
+
+    fn visit_function(&mut self, function: &Function) {
+        self.in_function(|this| function.visit_children_with(this));
+    }
+
+    fn visit_arrow_expr(&mut self, arrow: &ArrowExpr) {
+        self.in_function(|this| arrow.visit_children_with(this));
+    }
+
+    fn visit_class(&mut self, class: &Class) {
+        self.next_name = None;
+        class.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Some(hook) = hook_callee(&call.callee) {
+            let hook = hook.to_string();
+            self.check_hook_call(&hook, call.span);
+        }
+        call.visit_children_with(self);
+    }
+
+    fn visit_if_stmt(&mut self, stmt: &IfStmt) {
+        stmt.test.visit_with(self);
+        self.conditional(|this| {
+            stmt.cons.visit_with(this);
+            stmt.alt.visit_with(this);
+        });
+    }
+
+    fn visit_cond_expr(&mut self, expr: &CondExpr) {
+        expr.test.visit_with(self);
+        self.conditional(|this| {
+            expr.cons.visit_with(this);
+            expr.alt.visit_with(this);
+        });
+    }
+
+    fn visit_bin_expr(&mut self, expr: &BinExpr) {
+        expr.left.visit_with(self);
+        if matches!(
+            expr.op,
+            BinaryOp::LogicalAnd | BinaryOp::LogicalOr | BinaryOp::NullishCoalescing
+        ) {
+            self.conditional(|this| expr.right.visit_with(this));
+        } else {
+            expr.right.visit_with(self);
+        }
+    }
+
+    fn visit_switch_case(&mut self, case: &SwitchCase) {
+        self.conditional(|this| case.visit_children_with(this));
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt) {
+        stmt.init.visit_with(self);
+        self.looping(|this| {
+            stmt.test.visit_with(this);
+            stmt.update.visit_with(this);
+            stmt.body.visit_with(this);
+        });
+    }
+
+    fn visit_for_in_stmt(&mut self, stmt: &ForInStmt) {
+        stmt.right.visit_with(self);
+        self.looping(|this| stmt.body.visit_with(this));
+    }
+
+    fn visit_for_of_stmt(&mut self, stmt: &ForOfStmt) {
+        stmt.right.visit_with(self);
+        self.looping(|this| stmt.body.visit_with(this));
+    }
+
+    fn visit_while_stmt(&mut self, stmt: &WhileStmt) {
+        self.looping(|this| stmt.visit_children_with(this));
+    }
+
+    fn visit_do_while_stmt(&mut self, stmt: &DoWhileStmt) {
+        self.looping(|this| stmt.visit_children_with(this));
+    }
+}
+
+/// `react/jsx-key`: elements in array literals and returned from `.map()`
+/// callbacks need a `key` prop
+pub struct JsxKey;
+
+impl Rule for JsxKey {
+    fn name(&self) -> &'static str {
+        "react/jsx-key"
+    }
+
+    fn check(&self, module: &Module, cx: &mut LintContext) {
+        module.visit_with(&mut KeyChecker { cx });
+    }
+}
+
+struct KeyChecker<'a> {
+    cx: &'a mut LintContext,
+}
+
+impl KeyChecker<'_> {
+    fn check_element(&mut self, expr: &Expr, in_iterator: bool) {
+        match unparen(expr) {
+            Expr::JSXElement(element) if !has_key(element) => {
+                let message = if in_iterator {
+                    "Missing \"key\" prop for element in iterator"
+                } else {
+                    "Missing \"key\" prop for element in array"
+                };
+                self.cx.report(element.span, message);
+            }
+            Expr::JSXFragment(fragment) => {
+                self.cx.report(
+                    fragment.span,
+                    "Missing \"key\" prop for element in iterator. Shorthand fragment syntax does not support providing keys. Use React.Fragment instead",
+                );
+            }
+            Expr::Cond(cond) => {
+                self.check_element(&cond.cons, in_iterator);
+                self.check_element(&cond.alt, in_iterator);
+            }
+            Expr::Bin(BinExpr {
+                op: BinaryOp::LogicalAnd,
+                right,
+                ..
+            }) => self.check_element(right, in_iterator),
+            _ => {}
+        }
+    }
+
+    /// Checks what an iterator callback returns
+    fn check_callback(&mut self, callback: &Expr) {
+        match unparen(callback) {
+            Expr::Arrow(arrow) => match &*arrow.body {
+                BlockStmtOrExpr::Expr(body) => self.check_element(body, true),
+                BlockStmtOrExpr::BlockStmt(body) => self.check_returns(body),
+            },
+            Expr::Fn(function) => {
+                if let Some(body) = &function.function.body {
+                    self.check_returns(body);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_returns(&mut self, body: &BlockStmt) {
+        let mut returns = ReturnFinder::default();
+        body.visit_with(&mut returns);
+        for arg in returns.args {
+            self.check_element(&arg, true);
+        }
+    }
+}
+
+/// Returned expressions of a function body, without those of nested functions
+#[derive(Default)]
+struct ReturnFinder {
+    args: Vec<Expr>,
+}
+
+impl Visit for ReturnFinder {
+    fn visit_return_stmt(&mut self, stmt: &ReturnStmt) {
+        if let Some(arg) = &stmt.arg {
+            self.args.push((**arg).clone());
+        }
+    }
+
+    fn visit_function(&mut self, _: &Function) {}
+
+    fn visit_arrow_expr(&mut self, _: &ArrowExpr) {}
+}
+
+fn has_key(element: &JSXElement) -> bool {
+    element.opening.attrs.iter().any(|attr| {
+        matches!(attr, JSXAttrOrSpread::JSXAttr(JSXAttr {
+            name: JSXAttrName::Ident(name),
+            ..
+        }) if name.sym == "key")
+    })
+}
+
+impl Visit for KeyChecker<'_> {
+    fn visit_array_lit(&mut self, array: &ArrayLit) {
+        for elem in array.elems.iter().flatten() {
+            if elem.spread.is_none() {
+                self.check_element(&elem.expr, false);
+            }
+        }
+        array.visit_children_with(self);
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        if let Callee::Expr(callee) = &call.callee
+            && let Expr::Member(MemberExpr {
+                prop: MemberProp::Ident(method),
+                ..
+            }) = unparen(callee)
+        {
+            let callback = match &*method.sym {
+                "map" | "flatMap" => call.args.first(),
+                "from" => call.args.get(1),
+                _ => None,
+            };
+            if let Some(callback) = callback {
+                self.check_callback(&callback.expr);
+            }
+        }
+        call.visit_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(linter: &Linter, code: &str, filename: &str) -> Vec<LintDiagnostic> {
+        let parser = JavaScriptParser::new();
+        linter.lint_code(&parser, code, Some(filename)).unwrap()
+    }
+
+    fn rules(diagnostics: &[LintDiagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let linter = Linter::new();
+        let code = r#"
+import React, { useState, useEffect } from 'react';
+import unused from 'lodash';
+
+export function List({ items, open }) {
+    if (open) {
+        useEffect(() => {});
+    }
+    debugger;
+    const [count] = useState(0);
+    const _ignored = 1;
+    let stale = count == 1;
+    return <ul>{items.map(item => <li>{item}</li>)}</ul>;
+}
+
+function helper() {
+    return useState(1);
+}
+"#;
+        let diagnostics = lint(&linter, code, "list.jsx");
+
+        assert_eq!(
+            rules(&diagnostics),
+            vec![
+                "no-unused-vars",
+                "react-hooks/rules-of-hooks",
+                "no-debugger",
+                "no-unused-vars",
+                "eqeqeq",
+                "react/jsx-key",
+                "no-unused-vars",
+                "react-hooks/rules-of-hooks",
+            ]
+        );
+        assert!(diagnostics[0].message.contains("'unused'"));
+        assert!(diagnostics[1].message.contains("called conditionally"));
+        assert_eq!((diagnostics[2].start_line, diagnostics[2].start_column), (9, 5));
+        assert_eq!(diagnostics[4].severity, Severity::Warning);
+        assert!(diagnostics[6].message.contains("'helper'"));
+        assert!(diagnostics[7].message.contains("\"helper\""));
+    }
+
+    #[test]
+    fn test_unused_vars_uses_scopes() {
+        let linter = Linter::new();
+        let code = r#"
+const shadowed = 1;
+function read(list) {
+    const shadowed = list.length;
+    return shadowed;
+}
+read([]);
+"#;
+        let diagnostics = lint(&linter, code, "scopes.js");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].start_line, 2);
+        assert_eq!(diagnostics[0].message, "'shadowed' is assigned a value but never used.");
+    }
+
+    #[test]
+    fn test_config_levels() {
+        let config = LintConfig::from_json(
+            r#"{"rules": {"no-debugger": "off", "eqeqeq": ["error", "always"], "react/jsx-key": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.rules["eqeqeq"], RuleLevel::Error);
+        assert_eq!(config.rules["react/jsx-key"], RuleLevel::Warn);
+
+        let linter = Linter::new().config(config).unwrap();
+        let diagnostics = lint(&linter, "debugger; if (a != b) {}", "levels.js");
+        assert_eq!(rules(&diagnostics), vec!["eqeqeq"]);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+
+        assert!(Linter::new().config(LintConfig::new().rule("no-such-rule", RuleLevel::Warn)).is_err());
+        assert!(LintConfig::from_json(r#"{"rules": {"eqeqeq": "loud"}}"#).is_err());
+    }
+}
@@ -1,6 +1,11 @@
-use js_processor::actix_integration;
+use js_processor::{actix_integration, cli};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    actix_integration::start_server().await
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("fmt") => std::process::exit(cli::fmt(&args[1..])),
+        Some("lint") => std::process::exit(cli::lint(&args[1..])),
+        _ => actix_integration::start_server().await,
+    }
 }
@@ -0,0 +1,245 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use std::fmt;
+use std::io::Write;
+use swc_common::comments::{Comment, Comments, SingleThreadedComments};
+use swc_common::sync::Lrc;
+use swc_common::{Mark, SourceMap};
+use swc_ecma_ast::Program;
+use swc_ecma_minifier::optimize;
+use swc_ecma_minifier::option::{
+    CompressOptions, ExtraOptions, MangleOptions, MinifyOptions as SwcMinifyOptions,
+    TopLevelOptions,
+};
+use swc_ecma_transforms_base::fixer::fixer;
+use swc_ecma_transforms_base::hygiene::hygiene;
+use swc_ecma_visit::VisitMutWith;
+
+/// Production minification settings for [`crate::react_compiler::ReactCompiler::with_minify`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinifyOptions {
+    /// Constant folding, dead-code removal and dropping of unused `/*#__PURE__*/` calls
+    pub compress: bool,
+    /// Shortening of local names
+    pub mangle: bool,
+    /// Whether module-level bindings are compressed and mangled too
+    ///
+    /// Safe for every [`crate::module_format::ModuleFormat`]: ES modules and CommonJS
+    /// have module scope, AMD, UMD and SystemJS output declares them inside a factory
+    /// function, and the IIFE wrapper and the UMD global names are added after
+    /// minification. Only output evaluated as a classic script, whose top level is
+    /// the global scope, can lose names other scripts read.
+    pub top_level: bool,
+    /// Whether `console.*` calls are removed
+    pub drop_console: bool,
+}
+
+impl MinifyOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    pub fn mangle(mut self, mangle: bool) -> Self {
+        self.mangle = mangle;
+        self
+    }
+
+    pub fn top_level(mut self, top_level: bool) -> Self {
+        self.top_level = top_level;
+        self
+    }
+
+    pub fn drop_console(mut self, drop_console: bool) -> Self {
+        self.drop_console = drop_console;
+        self
+    }
+
+    #[allow(deprecated)] // `MangleOptions::safari10` has no `Default` to fall back on
+    fn to_swc(&self) -> SwcMinifyOptions {
+        SwcMinifyOptions {
+            compress: self.compress.then(|| CompressOptions {
+                drop_console: self.drop_console,
+                top_level: Some(TopLevelOptions {
+                    functions: self.top_level,
+                }),
+                ..Default::default()
+            }),
+            mangle: self.mangle.then(|| MangleOptions {
+                props: None,
+                top_level: Some(self.top_level),
+                keep_class_names: false,
+                keep_fn_names: false,
+                keep_private_props: false,
+                ie8: false,
+                safari10: false,
+                reserved: Vec::new(),
+                eval: false,
+                disable_char_freq: false,
+            }),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for MinifyOptions {
+    fn default() -> Self {
+        Self {
+            compress: true,
+            mangle: true,
+            top_level: true,
+            drop_console: false,
+        }
+    }
+}
+
+/// Runs the minifier over a program that went through `resolver` with the given marks
+///
+/// `/*#__PURE__*/` annotations are read from `comments`. Afterwards only license
+/// comments (`/*! ... */`, `@license`, `@preserve`) are left in the store.
+pub(crate) fn minify_program(
+    program: Program,
+    source_map: Lrc<SourceMap>,
+    comments: &SingleThreadedComments,
+    options: &MinifyOptions,
+    unresolved_mark: Mark,
+    top_level_mark: Mark,
+) -> Program {
+    let mut program = optimize(
+        program,
+        source_map,
+        Some(comments as &dyn Comments),
+        None,
+        &options.to_swc(),
+        &ExtraOptions {
+            unresolved_mark,
+            top_level_mark,
+            mangle_name_cache: None,
+        },
+    );
+    if !options.mangle {
+        program.visit_mut_with(&mut hygiene());
+    }
+    program.visit_mut_with(&mut fixer(Some(comments as &dyn Comments)));
+
+    let (mut leading, mut trailing) = comments.borrow_all_mut();
+    for map in [&mut *leading, &mut *trailing] {
+        map.retain(|_, list| {
+            list.retain(is_license_comment);
+            !list.is_empty()
+        });
+    }
+    program
+}
+
+fn is_license_comment(comment: &Comment) -> bool {
+    comment.text.starts_with('!')
+        || comment.text.contains("@license")
+        || comment.text.contains("@preserve")
+}
+
+/// Raw and gzip-compressed byte counts of a piece of output code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeReport {
+    pub raw_bytes: usize,
+    pub gzip_bytes: usize,
+}
+
+impl SizeReport {
+    /// Measures `code`, compressing it at the default gzip level like most servers do
+    pub fn measure(code: &str) -> Self {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        // writing to a `Vec` cannot fail
+        let gzip_bytes = encoder
+            .write_all(code.as_bytes())
+            .and_then(|_| encoder.finish())
+            .map(|compressed| compressed.len())
+            .unwrap_or_default();
+
+        Self {
+            raw_bytes: code.len(),
+            gzip_bytes,
+        }
+    }
+}
+
+impl fmt::Display for SizeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} B ({} B gzip)", self.raw_bytes, self.gzip_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::react_compiler::ReactCompiler;
+
+    const COMPONENT: &str = r#"/*! my-lib v1.0 | MIT */
+import React from 'react';
+
+// Greets the current user
+const unused = /*#__PURE__*/ createStore();
+
+function formatGreeting(greetingName: string) {
+    if (false) {
+        console.log('never');
+    }
+    return 'Hello, ' + greetingName;
+}
+
+export default function Greeting({ name }: { name: string }) {
+    return <div className="greeting">{formatGreeting(name)}</div>;
+}
+"#;
+
+    #[test]
+    fn test_minify_removes_dead_code_and_comments() {
+        let plain = ReactCompiler::new()
+            .compile_react_component(COMPONENT, Some("greeting.tsx"))
+            .unwrap();
+        let minified = ReactCompiler::new()
+            .with_minify(MinifyOptions::new())
+            .compile_react_component(COMPONENT, Some("greeting.tsx"))
+            .unwrap();
+
+        assert!(minified.len() < plain.len());
+        assert!(minified.starts_with("/*! my-lib v1.0 | MIT */"));
+        assert!(!minified.contains("Greets the current user"));
+        assert!(!minified.contains("createStore"));
+        assert!(!minified.contains("never"));
+        assert!(!minified.contains("formatGreeting"));
+        assert!(!minified.contains("greetingName"));
+        assert!(minified.contains("\"greeting\""));
+    }
+
+    #[test]
+    fn test_minify_without_mangle_keeps_names() {
+        let minified = ReactCompiler::new()
+            .with_minify(MinifyOptions::new().mangle(false).compress(false))
+            .compile_react_component(COMPONENT, Some("greeting.tsx"))
+            .unwrap();
+
+        assert!(minified.contains("createStore"));
+        assert!(minified.contains("formatGreeting"));
+        assert!(!minified.trim_end().contains('\n'));
+    }
+
+    #[test]
+    fn test_size_report() {
+        let code = "const value = 1;\n".repeat(100);
+        let report = SizeReport::measure(&code);
+
+        assert_eq!(report.raw_bytes, code.len());
+        assert!(report.gzip_bytes > 0 && report.gzip_bytes < report.raw_bytes);
+        assert_eq!(
+            report.to_string(),
+            format!("{} B ({} B gzip)", report.raw_bytes, report.gzip_bytes)
+        );
+    }
+}
@@ -0,0 +1,462 @@
+use crate::resolver::ResolveKind;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use swc_atoms::Atom;
+use swc_common::comments::Comments;
+use swc_common::sync::Lrc;
+use swc_common::{FileName, Mark, SourceMap};
+use swc_ecma_ast::{
+    ArrayLit, AssignTarget, CallExpr, Callee, ComputedPropName, Expr, ExprOrSpread, Ident, Lit,
+    MemberExpr, MemberProp, Module, ModuleItem, Pass, Program, SimpleAssignTarget, Stmt,
+};
+use swc_ecma_parser::{Syntax, parse_file_as_module};
+use swc_ecma_transforms_module::path::Resolver;
+use swc_ecma_transforms_module::util::{Config as ModuleConfig, ImportInterop};
+use swc_ecma_transforms_module::rewriter::import_rewriter;
+use swc_ecma_transforms_module::{amd, common_js, system_js, umd};
+use swc_ecma_visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+
+/// How CommonJS output imports ES modules compiled to CommonJS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interop {
+    /// Babel-compatible: `default` is `module.exports` unless it has `__esModule`
+    #[default]
+    Babel,
+    /// Node-compatible: `default` is always `module.exports`
+    Node,
+    /// `default` is read from `module.exports.default` as is
+    None,
+}
+
+/// Module system compiled code is emitted in
+///
+/// `globals` map import specifiers to the global variables a plain `<script>`
+/// finds them in, such as `react` to `React`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleFormat {
+    /// `import` and `export` are kept
+    Esm,
+    /// `require()` calls and `exports` properties
+    CommonJs { interop: Interop },
+    /// CommonJS, AMD, or a browser global named `global_name`
+    Umd {
+        global_name: String,
+        globals: BTreeMap<String, String>,
+    },
+    /// A `define()` call, named when `module_id` is set
+    Amd { module_id: Option<String> },
+    /// A `System.register()` call
+    SystemJs,
+    /// A self-executing function for a `<script>` tag, assigning the exports to
+    /// `global_name` when set
+    Iife {
+        global_name: Option<String>,
+        globals: BTreeMap<String, String>,
+    },
+}
+
+impl ModuleFormat {
+    pub fn common_js() -> Self {
+        Self::CommonJs {
+            interop: Interop::Babel,
+        }
+    }
+
+    pub fn umd(global_name: &str) -> Self {
+        Self::Umd {
+            global_name: global_name.to_string(),
+            globals: BTreeMap::new(),
+        }
+    }
+
+    pub fn iife(global_name: Option<&str>) -> Self {
+        Self::Iife {
+            global_name: global_name.map(str::to_string),
+            globals: BTreeMap::new(),
+        }
+    }
+
+    /// Reads imports of `specifier` from the global `global` in UMD and IIFE builds
+    pub fn global(mut self, specifier: &str, global: &str) -> Self {
+        if let Self::Umd { globals, .. } | Self::Iife { globals, .. } = &mut self {
+            globals.insert(specifier.to_string(), global.to_string());
+        }
+        self
+    }
+
+    fn config(&self) -> ModuleConfig {
+        let import_interop = match self {
+            Self::CommonJs { interop } => match interop {
+                Interop::Babel => ImportInterop::Swc,
+                Interop::Node => ImportInterop::Node,
+                Interop::None => ImportInterop::None,
+            },
+            _ => ImportInterop::Swc,
+        };
+
+        ModuleConfig {
+            import_interop: Some(import_interop),
+            ..Default::default()
+        }
+    }
+
+    /// The `swc` module config the format is built on; IIFEs are CommonJS wrapped by
+    /// [`Self::finish`]
+    pub(crate) fn swc_config(&self) -> swc::config::ModuleConfig {
+        use swc::config::ModuleConfig as SwcModuleConfig;
+
+        match self {
+            Self::Esm => SwcModuleConfig::Es6(Default::default()),
+            Self::CommonJs { .. } | Self::Iife { .. } => SwcModuleConfig::CommonJs(self.config()),
+            Self::Umd { .. } => SwcModuleConfig::Umd(umd::Config {
+                globals: Default::default(),
+                config: self.config(),
+            }),
+            Self::Amd { module_id } => SwcModuleConfig::Amd(amd::Config {
+                module_id: module_id.clone(),
+                config: self.config(),
+            }),
+            Self::SystemJs => SwcModuleConfig::SystemJs(system_js::Config {
+                allow_top_level_this: false,
+                config: self.config(),
+            }),
+        }
+    }
+
+    /// How the output loads its imports, which selects the `package.json`
+    /// conditions they resolve with
+    pub(crate) fn resolve_kind(&self) -> ResolveKind {
+        match self {
+            Self::Esm | Self::SystemJs => ResolveKind::Import,
+            Self::CommonJs { .. } | Self::Umd { .. } | Self::Amd { .. } | Self::Iife { .. } => {
+                ResolveKind::Require
+            }
+        }
+    }
+
+    /// Turns the `import`s and `export`s of a resolved program into this format
+    pub(crate) fn transform(
+        &self,
+        program: Program,
+        source_map: Lrc<SourceMap>,
+        comments: &dyn Comments,
+        resolver: Resolver,
+        unresolved_mark: Mark,
+    ) -> Program {
+        match self {
+            Self::Esm => match resolver {
+                Resolver::Real { base, resolver } => program.apply(import_rewriter(base, resolver)),
+                Resolver::Default => program,
+            },
+            Self::CommonJs { .. } | Self::Iife { .. } => program.apply(common_js(
+                resolver,
+                unresolved_mark,
+                self.config(),
+                Default::default(),
+            )),
+            Self::Umd { .. } => program.apply(umd(
+                source_map,
+                resolver,
+                unresolved_mark,
+                umd::Config {
+                    globals: Default::default(),
+                    config: self.config(),
+                },
+                Default::default(),
+            )),
+            Self::Amd { module_id } => program.apply(amd(
+                resolver,
+                unresolved_mark,
+                amd::Config {
+                    module_id: module_id.clone(),
+                    config: self.config(),
+                },
+                Default::default(),
+                Some(comments),
+            )),
+            Self::SystemJs => program.apply(system_js(
+                resolver,
+                unresolved_mark,
+                system_js::Config {
+                    allow_top_level_this: false,
+                    config: self.config(),
+                },
+            )),
+        }
+    }
+
+    /// Applies the global names and the IIFE wrapper once every other pass ran
+    pub(crate) fn finish(&self, program: &mut Program) {
+        match self {
+            Self::Umd {
+                global_name,
+                globals,
+            } => program.visit_mut_with(&mut UmdGlobals {
+                global_name,
+                globals,
+                dependencies: Vec::new(),
+            }),
+            Self::Iife {
+                global_name,
+                globals,
+            } => {
+                if let Program::Module(module) = program {
+                    wrap_iife(module, global_name.as_deref(), globals);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// [`Self::finish`] as a pass to run after `swc`'s own
+    pub(crate) fn finish_pass(&self) -> impl Pass + '_ {
+        swc_ecma_ast::fn_pass(|program| self.finish(program))
+    }
+}
+
+impl Default for ModuleFormat {
+    fn default() -> Self {
+        Self::common_js()
+    }
+}
+
+/// Renames the globals of the UMD wrapper, which `swc` derives from file names
+///
+/// The browser branch calls `factory(global.<name> = {}, global.<dep>, ...)` with
+/// the dependencies in the order of the `define(["exports", "<dep>", ...])` list.
+struct UmdGlobals<'a> {
+    global_name: &'a str,
+    globals: &'a BTreeMap<String, String>,
+    dependencies: Vec<String>,
+}
+
+impl VisitMut for UmdGlobals<'_> {
+    fn visit_mut_module(&mut self, module: &mut Module) {
+        // the wrapper is the last top-level statement; only its callee is visited,
+        // so calls in the factory are left alone
+        if let Some(ModuleItem::Stmt(Stmt::Expr(stmt))) = module.body.last_mut()
+            && let Expr::Call(wrapper) = &mut *stmt.expr
+            && let Callee::Expr(callee) = &mut wrapper.callee
+        {
+            let mut define = DefineDependencies::default();
+            callee.visit_with(&mut define);
+            self.dependencies = define
+                .dependencies
+                .into_iter()
+                .filter(|dep| !matches!(dep.as_str(), "exports" | "require" | "module"))
+                .collect();
+            callee.visit_mut_with(self);
+        }
+    }
+
+    fn visit_mut_call_expr(&mut self, call: &mut CallExpr) {
+        call.visit_mut_children_with(self);
+        let is_factory_call = !call.args.is_empty()
+            && call
+                .args
+                .iter()
+                .all(|arg| matches!(&*arg.expr, Expr::Member(_) | Expr::Assign(_)));
+        if !is_factory_call {
+            return;
+        }
+
+        let mut args = call.args.iter_mut().peekable();
+        if let Some(ExprOrSpread { expr, .. }) = args.peek_mut()
+            && let Expr::Assign(assign) = &mut **expr
+        {
+            if let AssignTarget::Simple(SimpleAssignTarget::Member(member)) = &mut assign.left {
+                rename_member(member, self.global_name);
+            }
+            args.next();
+        }
+        for (arg, dependency) in args.zip(&self.dependencies) {
+            if let Expr::Member(member) = &mut *arg.expr
+                && let Some(global) = self.globals.get(dependency)
+            {
+                rename_member(member, global);
+            }
+        }
+    }
+}
+
+/// Points `global.<dep>` at `name`, as `global["<name>"]` when it is not an
+/// identifier, like `my-lib` or `MyOrg.Lib`
+fn rename_member(member: &mut MemberExpr, name: &str) {
+    if let MemberProp::Ident(prop) = &mut member.prop {
+        if Ident::verify_symbol(name).is_ok() {
+            prop.sym = Atom::from(name);
+        } else {
+            member.prop = MemberProp::Computed(ComputedPropName {
+                span: prop.span,
+                expr: Box::new(Expr::Lit(name.into())),
+            });
+        }
+    }
+}
+
+/// The string list of the `define()` call in a UMD wrapper
+#[derive(Default)]
+struct DefineDependencies {
+    dependencies: Vec<String>,
+}
+
+impl Visit for DefineDependencies {
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        call.visit_children_with(self);
+        if let Callee::Expr(callee) = &call.callee
+            && let Expr::Ident(callee) = &**callee
+            && callee.sym == "define"
+            && let Some(ExprOrSpread { expr, .. }) = call.args.first()
+            && let Expr::Array(ArrayLit { elems, .. }) = &**expr
+        {
+            self.dependencies = elems
+                .iter()
+                .flatten()
+                .filter_map(|elem| match &*elem.expr {
+                    Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+                    _ => None,
+                })
+                .collect();
+        }
+    }
+}
+
+/// Wraps CommonJS output in `var <name> = function(exports, require) { ... }({}, ...)`
+/// where `require` reads imports from the globals named in `globals`, or else
+/// from the global named like the specifier
+///
+/// A `global_name` that is not an identifier is assigned as `globalThis["<name>"]`.
+fn wrap_iife(module: &mut Module, global_name: Option<&str>, globals: &BTreeMap<String, String>) {
+    let globals = serde_json::to_string(globals).unwrap_or_else(|_| "{}".to_string());
+    let head = match global_name {
+        Some(name) if Ident::verify_symbol(name).is_ok() => format!("var {name} = "),
+        Some(name) => format!("globalThis[{}] = ", serde_json::Value::from(name)),
+        None => String::new(),
+    };
+    let template = format!(
+        "{head}(function(exports, require) {{\n    return exports;\n}})({{}}, function(name) {{\n    \
+         return globalThis[{globals}[name] || name];\n}});\n"
+    );
+
+    let source_map = SourceMap::default();
+    let file = source_map.new_source_file(
+        Arc::new(FileName::Internal("iife-wrapper.js".to_string())),
+        template,
+    );
+    let Ok(mut wrapper) = parse_file_as_module(
+        &file,
+        Syntax::default(),
+        Default::default(),
+        None,
+        &mut Vec::new(),
+    ) else {
+        return;
+    };
+
+    let mut body: Vec<Stmt> = std::mem::take(&mut module.body)
+        .into_iter()
+        .filter_map(|item| item.stmt())
+        .collect();
+    let mut insert = InsertBody { body: Some(&mut body) };
+    wrapper.visit_mut_with(&mut insert);
+    module.body = wrapper.body;
+}
+
+/// Moves statements in front of the first `return` of the wrapper
+struct InsertBody<'a> {
+    body: Option<&'a mut Vec<Stmt>>,
+}
+
+impl VisitMut for InsertBody<'_> {
+    fn visit_mut_stmts(&mut self, stmts: &mut Vec<Stmt>) {
+        if let Some(position) = stmts.iter().position(|stmt| matches!(stmt, Stmt::Return(_)))
+            && let Some(body) = self.body.take()
+        {
+            stmts.splice(position..position, body.drain(..));
+            return;
+        }
+        stmts.visit_mut_children_with(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_loader::transpile_to;
+    use crate::target::TargetOptions;
+    use swc_ecma_ast::EsVersion;
+    use crate::react_compiler::ReactCompiler;
+
+    const COMPONENT: &str = r#"import React from 'react';
+
+export const greeting = 'Hello';
+
+export default function Greeting() {
+    return <p>{greeting}</p>;
+}
+"#;
+
+    fn compile(module_format: ModuleFormat) -> String {
+        ReactCompiler::new()
+            .with_module_format(module_format)
+            .compile_react_component(COMPONENT, Some("greeting.tsx"))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_esm_and_commonjs_interop() {
+        let esm = compile(ModuleFormat::Esm);
+        assert!(esm.contains("import React from 'react';"));
+        assert!(esm.contains("export default function Greeting()"));
+
+        let babel = compile(ModuleFormat::common_js());
+        assert!(babel.contains("_interop_require_default(require(\"react\"))"));
+
+        let none = compile(ModuleFormat::CommonJs {
+            interop: Interop::None,
+        });
+        assert!(none.contains("var _react = require(\"react\");"));
+        assert!(!none.contains("_interop_require_default"));
+    }
+
+    #[test]
+    fn test_umd_amd_and_systemjs() {
+        let umd = compile(ModuleFormat::umd("Greeting").global("react", "React"));
+        assert!(umd.contains("factory(exports, require(\"react\"))"));
+        assert!(umd.contains("factory(global.Greeting = {}, global.React)"));
+
+        // names that are not identifiers are bracketed rather than emitted as invalid JS
+        let umd = compile(ModuleFormat::umd("greeting-lib").global("react", "My.React"));
+        assert!(umd.contains(r#"factory(global["greeting-lib"] = {}, global["My.React"])"#));
+
+        let amd = compile(ModuleFormat::Amd {
+            module_id: Some("greeting".to_string()),
+        });
+        assert!(amd.contains("define(\"greeting\", ["));
+
+        let system = compile(ModuleFormat::SystemJs);
+        assert!(system.starts_with("System.register(["));
+        assert!(system.contains("_export(\"greeting\", greeting = 'Hello')"));
+    }
+
+    #[test]
+    fn test_iife() {
+        let iife = compile(ModuleFormat::iife(Some("Greeting")).global("react", "React"));
+        assert!(iife.starts_with("var Greeting = (function(exports, require) {\n    \"use strict\";"));
+        assert!(iife.contains("    return exports;\n})({}, function(name) {"));
+        assert!(iife.contains("\"react\": \"React\""));
+
+        let iife = compile(ModuleFormat::iife(Some("greeting-lib")));
+        assert!(iife.starts_with("globalThis[\"greeting-lib\"] = (function(exports, require) {"));
+
+        let transpiled = transpile_to(
+            COMPONENT,
+            "greeting.tsx",
+            &ModuleFormat::iife(None),
+            &TargetOptions::es_version(EsVersion::Es5),
+        )
+        .unwrap();
+        assert!(transpiled.starts_with("(function(exports, require) {"));
+        assert!(transpiled.contains("require(\"react\")"));
+    }
+}
@@ -0,0 +1,709 @@
+use crate::js_parser::JavaScriptParser;
+use crate::resolver::{NodeResolver, ResolveKind, Resolved, is_relative};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use swc_common::{SourceMap, Span};
+use swc_ecma_ast::*;
+use swc_ecma_visit::{Visit, VisitWith};
+
+/// Extensions tried, in order, for specifiers without one
+pub const RESOLVE_EXTENSIONS: &[&str] = &["tsx", "ts", "jsx", "js", "mjs", "cjs", "json"];
+
+/// How a module refers to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportKind {
+    /// `import ... from "x"` or `import "x"`
+    Static,
+    /// `export ... from "x"`
+    ReExport,
+    /// `import("x")`
+    Dynamic,
+    /// `require("x")` or `import x = require("x")`
+    Require,
+}
+
+/// A reference to another module found in a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRecord {
+    pub specifier: String,
+    pub kind: ImportKind,
+    /// Erased by TypeScript, e.g. `import type { A } from "x"`
+    pub type_only: bool,
+    /// 1-based line of the import
+    pub line: usize,
+}
+
+/// Where an import points to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "value")]
+pub enum ImportTarget {
+    /// A file in the project
+    File(PathBuf),
+    /// A package from `node_modules`, a Node built-in, or a bare specifier
+    /// that matches no installed package
+    External(String),
+    /// A relative or `#` specifier that matches no file
+    Unresolved,
+}
+
+/// An import together with what it resolved to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    #[serde(flatten)]
+    pub import: ImportRecord,
+    pub target: ImportTarget,
+}
+
+/// A file of the graph and its outgoing imports
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModuleNode {
+    pub dependencies: Vec<Dependency>,
+    /// Why the file could not be read or parsed, if it could not
+    pub error: Option<String>,
+}
+
+/// The import graph of the files reachable from a set of entrypoints
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModuleGraph {
+    entries: BTreeSet<PathBuf>,
+    modules: BTreeMap<PathBuf, ModuleNode>,
+    #[serde(skip)]
+    resolver: Arc<NodeResolver>,
+}
+
+impl ModuleGraph {
+    /// Parses the entrypoints and every project file they import, transitively
+    ///
+    /// Imports are resolved with `resolver`, so `tsconfig.json` aliases and
+    /// package `imports` lead to the same files the compiler picks; without one,
+    /// Node's defaults are used. Files that fail to parse stay in the graph with
+    /// their error and no dependencies; JSON and other non-script files are leaves.
+    pub fn build<P: AsRef<Path>>(
+        parser: &JavaScriptParser,
+        resolver: Option<Arc<NodeResolver>>,
+        entries: impl IntoIterator<Item = P>,
+    ) -> Self {
+        let mut graph = Self {
+            resolver: resolver.unwrap_or_default(),
+            ..Self::default()
+        };
+        let mut queue = VecDeque::new();
+
+        for entry in entries {
+            let entry = normalize_path(entry.as_ref());
+            graph.entries.insert(entry.clone());
+            queue.push_back(entry);
+        }
+
+        graph.scan_queue(parser, queue);
+        graph
+    }
+
+    /// Scans changed files again, dropping the ones that no longer exist
+    ///
+    /// Files the graph did not contain become entrypoints, and newly imported
+    /// files are added like in [`Self::build`].
+    pub fn update<P: AsRef<Path>>(
+        &mut self,
+        parser: &JavaScriptParser,
+        changed: impl IntoIterator<Item = P>,
+    ) {
+        let mut queue = VecDeque::new();
+        for path in changed {
+            let path = normalize_path(path.as_ref());
+            if path.exists() {
+                if self.modules.remove(&path).is_none() {
+                    self.entries.insert(path.clone());
+                }
+                queue.push_back(path);
+            } else {
+                self.entries.remove(&path);
+                self.modules.remove(&path);
+            }
+        }
+        self.scan_queue(parser, queue);
+    }
+
+    /// Scans the queued files that are not in the graph yet and the files they import
+    fn scan_queue(&mut self, parser: &JavaScriptParser, mut queue: VecDeque<PathBuf>) {
+        while let Some(path) = queue.pop_front() {
+            if self.modules.contains_key(&path) {
+                continue;
+            }
+            let node = self.scan_file(parser, &path);
+            for dependency in &node.dependencies {
+                if let ImportTarget::File(target) = &dependency.target
+                    && !self.modules.contains_key(target)
+                {
+                    queue.push_back(target.clone());
+                }
+            }
+            self.modules.insert(path, node);
+        }
+    }
+
+    /// Reads one file and resolves its imports
+    fn scan_file(&self, parser: &JavaScriptParser, path: &Path) -> ModuleNode {
+        if !is_script(path) {
+            return ModuleNode::default();
+        }
+
+        match parser.parse_file(path) {
+            Ok(module) => ModuleNode {
+                dependencies: collect_imports(parser.source_map(), &module)
+                    .into_iter()
+                    .map(|import| Dependency {
+                        target: self.resolve(path, &import),
+                        import,
+                    })
+                    .collect(),
+                error: None,
+            },
+            Err(err) => ModuleNode {
+                dependencies: vec![],
+                error: Some(err.to_string()),
+            },
+        }
+    }
+
+    /// Resolves an import of `from`, leaving packages and built-ins out of the graph
+    fn resolve(&self, from: &Path, import: &ImportRecord) -> ImportTarget {
+        let kind = match import.kind {
+            ImportKind::Require => ResolveKind::Require,
+            _ => ResolveKind::Import,
+        };
+        let from_dir = from.parent().unwrap_or(Path::new(""));
+        let specifier = &import.specifier;
+
+        match self.resolver.resolve(specifier, from_dir, kind) {
+            Ok(Resolved::File(path))
+                if !path.components().any(|c| c.as_os_str() == "node_modules") =>
+            {
+                ImportTarget::File(path)
+            }
+            Err(_) if is_relative(specifier) || specifier.starts_with('#') => {
+                ImportTarget::Unresolved
+            }
+            _ => ImportTarget::External(specifier.clone()),
+        }
+    }
+
+    /// The entrypoints the graph was built from
+    pub fn entries(&self) -> impl Iterator<Item = &Path> {
+        self.entries.iter().map(PathBuf::as_path)
+    }
+
+    /// Every file in the graph
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.modules.keys().map(PathBuf::as_path)
+    }
+
+    pub fn module(&self, path: &Path) -> Option<&ModuleNode> {
+        self.modules.get(&normalize_path(path))
+    }
+
+    /// Files directly imported by `path`
+    pub fn dependencies(&self, path: &Path) -> Vec<&Path> {
+        let mut files: Vec<&Path> = self
+            .module(path)
+            .into_iter()
+            .flat_map(|node| &node.dependencies)
+            .filter_map(|dependency| match &dependency.target {
+                ImportTarget::File(target) => Some(target.as_path()),
+                _ => None,
+            })
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Files that directly import `path`
+    pub fn dependents(&self, path: &Path) -> Vec<&Path> {
+        let path = normalize_path(path);
+        self.modules
+            .iter()
+            .filter(|(_, node)| {
+                node.dependencies
+                    .iter()
+                    .any(|d| matches!(&d.target, ImportTarget::File(target) if *target == path))
+            })
+            .map(|(file, _)| file.as_path())
+            .collect()
+    }
+
+    /// Files that import `path` directly or through other files
+    pub fn transitive_dependents(&self, path: &Path) -> BTreeSet<&Path> {
+        let mut seen = BTreeSet::new();
+        let mut queue = VecDeque::from([normalize_path(path)]);
+        while let Some(file) = queue.pop_front() {
+            for dependent in self.dependents(&file) {
+                if seen.insert(dependent) {
+                    queue.push_back(dependent.to_path_buf());
+                }
+            }
+        }
+        seen
+    }
+
+    /// Entrypoints whose output may change when `path` changes
+    pub fn affected_entries(&self, path: &Path) -> Vec<&Path> {
+        let path = normalize_path(path);
+        let dependents = self.transitive_dependents(&path);
+        self.entries
+            .iter()
+            .filter(|entry| **entry == path || dependents.contains(entry.as_path()))
+            .map(PathBuf::as_path)
+            .collect()
+    }
+
+    /// Groups of files that import each other, directly or indirectly
+    ///
+    /// Each cycle is a strongly connected component, listed in path order.
+    pub fn cycles(&self) -> Vec<Vec<&Path>> {
+        Tarjan::new(self).run()
+    }
+}
+
+/// Tarjan's strongly connected components over the file graph
+struct Tarjan<'g> {
+    graph: &'g ModuleGraph,
+    index: BTreeMap<&'g Path, usize>,
+    low_link: BTreeMap<&'g Path, usize>,
+    stack: Vec<&'g Path>,
+    on_stack: BTreeSet<&'g Path>,
+    cycles: Vec<Vec<&'g Path>>,
+}
+
+/// A file whose dependencies are being visited by [`Tarjan::connect`]
+struct Frame<'g> {
+    file: &'g Path,
+    dependencies: Vec<&'g Path>,
+    next: usize,
+}
+
+impl<'g> Tarjan<'g> {
+    fn new(graph: &'g ModuleGraph) -> Self {
+        Self {
+            graph,
+            index: BTreeMap::new(),
+            low_link: BTreeMap::new(),
+            stack: vec![],
+            on_stack: BTreeSet::new(),
+            cycles: vec![],
+        }
+    }
+
+    fn run(mut self) -> Vec<Vec<&'g Path>> {
+        for file in self.graph.files() {
+            if !self.index.contains_key(file) {
+                self.connect(file);
+            }
+        }
+        self.cycles
+    }
+
+    /// Visits the files reachable from `root` depth first
+    ///
+    /// The recursion is kept on the heap, so long import chains cannot
+    /// overflow the stack.
+    fn connect(&mut self, root: &'g Path) {
+        let mut calls = vec![self.visit(root)];
+
+        while let Some(frame) = calls.last_mut() {
+            let file = frame.file;
+            if let Some(&dependency) = frame.dependencies.get(frame.next) {
+                frame.next += 1;
+                if !self.index.contains_key(dependency) {
+                    calls.push(self.visit(dependency));
+                } else if self.on_stack.contains(dependency) {
+                    let low = self.low_link[file].min(self.index[dependency]);
+                    self.low_link.insert(file, low);
+                }
+                continue;
+            }
+
+            let frame = calls.pop().expect("the frame was just looked at");
+            if let Some(parent) = calls.last() {
+                let low = self.low_link[parent.file].min(self.low_link[file]);
+                self.low_link.insert(parent.file, low);
+            }
+            self.close(&frame);
+        }
+    }
+
+    fn visit(&mut self, file: &'g Path) -> Frame<'g> {
+        let index = self.index.len();
+        self.index.insert(file, index);
+        self.low_link.insert(file, index);
+        self.stack.push(file);
+        self.on_stack.insert(file);
+
+        Frame {
+            file,
+            dependencies: self.graph.dependencies(file),
+            next: 0,
+        }
+    }
+
+    /// Pops the component rooted at a file once all its dependencies are visited
+    fn close(&mut self, frame: &Frame<'g>) {
+        let file = frame.file;
+        if self.low_link[file] == self.index[file] {
+            let mut component = vec![];
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == file {
+                    break;
+                }
+            }
+            if component.len() > 1 || frame.dependencies.contains(&file) {
+                component.sort();
+                self.cycles.push(component);
+            }
+        }
+    }
+}
+
+/// Lists the imports, re-exports, `import()` and `require()` calls of a module
+///
+/// Only string-literal specifiers are collected.
+pub fn collect_imports(source_map: &SourceMap, module: &Module) -> Vec<ImportRecord> {
+    let mut collector = ImportCollector {
+        source_map,
+        imports: vec![],
+    };
+    module.visit_with(&mut collector);
+    collector.imports
+}
+
+struct ImportCollector<'a> {
+    source_map: &'a SourceMap,
+    imports: Vec<ImportRecord>,
+}
+
+impl ImportCollector<'_> {
+    fn push(&mut self, specifier: &str, kind: ImportKind, type_only: bool, span: Span) {
+        self.imports.push(ImportRecord {
+            specifier: specifier.to_string(),
+            kind,
+            type_only,
+            line: self.source_map.lookup_char_pos(span.lo).line,
+        });
+    }
+}
+
+impl Visit for ImportCollector<'_> {
+    fn visit_import_decl(&mut self, import: &ImportDecl) {
+        self.push(&import.src.value, ImportKind::Static, import.type_only, import.span);
+    }
+
+    fn visit_named_export(&mut self, export: &NamedExport) {
+        if let Some(src) = &export.src {
+            self.push(&src.value, ImportKind::ReExport, export.type_only, export.span);
+        }
+    }
+
+    fn visit_export_all(&mut self, export: &ExportAll) {
+        self.push(&export.src.value, ImportKind::ReExport, export.type_only, export.span);
+    }
+
+    fn visit_ts_import_equals_decl(&mut self, import: &TsImportEqualsDecl) {
+        if let TsModuleRef::TsExternalModuleRef(external) = &import.module_ref {
+            self.push(
+                &external.expr.value,
+                ImportKind::Require,
+                import.is_type_only,
+                import.span,
+            );
+        }
+    }
+
+    fn visit_call_expr(&mut self, call: &CallExpr) {
+        let kind = match &call.callee {
+            Callee::Import(_) => Some(ImportKind::Dynamic),
+            Callee::Expr(callee) => match &**callee {
+                Expr::Ident(ident) if &*ident.sym == "require" && call.args.len() == 1 => {
+                    Some(ImportKind::Require)
+                }
+                _ => None,
+            },
+            Callee::Super(_) => None,
+        };
+        let specifier = call.args.first().and_then(|arg| match &*arg.expr {
+            Expr::Lit(Lit::Str(s)) => Some(s.value.to_string()),
+            Expr::Tpl(tpl) if tpl.exprs.is_empty() => tpl
+                .quasis
+                .first()
+                .and_then(|q| q.cooked.as_ref())
+                .map(|cooked| cooked.to_string()),
+            _ => None,
+        });
+        if let (Some(kind), Some(specifier)) = (kind, specifier) {
+            self.push(&specifier, kind, false, call.span);
+        }
+
+        call.visit_children_with(self);
+    }
+}
+
+/// Applies file and directory fallbacks to a path
+///
+/// Tries the exact path, each of [`RESOLVE_EXTENSIONS`], TypeScript sources
+/// for `.js` paths and finally `index` files.
+pub fn resolve_file(base: &Path) -> Option<PathBuf> {
+    if base.is_file() {
+        return Some(base.to_path_buf());
+    }
+
+    let with_extension = |base: &Path, ext: &str| {
+        let mut path = base.as_os_str().to_owned();
+        path.push(".");
+        path.push(ext);
+        PathBuf::from(path)
+    };
+    if let Some(found) = RESOLVE_EXTENSIONS
+        .iter()
+        .map(|ext| with_extension(base, ext))
+        .find(|path| path.is_file())
+    {
+        return Some(found);
+    }
+
+    // TypeScript sources are imported by the name of their compiled output
+    let ts_sources: &[&str] = match base.extension().and_then(|ext| ext.to_str()) {
+        Some("js") => &["ts", "tsx"],
+        Some("jsx") => &["tsx"],
+        Some("mjs") => &["mts"],
+        Some("cjs") => &["cts"],
+        _ => &[],
+    };
+    if let Some(found) = ts_sources
+        .iter()
+        .map(|ext| base.with_extension(ext))
+        .find(|path| path.is_file())
+    {
+        return Some(found);
+    }
+
+    if base.is_dir() {
+        return RESOLVE_EXTENSIONS
+            .iter()
+            .map(|ext| base.join(format!("index.{ext}")))
+            .find(|path| path.is_file());
+    }
+
+    None
+}
+
+/// Whether a file is parsed for imports rather than treated as a leaf
+fn is_script(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts")
+    )
+}
+
+/// Canonicalizes existing paths and lexically cleans the others
+pub fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("js_processor_graph_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        for (file, code) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, code).unwrap();
+        }
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_collect_imports() {
+        let parser = JavaScriptParser::new();
+        let code = r#"
+            import React from "react";
+            import type { Props } from "./types";
+            export * from "./utils";
+            export { a } from "./a";
+            const lazy = () => import("./Lazy");
+            const fs = require("fs");
+        "#;
+
+        let module = parser.parse_code(code, Some("entry.tsx")).unwrap();
+        let imports = collect_imports(parser.source_map(), &module);
+
+        let summary: Vec<_> = imports
+            .iter()
+            .map(|i| (i.specifier.as_str(), i.kind, i.type_only))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("react", ImportKind::Static, false),
+                ("./types", ImportKind::Static, true),
+                ("./utils", ImportKind::ReExport, false),
+                ("./a", ImportKind::ReExport, false),
+                ("./Lazy", ImportKind::Dynamic, false),
+                ("fs", ImportKind::Require, false),
+            ]
+        );
+        assert_eq!(imports[0].line, 2);
+    }
+
+    #[test]
+    fn test_graph_dependents_and_cycles() {
+        let root = project(
+            "cycles",
+            &[
+                ("pages/home.tsx", "import { Button } from '../components';\nimport data from './data.json';"),
+                ("pages/about.tsx", "import React from 'react';\nimport { util } from '../util.js';"),
+                ("pages/data.json", "{}"),
+                ("components/index.ts", "export * from './Button';"),
+                ("components/Button.tsx", "import { util } from '../util';\nexport const Button = () => util();"),
+                ("util.ts", "export const util = () => require('./components/Button');"),
+            ],
+        );
+        let parser = JavaScriptParser::new();
+        let graph = ModuleGraph::build(
+            &parser,
+            None,
+            [root.join("pages/home.tsx"), root.join("pages/about.tsx")],
+        );
+
+        assert_eq!(graph.files().count(), 6);
+        assert_eq!(
+            graph.dependencies(&root.join("pages/home.tsx")),
+            vec![
+                root.join("components/index.ts").as_path(),
+                root.join("pages/data.json").as_path(),
+            ]
+        );
+
+        let about = graph.module(&root.join("pages/about.tsx")).unwrap();
+        assert_eq!(about.dependencies[0].target, ImportTarget::External("react".into()));
+        assert_eq!(
+            about.dependencies[1].target,
+            ImportTarget::File(root.join("util.ts"))
+        );
+
+        let button = root.join("components/Button.tsx");
+        assert_eq!(
+            graph.dependents(&button),
+            vec![root.join("components/index.ts").as_path(), root.join("util.ts").as_path()]
+        );
+        assert_eq!(graph.affected_entries(&button).len(), 2);
+        assert_eq!(
+            graph.affected_entries(&root.join("pages/data.json")),
+            vec![root.join("pages/home.tsx").as_path()]
+        );
+
+        assert_eq!(
+            graph.cycles(),
+            vec![vec![button.as_path(), root.join("util.ts").as_path()]]
+        );
+    }
+
+    #[test]
+    fn test_graph_resolves_with_the_project_resolver() {
+        let root = project(
+            "resolver",
+            &[
+                (
+                    "tsconfig.json",
+                    r#"{"compilerOptions": {"baseUrl": ".", "paths": {"@ui/*": ["src/ui/*"]}}}"#,
+                ),
+                ("package.json", r##"{"imports": {"#env": "./src/env.ts"}}"##),
+                (
+                    "src/app.ts",
+                    "import { Button } from '@ui/Button';\nimport env from '#env';\n\
+                     import fs from 'node:fs';\nimport React from 'react';\nimport '#missing';",
+                ),
+                ("src/ui/Button.tsx", "export const Button = 1;"),
+                ("src/env.ts", "export default {};"),
+                ("node_modules/react/package.json", r#"{"main": "index.js"}"#),
+                ("node_modules/react/index.js", "module.exports = {};"),
+            ],
+        );
+        let parser = JavaScriptParser::new();
+        let resolver = Arc::new(NodeResolver::for_project(&root).unwrap());
+        let graph = ModuleGraph::build(&parser, Some(resolver), [root.join("src/app.ts")]);
+
+        let app = graph.module(&root.join("src/app.ts")).unwrap();
+        let targets: Vec<_> = app.dependencies.iter().map(|d| d.target.clone()).collect();
+        assert_eq!(
+            targets,
+            vec![
+                ImportTarget::File(root.join("src/ui/Button.tsx")),
+                ImportTarget::File(root.join("src/env.ts")),
+                ImportTarget::External("node:fs".into()),
+                ImportTarget::External("react".into()),
+                ImportTarget::Unresolved,
+            ]
+        );
+        assert_eq!(graph.files().count(), 3);
+
+        let without = ModuleGraph::build(&parser, None, [root.join("src/app.ts")]);
+        let app = without.module(&root.join("src/app.ts")).unwrap();
+        assert_eq!(app.dependencies[0].target, ImportTarget::External("@ui/Button".into()));
+    }
+
+    #[test]
+    fn test_cycles_of_long_import_chains() {
+        let files: Vec<PathBuf> = (0..100_000)
+            .map(|i| PathBuf::from(format!("/chain/{i}.js")))
+            .collect();
+        let mut graph = ModuleGraph::default();
+        for (i, file) in files.iter().enumerate() {
+            let next = &files[(i + 1) % files.len()];
+            let dependency = Dependency {
+                import: ImportRecord {
+                    specifier: next.display().to_string(),
+                    kind: ImportKind::Static,
+                    type_only: false,
+                    line: 1,
+                },
+                target: ImportTarget::File(next.clone()),
+            };
+            graph.modules.insert(
+                file.clone(),
+                ModuleNode {
+                    dependencies: vec![dependency],
+                    error: None,
+                },
+            );
+        }
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].len(), files.len());
+    }
+}
@@ -0,0 +1,499 @@
+use crate::create_script_origin;
+use crate::module_format::ModuleFormat;
+use crate::react_compiler::ReactCompiler;
+use crate::resolver::{NodeResolver, ResolveKind, Resolved};
+use crate::target::{Target, TargetOptions};
+use anyhow::Result;
+use ssr_rs::v8;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use swc::Compiler;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::errors::Handler;
+use swc_common::sync::Lrc;
+use swc_common::{SourceMap, GLOBALS};
+use swc_ecma_ast::noop_pass;
+use swc_ecma_parser::{Syntax, TsSyntax};
+
+/// Loads the files that scripts `import` and `require` inside an isolate
+///
+/// The loader lives in an isolate slot, where the V8 callbacks find it.
+/// Specifiers go through a [`NodeResolver`]; modules that cannot be found
+/// throw instead of evaluating to an empty object.
+pub struct ModuleLoader {
+    resolver: Arc<NodeResolver>,
+    compiler: ReactCompiler,
+    /// Directory top-level `require` and `import` resolve from
+    root: PathBuf,
+    /// File of each compiled ES module
+    module_paths: RefCell<ModuleMap<PathBuf>>,
+    modules: RefCell<HashMap<PathBuf, v8::Global<v8::Module>>>,
+    /// `module.exports` of each loaded CommonJS file
+    exports: RefCell<HashMap<PathBuf, v8::Global<v8::Value>>>,
+    /// Values of synthetic modules waiting to be evaluated
+    pending_synthetic: RefCell<ModuleMap<v8::Global<v8::Value>>>,
+}
+
+/// Values keyed by module
+///
+/// Identity hashes are not unique, so each hash holds the modules sharing it.
+struct ModuleMap<T> {
+    buckets: HashMap<i32, Vec<(v8::Global<v8::Module>, T)>>,
+}
+
+impl<T> Default for ModuleMap<T> {
+    fn default() -> Self {
+        Self {
+            buckets: HashMap::new(),
+        }
+    }
+}
+
+impl<T> ModuleMap<T> {
+    fn insert(&mut self, scope: &mut v8::HandleScope, module: v8::Local<v8::Module>, value: T) {
+        let bucket = self
+            .buckets
+            .entry(module.get_identity_hash().get())
+            .or_default();
+        bucket.retain(|(key, _)| *key != module);
+        bucket.push((v8::Global::new(scope, module), value));
+    }
+
+    fn get(&self, module: v8::Local<v8::Module>) -> Option<&T> {
+        self.buckets
+            .get(&module.get_identity_hash().get())?
+            .iter()
+            .find(|(key, _)| *key == module)
+            .map(|(_, value)| value)
+    }
+
+    fn remove(&mut self, module: v8::Local<v8::Module>) -> Option<T> {
+        let hash = module.get_identity_hash().get();
+        let bucket = self.buckets.get_mut(&hash)?;
+        let index = bucket.iter().position(|(key, _)| *key == module)?;
+        let (_, value) = bucket.swap_remove(index);
+        if bucket.is_empty() {
+            self.buckets.remove(&hash);
+        }
+        Some(value)
+    }
+}
+
+impl ModuleLoader {
+    pub fn new(resolver: Arc<NodeResolver>, root: impl Into<PathBuf>) -> Self {
+        Self {
+            compiler: ReactCompiler::new().with_resolver(resolver.clone()),
+            resolver,
+            root: root.into(),
+            module_paths: Default::default(),
+            modules: RefCell::new(HashMap::new()),
+            exports: RefCell::new(HashMap::new()),
+            pending_synthetic: Default::default(),
+        }
+    }
+
+    /// A loader for the current directory, using its `tsconfig.json` if there is one
+    pub fn for_current_dir() -> Self {
+        let root = std::env::current_dir().unwrap_or_default();
+        let resolver = NodeResolver::for_project(&root).unwrap_or_default();
+        Self::new(Arc::new(resolver), root)
+    }
+
+    /// Makes the loader available to the module callbacks of `isolate`
+    pub fn install(self, isolate: &mut v8::Isolate) {
+        isolate.set_slot(Rc::new(self));
+    }
+
+    /// The loader installed in `isolate`, installing one for the current directory if needed
+    pub fn get(isolate: &mut v8::Isolate) -> Rc<Self> {
+        if let Some(loader) = isolate.get_slot::<Rc<Self>>() {
+            return loader.clone();
+        }
+        let loader = Rc::new(Self::for_current_dir());
+        isolate.set_slot(loader.clone());
+        loader
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Evaluates `require(specifier)` for a file in `dir`
+    pub fn require<'s>(
+        &self,
+        scope: &mut v8::HandleScope<'s>,
+        specifier: &str,
+        dir: &Path,
+    ) -> Result<v8::Local<'s, v8::Value>, String> {
+        match self.resolver.resolve(specifier, dir, ResolveKind::Require) {
+            Ok(Resolved::File(path)) => self.load_commonjs(scope, &path),
+            Ok(Resolved::Builtin(name)) => crate::mock_module(scope, &name)
+                .map(Into::into)
+                .ok_or_else(|| format!("Node built-in module '{name}' is not available")),
+            Err(err) => crate::mock_module(scope, specifier)
+                .map(Into::into)
+                .ok_or_else(|| err.to_string()),
+        }
+    }
+
+    /// Compiles the module `import`ed as `specifier` from a file in `dir`
+    ///
+    /// CommonJS files, JSON and built-in mocks become synthetic modules with a
+    /// `default` export and one export per property.
+    pub fn import<'s>(
+        &self,
+        scope: &mut v8::HandleScope<'s>,
+        specifier: &str,
+        dir: &Path,
+    ) -> Result<v8::Local<'s, v8::Module>, String> {
+        let path = match self.resolver.resolve(specifier, dir, ResolveKind::Import) {
+            Ok(Resolved::File(path)) => path,
+            Ok(Resolved::Builtin(name)) => {
+                let value = crate::mock_module(scope, &name)
+                    .ok_or_else(|| format!("Node built-in module '{name}' is not available"))?;
+                return Ok(self.synthetic_module(scope, &name, value.into()));
+            }
+            Err(err) => {
+                let value = crate::mock_module(scope, specifier).ok_or_else(|| err.to_string())?;
+                return Ok(self.synthetic_module(scope, specifier, value.into()));
+            }
+        };
+
+        if let Some(module) = self.modules.borrow().get(&path) {
+            return Ok(v8::Local::new(scope, module));
+        }
+
+        let module = if self.resolver.is_esm(&path) {
+            self.compile_esm(scope, &path)?
+        } else {
+            let exports = self.load_commonjs(scope, &path)?;
+            self.synthetic_module(scope, &path.to_string_lossy(), exports)
+        };
+        self.modules
+            .borrow_mut()
+            .insert(path, v8::Global::new(scope, module));
+        Ok(module)
+    }
+
+    fn compile_esm<'s>(
+        &self,
+        scope: &mut v8::HandleScope<'s>,
+        path: &Path,
+    ) -> Result<v8::Local<'s, v8::Module>, String> {
+        let filename = path.to_string_lossy();
+        let code = std::fs::read_to_string(path).map_err(|e| format!("{filename}: {e}"))?;
+        let code = transpile(&code, &filename).map_err(|e| format!("{filename}: {e}"))?;
+
+        let source = v8::String::new(scope, &code).ok_or("module source is too large")?;
+        let try_catch = &mut v8::TryCatch::new(scope);
+        let origin = create_script_origin(try_catch, &filename, true);
+        let mut source = v8::script_compiler::Source::new(source, Some(&origin));
+        let module = v8::script_compiler::compile_module(try_catch, &mut source)
+            .ok_or_else(|| exception_message(try_catch))?;
+
+        self.module_paths
+            .borrow_mut()
+            .insert(try_catch, module, path.to_path_buf());
+        Ok(module)
+    }
+
+    /// Runs a CommonJS file once and returns its `module.exports`
+    fn load_commonjs<'s>(
+        &self,
+        scope: &mut v8::HandleScope<'s>,
+        path: &Path,
+    ) -> Result<v8::Local<'s, v8::Value>, String> {
+        if let Some(exports) = self.exports.borrow().get(path) {
+            return Ok(v8::Local::new(scope, exports));
+        }
+
+        let filename = path.to_string_lossy();
+        let code = std::fs::read_to_string(path).map_err(|e| format!("{filename}: {e}"))?;
+
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let json = v8::String::new(scope, &code).ok_or("JSON file is too large")?;
+            let try_catch = &mut v8::TryCatch::new(scope);
+            let value =
+                v8::json::parse(try_catch, json).ok_or_else(|| exception_message(try_catch))?;
+            self.exports
+                .borrow_mut()
+                .insert(path.to_path_buf(), v8::Global::new(try_catch, value));
+            return Ok(value);
+        }
+
+        let code = if is_package_file(path) && !is_typescript(path) {
+            code
+        } else {
+            self.compiler
+                .compile_react_component(&code, Some(&filename))
+                .map_err(|e| format!("{filename}: {e}"))?
+        };
+
+        let wrapped = format!(
+            "(function (exports, require, module, __filename, __dirname) {{{code}\n}})"
+        );
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        let source = v8::String::new(scope, &wrapped).ok_or("module source is too large")?;
+        let try_catch = &mut v8::TryCatch::new(scope);
+        let origin = create_script_origin(try_catch, &filename, false);
+        let function = v8::Script::compile(try_catch, source, Some(&origin))
+            .and_then(|script| script.run(try_catch))
+            .ok_or_else(|| exception_message(try_catch))?;
+        let function = v8::Local::<v8::Function>::try_from(function)
+            .map_err(|_| "module wrapper is not a function".to_string())?;
+
+        let module = v8::Object::new(try_catch);
+        let exports: v8::Local<v8::Value> = v8::Object::new(try_catch).into();
+        let exports_key = v8::String::new(try_catch, "exports").unwrap();
+        module.set(try_catch, exports_key.into(), exports);
+
+        // cache the unfinished exports so require cycles terminate, as in Node
+        self.exports
+            .borrow_mut()
+            .insert(path.to_path_buf(), v8::Global::new(try_catch, exports));
+
+        let require = require_function(try_catch, dir);
+        let filename_value = v8::String::new(try_catch, &filename).unwrap();
+        let dirname_value = v8::String::new(try_catch, &dir.to_string_lossy()).unwrap();
+        let receiver = v8::undefined(try_catch).into();
+        let called = function.call(
+            try_catch,
+            receiver,
+            &[
+                exports,
+                require.into(),
+                module.into(),
+                filename_value.into(),
+                dirname_value.into(),
+            ],
+        );
+        if called.is_none() {
+            self.exports.borrow_mut().remove(path);
+            return Err(exception_message(try_catch));
+        }
+
+        let exports = module
+            .get(try_catch, exports_key.into())
+            .unwrap_or_else(|| v8::undefined(try_catch).into());
+        self.exports
+            .borrow_mut()
+            .insert(path.to_path_buf(), v8::Global::new(try_catch, exports));
+        Ok(exports)
+    }
+
+    /// Wraps a value in a module exporting it as `default` and by property
+    fn synthetic_module<'s>(
+        &self,
+        scope: &mut v8::HandleScope<'s>,
+        name: &str,
+        value: v8::Local<'s, v8::Value>,
+    ) -> v8::Local<'s, v8::Module> {
+        let export_names: Vec<_> = std::iter::once("default".to_string())
+            .chain(own_keys(scope, value).into_iter().filter(|key| key != "default"))
+            .map(|key| v8::String::new(scope, &key).unwrap())
+            .collect();
+        let name = v8::String::new(scope, name).unwrap();
+
+        let module = v8::Module::create_synthetic_module(
+            scope,
+            name,
+            &export_names,
+            evaluate_synthetic_module,
+        );
+        let value = v8::Global::new(scope, value);
+        self.pending_synthetic.borrow_mut().insert(scope, module, value);
+        module
+    }
+
+    /// Directory the imports of `module` resolve from
+    fn module_dir(&self, module: v8::Local<v8::Module>) -> PathBuf {
+        self.module_paths
+            .borrow()
+            .get(module)
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| self.root.clone())
+    }
+}
+
+/// V8 callback resolving the `import`s of ES modules through the installed [`ModuleLoader`]
+pub fn resolve_module_callback<'a>(
+    context: v8::Local<'a, v8::Context>,
+    specifier: v8::Local<'a, v8::String>,
+    _import_attributes: v8::Local<'a, v8::FixedArray>,
+    referrer: v8::Local<'a, v8::Module>,
+) -> Option<v8::Local<'a, v8::Module>> {
+    let scope = &mut unsafe { v8::CallbackScope::new(context) };
+    let specifier = specifier.to_rust_string_lossy(scope);
+    let loader = ModuleLoader::get(scope);
+    let dir = loader.module_dir(referrer);
+
+    match loader.import(scope, &specifier, &dir) {
+        Ok(module) => Some(module),
+        Err(message) => {
+            throw_error(scope, &message);
+            None
+        }
+    }
+}
+
+fn evaluate_synthetic_module<'a>(
+    context: v8::Local<'a, v8::Context>,
+    module: v8::Local<'a, v8::Module>,
+) -> Option<v8::Local<'a, v8::Value>> {
+    let scope = &mut unsafe { v8::CallbackScope::new(context) };
+    let loader = ModuleLoader::get(scope);
+    let value = loader
+        .pending_synthetic
+        .borrow_mut()
+        .remove(module)?;
+    let value = v8::Local::new(scope, value);
+
+    let default = v8::String::new(scope, "default").unwrap();
+    module.set_synthetic_module_export(scope, default, value)?;
+    if let Ok(object) = v8::Local::<v8::Object>::try_from(value) {
+        for key in own_keys(scope, value).into_iter().filter(|key| key != "default") {
+            let name = v8::String::new(scope, &key).unwrap();
+            let export = object
+                .get(scope, name.into())
+                .unwrap_or_else(|| v8::undefined(scope).into());
+            module.set_synthetic_module_export(scope, name, export)?;
+        }
+    }
+    Some(v8::undefined(scope).into())
+}
+
+/// A `require` function resolving relative to `dir`
+pub fn require_function<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    dir: &Path,
+) -> v8::Local<'s, v8::Function> {
+    let dir = v8::String::new(scope, &dir.to_string_lossy()).unwrap();
+    v8::Function::builder(module_require_callback)
+        .data(dir.into())
+        .build(scope)
+        .unwrap()
+}
+
+#[allow(clippy::needless_pass_by_value)] // this function should follow the callback type
+fn module_require_callback(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    mut retval: v8::ReturnValue,
+) {
+    let specifier = args.get(0).to_rust_string_lossy(scope);
+    let dir = PathBuf::from(args.data().to_rust_string_lossy(scope));
+    let loader = ModuleLoader::get(scope);
+
+    match loader.require(scope, &specifier, &dir) {
+        Ok(exports) => retval.set(exports),
+        Err(message) => throw_error(scope, &message),
+    }
+}
+
+/// Throws a JavaScript `Error` with `message`
+pub fn throw_error(scope: &mut v8::HandleScope, message: &str) {
+    let message = v8::String::new(scope, message).unwrap();
+    let error = v8::Exception::error(scope, message);
+    scope.throw_exception(error);
+}
+
+fn exception_message(try_catch: &mut v8::TryCatch<v8::HandleScope>) -> String {
+    try_catch
+        .exception()
+        .map(|exception| exception.to_rust_string_lossy(try_catch))
+        .unwrap_or_else(|| "module evaluation failed".to_string())
+}
+
+/// Own enumerable string keys of an object, empty for other values
+fn own_keys(scope: &mut v8::HandleScope, value: v8::Local<v8::Value>) -> Vec<String> {
+    let Ok(object) = v8::Local::<v8::Object>::try_from(value) else {
+        return vec![];
+    };
+    let Some(names) = object.get_own_property_names(scope, Default::default()) else {
+        return vec![];
+    };
+    let mut keys = vec![];
+    for i in 0..names.length() {
+        if let Some(name) = names.get_index(scope, i).filter(|name| name.is_string()) {
+            keys.push(name.to_rust_string_lossy(scope));
+        }
+    }
+    keys
+}
+
+pub(crate) fn is_package_file(path: &Path) -> bool {
+    path.components().any(|c| c.as_os_str() == "node_modules")
+}
+
+pub(crate) fn is_typescript(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("ts" | "tsx" | "mts" | "cts")
+    )
+}
+
+/// Compiles TypeScript/JSX to ES5, keeping ES module syntax
+pub fn transpile(source: &str, filename: &str) -> Result<String> {
+    transpile_to(
+        source,
+        filename,
+        &ModuleFormat::Esm,
+        &TargetOptions::es_version(swc_ecma_ast::EsVersion::Es5),
+    )
+}
+
+/// Compiles TypeScript/JSX for `target` in the given module format
+pub fn transpile_to(
+    source: &str,
+    filename: &str,
+    module_format: &ModuleFormat,
+    target: &TargetOptions,
+) -> Result<String> {
+    let env = target.env_config()?;
+    let cm: Lrc<SourceMap> = Default::default();
+    let handler = Handler::with_emitter_writer(Box::new(std::io::stderr()), Some(cm.clone()));
+    let compiler = Compiler::new(cm.clone());
+    let fm = cm.new_source_file(
+        swc_common::FileName::Custom(filename.into()).into(),
+        source.to_string(),
+    );
+
+    GLOBALS.set(&Default::default(), || {
+        compiler.process_js_with_custom_pass(
+            fm,
+            None,
+            &handler,
+            &swc::config::Options {
+                config: swc::config::Config {
+                    jsc: swc::config::JscConfig {
+                        syntax: Some(Syntax::Typescript(TsSyntax {
+                            tsx: true,
+                            decorators: true,
+                            dts: false,
+                            no_early_errors: false,
+                            disallow_ambiguous_jsx_like: true,
+                        })),
+                        target: match &target.target {
+                            Target::EsVersion(version) => Some(*version),
+                            Target::Browsers(_) => None,
+                        },
+                        ..Default::default()
+                    },
+                    env,
+                    module: Some(module_format.swc_config()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            SingleThreadedComments::default(),
+            |_| noop_pass(),
+            |_| module_format.finish_pass(),
+        )
+    })
+    .map(|output| output.code)
+}
@@ -3,12 +3,12 @@ use crate::send_wrapper::SendWrapper;
 use ssr_rs::v8;
 use std::collections::HashMap;
 use std::convert::TryFrom;
-use swc::Compiler;
-use swc_common::errors::{ColorConfig, Handler};
-use swc_common::sync::Lrc;
-use swc_common::SourceMap;
-use swc_ecma_parser::Syntax;
-use swc_ecma_parser::TsSyntax;
+use crate::module_format::ModuleFormat;
+use crate::module_loader::{transpile_to, ModuleLoader};
+use crate::execute_script::SCRIPT_NAME;
+use crate::runtime_error::{JsError, RuntimeError};
+use crate::target::TargetOptions;
+use swc_ecma_ast::EsVersion;
 
 impl<'s, 'i> JsHttpRequestProcessor<'s, 'i>
 where
@@ -19,7 +19,42 @@ where
         isolate_scope: &'i mut v8::HandleScope<'s, ()>,
         source: v8::Local<'s, v8::String>,
         options: HashMap<String, String>,
-    ) -> Self {
+    ) -> Result<Self, RuntimeError> {
+        Self::with_module_format(isolate_scope, source, options, &ModuleFormat::Esm)
+    }
+
+    /// Creates a processor whose script is compiled to `module_format` first
+    ///
+    /// `Process` is looked up as a global, on `module.exports` for CommonJS and
+    /// UMD, and on the global an IIFE is assigned to. AMD and SystemJS output
+    /// need a module loader, which the processor doesn't provide.
+    pub fn with_module_format(
+        isolate_scope: &'i mut v8::HandleScope<'s, ()>,
+        source: v8::Local<'s, v8::String>,
+        options: HashMap<String, String>,
+        module_format: &ModuleFormat,
+    ) -> Result<Self, RuntimeError> {
+        Self::with_target(
+            isolate_scope,
+            source,
+            options,
+            module_format,
+            &TargetOptions::es_version(EsVersion::Es5),
+        )
+    }
+
+    /// Creates a processor whose script is compiled to `module_format` and
+    /// downleveled for `target`, ES5 with the other constructors
+    pub fn with_target(
+        isolate_scope: &'i mut v8::HandleScope<'s, ()>,
+        source: v8::Local<'s, v8::String>,
+        options: HashMap<String, String>,
+        module_format: &ModuleFormat,
+        target: &TargetOptions,
+    ) -> Result<Self, RuntimeError> {
+        // make sure `require` and `import` have a loader to resolve with
+        ModuleLoader::get(isolate_scope);
+
         let global = v8::ObjectTemplate::new(isolate_scope);
         global.set(
             v8::String::new(isolate_scope, "log").unwrap().into(),
@@ -51,6 +86,7 @@ where
             process_fn: None,
             request_template,
             _map_template: None,
+            requests: Default::default(),
         };
 
         // loads options and output
@@ -71,60 +107,74 @@ where
         );
 
         // execute script
-        let cm: Lrc<SourceMap> = Default::default();
-        let handler = Handler::with_emitter_writer(
-            Box::new(std::io::stderr()),
-            Some(cm.clone()),
-        );
-        let compiler = Compiler::new(cm.clone());
-        let fm = cm.new_source_file(
-            swc_common::FileName::Custom("in.js".into()).into(),
-            source.to_rust_string_lossy(&mut *self_.context_scope),
-        );
-        let transformed = compiler
-            .process_js_file(
-                fm,
-                &handler,
-                &swc::config::Options {
-                    config: swc::config::Config {
-                        jsc: swc::config::JscConfig {
-                            syntax: Some(Syntax::Typescript(TsSyntax {
-                                tsx: true,
-                                decorators: true,
-                                dts: false,
-                                no_early_errors: false,
-                                disallow_ambiguous_jsx_like: true,
-                            })),
-                            target: Some(swc_ecma_ast::EsVersion::Es5),
-                            ..Default::default()
-                        },
-                        module: Some(swc::config::ModuleConfig::Es6(Default::default())),
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-            )
-            .unwrap();
+        let source = source.to_rust_string_lossy(&mut *self_.context_scope);
+        let transformed = transpile_to(&source, SCRIPT_NAME, module_format, target)
+            .map_err(|e| RuntimeError::transpile(&e, SCRIPT_NAME))?;
 
         let transformed_source =
-            v8::String::new(&mut *self_.context_scope, &transformed.code).unwrap();
-        if transformed.code.contains("import") || transformed.code.contains("export") {
-            self_.execute_module(transformed_source);
-        } else {
-            self_.execute_script(transformed_source);
-        }
+            v8::String::new(&mut *self_.context_scope, &transformed).unwrap();
+        match module_format {
+            ModuleFormat::Esm
+                if transformed.contains("import") || transformed.contains("export") =>
+            {
+                self_.execute_module(transformed_source)?
+            }
+            ModuleFormat::Esm => self_.execute_script(transformed_source)?,
+            ModuleFormat::Amd { .. } | ModuleFormat::SystemJs => {
+                return Err(RuntimeError::Instantiate(JsError {
+                    script_name: Some(SCRIPT_NAME.to_string()),
+                    ..JsError::new(format!("{module_format:?} output needs a module loader"))
+                }));
+            }
+            _ => {
+                // `module` and `exports` for CommonJS and the CommonJS branch of UMD
+                let scope = &mut *self_.context_scope;
+                let module = v8::Object::new(scope);
+                let exports = v8::Object::new(scope);
+                let module_str = v8::String::new(scope, "module").unwrap();
+                let exports_str = v8::String::new(scope, "exports").unwrap();
+                module.set(scope, exports_str.into(), exports.into());
+                let global = self_.context.global(scope);
+                global.set(scope, module_str.into(), module.into());
+                global.set(scope, exports_str.into(), exports.into());
 
-        let process_str = v8::String::new(&mut *self_.context_scope, "Process").unwrap();
-        let process_fn = self_
-            .context
-            .global(&mut *self_.context_scope)
-            .get(&mut *self_.context_scope, process_str.into())
-            .expect("missing function Process");
+                self_.execute_script(transformed_source)?
+            }
+        }
 
-        let process_fn =
-            v8::Local::<v8::Function>::try_from(process_fn).expect("function expected");
+        let exports_path: &[&str] = match module_format {
+            ModuleFormat::Iife {
+                global_name: Some(name),
+                ..
+            } => &[name.as_str()],
+            _ => &["module", "exports"],
+        };
+        let process_fn = [&[][..], exports_path]
+            .into_iter()
+            .find_map(|path| self_.lookup_global(path, "Process"))
+            .and_then(|process_fn| v8::Local::<v8::Function>::try_from(process_fn).ok())
+            .ok_or_else(|| {
+                RuntimeError::MissingEntrypoint(JsError {
+                    script_name: Some(SCRIPT_NAME.to_string()),
+                    ..JsError::new("function Process is not defined")
+                })
+            })?;
         self_.process_fn = Some(process_fn);
 
-        self_
+        Ok(self_)
+    }
+
+    /// The function `name` on the object at `path` from the global object
+    fn lookup_global(&mut self, path: &[&str], name: &str) -> Option<v8::Local<'s, v8::Value>> {
+        let scope = &mut *self.context_scope;
+        let mut object = self.context.global(scope);
+        for key in path {
+            let key = v8::String::new(scope, key)?;
+            object = object.get(scope, key.into())?.to_object(scope)?;
+        }
+        let name = v8::String::new(scope, name)?;
+        object
+            .get(scope, name.into())
+            .filter(|value| value.is_function())
     }
 }
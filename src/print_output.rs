@@ -27,7 +27,7 @@ where
             let key = key.to_rust_string_lossy(scope);
             let value = value.to_rust_string_lossy(scope);
 
-            println!("foss {key}: {value}");
+            println!("{key}: {value}");
         }
     }
 }
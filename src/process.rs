@@ -1,33 +1,45 @@
 use ssr_rs::v8;
 use super::JsHttpRequestProcessor;
+use crate::processor_response::ProcessorResponse;
+use crate::runtime_error::{JsError, RuntimeError};
 use crate::ssr::http_request::SimpleHttpRequest;
 
 impl<'s, 'i> JsHttpRequestProcessor<'s, 'i>
 where
     's: 'i,
 {
-    /// Processes the given HTTP request.
-    pub fn process<R>(&mut self, request: R)
+    /// Processes the given HTTP request, returning the response the script
+    /// wrote to `output`.
+    ///
+    /// Each call starts with an empty `output`.
+    pub fn process<R>(&mut self, request: R) -> Result<ProcessorResponse, RuntimeError>
     where
         R: SimpleHttpRequest + 'static,
     {
-        let request: Box<dyn SimpleHttpRequest> = Box::new(request);
-        let request = self.wrap_request(request);
-
+        // the wrapper lives in this call's scope, so it can be collected afterwards
         let scope = &mut v8::HandleScope::new(&mut *self.context_scope);
+        let request =
+            Self::wrap_request(scope, &self.request_template, &self.requests, Box::new(request));
         let try_catch = &mut v8::TryCatch::new(scope);
 
-        let process_fn = self.process_fn.as_mut().unwrap();
-        let global = self.context.global(try_catch).into();
+        let process_fn = self.process_fn.as_mut().ok_or_else(|| {
+            RuntimeError::MissingEntrypoint(JsError::new("function Process is not defined"))
+        })?;
+        let global = self.context.global(try_catch);
+        let output_str: v8::Local<v8::Value> = v8::String::new(try_catch, "output").unwrap().into();
+        let output = v8::Object::new(try_catch);
+        global.set(try_catch, output_str, output.into());
 
         if process_fn
-            .call(try_catch, global, &[request.into()][..])
+            .call(try_catch, global.into(), &[request.into()][..])
             .is_none()
         {
-            let exception = try_catch.exception().unwrap();
-            let exception_string = exception.to_rust_string_lossy(try_catch);
-
-            panic!("{exception_string}");
+            return Err(RuntimeError::Exception(JsError::from_try_catch(try_catch)));
         }
+        // the script may have replaced `output`
+        let output = global
+            .get(try_catch, output_str)
+            .ok_or_else(|| RuntimeError::Response(JsError::from_try_catch(try_catch)))?;
+        ProcessorResponse::from_output(try_catch, output)
     }
 }
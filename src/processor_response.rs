@@ -0,0 +1,208 @@
+use crate::runtime_error::{JsError, RuntimeError};
+use actix_web::HttpResponse;
+use actix_web::http::StatusCode;
+use actix_web::http::header::{CONTENT_TYPE, HeaderName, HeaderValue};
+use ssr_rs::v8;
+
+/// The response a `Process` call wrote to `output`
+///
+/// `output.status` defaults to 200 and `output.headers` to none. `output.body`
+/// may be a string, a `Uint8Array` or any other typed array, or a value
+/// `JSON.stringify` accepts, which also defaults the `content-type` to JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessorResponse {
+    pub status: u16,
+    /// Header names and values in the order they were set, with repeats
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl ProcessorResponse {
+    /// Reads and validates the `output` object
+    ///
+    /// Exceptions thrown while reading it, by a getter or a `Proxy`, are
+    /// reported as invalid responses.
+    pub(crate) fn from_output(
+        scope: &mut v8::TryCatch<v8::HandleScope>,
+        output: v8::Local<v8::Value>,
+    ) -> Result<Self, RuntimeError> {
+        let output = v8::Local::<v8::Object>::try_from(output)
+            .map_err(|_| invalid("output is not an object"))?;
+
+        let status = match get(scope, output, "status")? {
+            None => 200,
+            Some(status) => match status.number_value(scope) {
+                Some(number)
+                    if status.is_number()
+                        && number.fract() == 0.0
+                        && (100.0..=999.0).contains(&number) =>
+                {
+                    number as u16
+                }
+                _ => {
+                    return Err(invalid(format!(
+                        "output.status must be an integer from 100 to 999, got {}",
+                        status.to_rust_string_lossy(scope)
+                    )));
+                }
+            },
+        };
+
+        let mut headers = Vec::new();
+        if let Some(value) = get(scope, output, "headers")? {
+            let object = v8::Local::<v8::Object>::try_from(value)
+                .map_err(|_| invalid("output.headers must be an object"))?;
+            let names = object
+                .get_own_property_names(scope, v8::GetPropertyNamesArgsBuilder::new().build())
+                .ok_or_else(|| thrown(scope))?;
+            for i in 0..names.length() {
+                let key = names.get_index(scope, i).ok_or_else(|| thrown(scope))?;
+                let name = key.to_rust_string_lossy(scope);
+                let value = object.get(scope, key).ok_or_else(|| thrown(scope))?;
+                let values = match v8::Local::<v8::Array>::try_from(value) {
+                    Ok(array) => (0..array.length())
+                        .map(|i| array.get_index(scope, i).ok_or_else(|| thrown(scope)))
+                        .collect::<Result<_, _>>()?,
+                    Err(_) => vec![value],
+                };
+                for value in values {
+                    headers.push(header(scope, &name, value)?);
+                }
+            }
+        }
+
+        let body = match get(scope, output, "body")? {
+            None => Vec::new(),
+            Some(body) if body.is_string() => body.to_rust_string_lossy(scope).into_bytes(),
+            Some(body) if body.is_array_buffer_view() => {
+                let view = v8::Local::<v8::ArrayBufferView>::try_from(body).unwrap();
+                let mut bytes = vec![0; view.byte_length()];
+                view.copy_contents(&mut bytes);
+                bytes
+            }
+            Some(body) => {
+                let json = v8::json::stringify(scope, body)
+                    .filter(|json| json.is_string())
+                    .map(|json| json.to_rust_string_lossy(scope));
+                let Some(json) = json else {
+                    let reason = match scope.exception() {
+                        Some(exception) => exception.to_rust_string_lossy(scope),
+                        None => "it has no JSON representation".to_string(),
+                    };
+                    return Err(invalid(format!(
+                        "output.body could not be serialized: {reason}"
+                    )));
+                };
+                if !headers
+                    .iter()
+                    .any(|(name, _)| name.eq_ignore_ascii_case(CONTENT_TYPE.as_str()))
+                {
+                    headers.push((CONTENT_TYPE.to_string(), "application/json".to_string()));
+                }
+                json.into_bytes()
+            }
+        };
+
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// The first value of the header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl From<ProcessorResponse> for HttpResponse {
+    /// Headers that are not valid HTTP are skipped; responses read from
+    /// `output` never have any
+    fn from(response: ProcessorResponse) -> Self {
+        let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
+        let mut builder = HttpResponse::build(status);
+        for (name, value) in response.headers {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_str(&value),
+            ) {
+                builder.append_header((name, value));
+            }
+        }
+        builder.body(response.body)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> RuntimeError {
+    RuntimeError::Response(JsError::new(message))
+}
+
+/// The exception thrown while reading `output`
+fn thrown(scope: &mut v8::TryCatch<v8::HandleScope>) -> RuntimeError {
+    RuntimeError::Response(JsError::from_try_catch(scope))
+}
+
+/// A property of `object`, or `None` when it is `undefined` or `null`
+fn get<'t>(
+    scope: &mut v8::TryCatch<v8::HandleScope<'t>>,
+    object: v8::Local<v8::Object>,
+    name: &str,
+) -> Result<Option<v8::Local<'t, v8::Value>>, RuntimeError> {
+    let key = v8::String::new(scope, name).unwrap();
+    let value = object.get(scope, key.into()).ok_or_else(|| thrown(scope))?;
+    Ok(Some(value).filter(|value| !value.is_null_or_undefined()))
+}
+
+/// A header whose value is a string or a number
+fn header(
+    scope: &mut v8::HandleScope,
+    name: &str,
+    value: v8::Local<v8::Value>,
+) -> Result<(String, String), RuntimeError> {
+    if HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(invalid(format!("invalid header name `{name}`")));
+    }
+    if !value.is_string() && !value.is_number() {
+        return Err(invalid(format!(
+            "header `{name}` must be a string, a number or an array of them"
+        )));
+    }
+    let value = value.to_rust_string_lossy(scope);
+    if HeaderValue::from_str(&value).is_err() {
+        return Err(invalid(format!("invalid value for header `{name}`")));
+    }
+    Ok((name.to_string(), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::MessageBody;
+
+    #[test]
+    fn test_into_http_response() {
+        let response = ProcessorResponse {
+            status: 201,
+            headers: vec![
+                ("Set-Cookie".to_string(), "a=1".to_string()),
+                ("set-cookie".to_string(), "b=2".to_string()),
+                ("bad header".to_string(), "skipped".to_string()),
+            ],
+            body: b"created".to_vec(),
+        };
+        assert_eq!(response.header("SET-COOKIE"), Some("a=1"));
+
+        let response = HttpResponse::from(response);
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers().get_all("set-cookie").count(), 2);
+        assert_eq!(response.headers().len(), 2);
+        assert_eq!(
+            response.into_body().try_into_bytes().unwrap().as_ref(),
+            b"created"
+        );
+    }
+}
@@ -1,22 +1,142 @@
+use crate::compile_cache::{CacheEntry, CompileCache, Dependency};
+use crate::define::Define;
+use crate::diagnostics::{ParseDiagnostic, Severity};
+use crate::js_parser::JavaScriptParser;
+use crate::minify::{MinifyOptions, minify_program};
+use crate::module_format::ModuleFormat;
+use crate::module_graph::{ImportKind, collect_imports, normalize_path};
+use crate::module_loader::is_package_file;
+use crate::target::TargetOptions;
+use crate::source_maps::{
+    CompileOutput, SourceMapConfig, SourceMaps, input_source_map, output_file_name,
+    source_mapping_url, strip_source_mapping_urls,
+};
+use crate::resolver::{NodeImportResolver, NodeResolver, ResolveKind, Resolved};
 use anyhow::Result;
 use swc_ecma_transforms_module::path::Resolver;
 use std::sync::Arc;
 use swc_atoms::Atom;
 use swc_common::sync::Lrc;
 use swc_common::{
-    FileName, GLOBALS, Globals, Mark, SourceMap, comments::NoopComments, errors::Handler,
+    FileName, GLOBALS, Globals, Mark, SourceMap,
+    comments::{Comments, SingleThreadedComments},
+    errors::Handler,
 };
 use swc_ecma_ast::EsVersion;
-use swc_ecma_codegen::{Emitter, text_writer::JsWriter};
+use swc_ecma_transforms_base::fixer::fixer;
+use swc_ecma_transforms_base::helpers::{HELPERS, Helpers, inject_helpers};
+use swc_ecma_transforms_base::hygiene::hygiene;
+use swc_ecma_codegen::text_writer::{JsWriter, WriteJs, omit_trailing_semi};
+use swc_ecma_codegen::Emitter;
 use swc_ecma_parser::{Parser, StringInput, Syntax, TsSyntax, lexer::Lexer};
-use swc_ecma_transforms_module::common_js::{common_js};
-use swc_ecma_transforms_react::{Options as ReactOptions, Runtime as ReactRuntime, react};
-use swc_ecma_transforms_typescript::strip;
+use swc_ecma_transforms_react::{
+    Options as ReactOptions, RefreshOptions, Runtime as ReactRuntime, react, refresh,
+};
+use swc_ecma_transforms_typescript::{Config as TsConfig, TsxConfig, tsx};
 use std::borrow::Cow;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Which functions JSX compiles to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsxRuntime {
+    /// Calls to the pragma, `React.createElement` unless configured otherwise
+    #[default]
+    Classic,
+    /// Calls imported from `<import_source>/jsx-runtime`, so components need no
+    /// `React` in scope
+    Automatic,
+}
+
+/// JSX transform settings for [`ReactCompiler::with_jsx`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsxOptions {
+    pub runtime: JsxRuntime,
+    /// Package the automatic runtime is imported from, such as `preact` or `@emotion/react`
+    pub import_source: String,
+    /// Element factory of the classic runtime
+    pub pragma: String,
+    /// Fragment component of the classic runtime
+    pub pragma_frag: String,
+    /// Emits `jsxDEV` calls, or `__source` and `__self` props with the classic runtime
+    pub development: bool,
+}
+
+impl JsxOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The automatic runtime imported from `react`
+    pub fn automatic() -> Self {
+        Self::default().runtime(JsxRuntime::Automatic)
+    }
+
+    pub fn runtime(mut self, runtime: JsxRuntime) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub fn import_source(mut self, import_source: &str) -> Self {
+        self.import_source = import_source.to_string();
+        self
+    }
+
+    pub fn pragma(mut self, pragma: &str) -> Self {
+        self.pragma = pragma.to_string();
+        self
+    }
+
+    pub fn pragma_frag(mut self, pragma_frag: &str) -> Self {
+        self.pragma_frag = pragma_frag.to_string();
+        self
+    }
+
+    pub fn development(mut self, development: bool) -> Self {
+        self.development = development;
+        self
+    }
+
+    fn to_swc(&self) -> ReactOptions {
+        ReactOptions {
+            runtime: Some(match self.runtime {
+                JsxRuntime::Classic => ReactRuntime::Classic,
+                JsxRuntime::Automatic => ReactRuntime::Automatic,
+            }),
+            pragma: Some(Arc::new(self.pragma.clone())),
+            pragma_frag: Some(Arc::new(self.pragma_frag.clone())),
+            import_source: Some(Atom::from(self.import_source.as_str())),
+            development: Some(self.development),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for JsxOptions {
+    fn default() -> Self {
+        Self {
+            runtime: JsxRuntime::Classic,
+            import_source: "react".to_string(),
+            pragma: "React.createElement".to_string(),
+            pragma_frag: "React.Fragment".to_string(),
+            development: false,
+        }
+    }
+}
 
 pub struct ReactCompiler {
     source_map: Lrc<SourceMap>,
     handler: Handler,
+    resolver: Option<Arc<NodeResolver>>,
+    minify: Option<MinifyOptions>,
+    source_maps: SourceMaps,
+    jsx: JsxOptions,
+    module_format: ModuleFormat,
+    cache: Option<Arc<CompileCache>>,
+    refresh: bool,
+    define: Define,
+    target: TargetOptions,
 }
 
 impl ReactCompiler {
@@ -28,16 +148,186 @@ impl ReactCompiler {
         Self {
             source_map,
             handler,
+            resolver: None,
+            minify: None,
+            source_maps: SourceMaps::None,
+            jsx: JsxOptions::default(),
+            module_format: ModuleFormat::default(),
+            cache: None,
+            refresh: false,
+            define: Define::default(),
+            target: TargetOptions::default(),
         }
     }
 
+    /// Rewrites imports of project files to the paths `resolver` finds for them
+    ///
+    /// Without a resolver, specifiers are emitted as written.
+    pub fn with_resolver(mut self, resolver: Arc<NodeResolver>) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// The resolver set with [`Self::with_resolver`]
+    pub fn resolver(&self) -> Option<&Arc<NodeResolver>> {
+        self.resolver.as_ref()
+    }
+
+    /// Minifies the output for production, keeping only license comments
+    pub fn with_minify(mut self, options: MinifyOptions) -> Self {
+        self.minify = Some(options);
+        self
+    }
+
+    /// Generates a source map for each compiled file, composed with any map the
+    /// source already links to
+    pub fn with_source_maps(mut self, source_maps: SourceMaps) -> Self {
+        self.source_maps = source_maps;
+        self
+    }
+
+    /// Sets the JSX runtime, pragma and development mode
+    ///
+    /// `@jsx`, `@jsxFrag`, `@jsxRuntime` and `@jsxImportSource` comments in a
+    /// file still take precedence.
+    pub fn with_jsx(mut self, jsx: JsxOptions) -> Self {
+        self.jsx = jsx;
+        self
+    }
+
+    /// Sets the module system of the output, CommonJS unless configured otherwise
+    pub fn with_module_format(mut self, module_format: ModuleFormat) -> Self {
+        self.module_format = module_format;
+        self
+    }
+
+    /// Instruments components for React Fast Refresh
+    ///
+    /// Components are registered with `$RefreshReg$(type, id)` and hook
+    /// signatures are tracked with `$RefreshSig$()`, both of which the page must
+    /// define, as [`crate::bundler::Bundler::hot`] bundles do.
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    /// Replaces global expressions such as `process.env.NODE_ENV` at compile
+    /// time and removes the branches that become dead
+    pub fn with_define(mut self, define: Define) -> Self {
+        self.define = define;
+        self
+    }
+
+    /// Downlevels the output for older engines or browsers, such as a legacy
+    /// build next to a modern one
+    ///
+    /// The source keeps its syntax unless configured otherwise.
+    pub fn with_target(mut self, target: TargetOptions) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Looks compiled output up in `cache` before compiling and stores it there after
+    pub fn with_cache(mut self, cache: Arc<CompileCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
     pub fn compile_react_component(&self, code: &str, filename: Option<&str>) -> Result<String> {
-        GLOBALS.set(&Globals::new(), || {
+        self.compile(code, filename).map(|output| output.code)
+    }
+
+    /// Compiles like [`Self::compile_react_component`], also returning the source map
+    /// when [`Self::with_source_maps`] asks for one
+    ///
+    /// With [`Self::with_cache`], unchanged sources are returned from the cache
+    /// without running SWC.
+    pub fn compile(&self, code: &str, filename: Option<&str>) -> Result<CompileOutput> {
+        let Some(cache) = &self.cache else {
+            return self.compile_uncached(code, filename);
+        };
+
+        let key = CompileCache::key(&["module", &self.fingerprint(), filename.unwrap_or(""), code]);
+        if let Some(entry) = cache.get(&key) {
+            return Ok(CompileOutput {
+                code: entry.code,
+                map: entry.map,
+            });
+        }
+
+        let output = self.compile_uncached(code, filename)?;
+        cache.insert(
+            key,
+            CacheEntry {
+                code: output.code.clone(),
+                map: output.map.clone(),
+                dependencies: self.rewritten_imports(&output.code, filename),
+            },
+        );
+        Ok(output)
+    }
+
+    /// Everything besides the source that affects the output of [`Self::compile`]
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?} {:?} {} {:?} {:?}",
+            self.resolver.as_ref().map(|resolver| resolver.fingerprint()),
+            self.minify,
+            self.source_maps,
+            self.jsx,
+            self.module_format,
+            self.refresh,
+            self.define,
+            self.target
+        )
+    }
+
+    pub(crate) fn cache(&self) -> Option<&Arc<CompileCache>> {
+        self.cache.as_ref()
+    }
+
+    pub(crate) fn define(&self) -> &Define {
+        &self.define
+    }
+
+    /// The project files the resolver rewrote the imports of `code` to, which
+    /// make a cached compile stale when they move or change
+    fn rewritten_imports(&self, code: &str, filename: Option<&str>) -> Vec<Dependency> {
+        let (Some(resolver), Some(filename)) = (&self.resolver, filename) else {
+            return vec![];
+        };
+        let parser = JavaScriptParser::new();
+        let Ok(module) = parser.parse_code(code, Some("compiled.js")) else {
+            return vec![];
+        };
+        let dir = normalize_path(Path::new(filename).parent().unwrap_or(Path::new(".")));
+
+        collect_imports(parser.source_map(), &module)
+            .into_iter()
+            .filter_map(|import| {
+                let kind = match import.kind {
+                    ImportKind::Require => ResolveKind::Require,
+                    _ => ResolveKind::Import,
+                };
+                match resolver.resolve(&import.specifier, &dir, kind) {
+                    Ok(Resolved::File(path)) if !is_package_file(&path) => Dependency::of(path),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    fn compile_uncached(&self, code: &str, filename: Option<&str>) -> Result<CompileOutput> {
+        // helpers such as `_interop_require_default` are emitted inline, as the
+        // SSR runtime has no `@swc/helpers` to require
+        GLOBALS.set(&Globals::new(), || HELPERS.set(&Helpers::new(false), || {
             let filename = filename.unwrap_or("component.tsx");
             let source_file = self.source_map.new_source_file(
                 Lrc::new(FileName::Custom(filename.to_string())),
                 code.to_string(),
             );
+            // per compile, so a long-lived compiler doesn't keep every file's comments
+            let comments = SingleThreadedComments::default();
 
             let lexer = Lexer::new(
                 Syntax::Typescript(TsSyntax {
@@ -47,36 +337,48 @@ impl ReactCompiler {
                     no_early_errors: false,
                     disallow_ambiguous_jsx_like: true,
                 }),
-                EsVersion::Es2018,
+                EsVersion::latest(),
                 StringInput::from(&*source_file),
-                None,
+                Some(&comments),
             );
 
             let mut parser = Parser::new_from(lexer);
-            let module = parser
-                .parse_module()
-                .map_err(|e| anyhow::anyhow!("Parse error: {:?}", e))?;
+            // a diagnostic carries the code frame the dev server overlay shows
+            let module = parser.parse_module().map_err(|e| {
+                ParseDiagnostic::from_swc_error(&self.source_map, &e, Severity::Error)
+            })?;
 
             let top_level_mark = Mark::new();
             let unresolved_mark = Mark::new();
             let mut program = swc_ecma_ast::Program::Module(module);
 
-            let react_options = ReactOptions {
-                runtime: Some(ReactRuntime::Classic),
-                pragma: Some(Arc::new("React.createElement".to_string())),
-                pragma_frag: Some(Arc::new("React.Fragment".to_string())),
-                import_source: Some(Atom::from("react")),
-                ..Default::default()
-            };
-
             // Apply transforms in the correct order using tuples
             let transforms = (
                 swc_ecma_transforms_base::resolver(unresolved_mark, top_level_mark, false),
-                strip(top_level_mark, unresolved_mark),
+                // unlike `strip`, keeps imports that only the JSX pragma uses
+                tsx(
+                    self.source_map.clone(),
+                    TsConfig::default(),
+                    TsxConfig {
+                        pragma: Some(Arc::new(self.jsx.pragma.clone())),
+                        pragma_frag: Some(Arc::new(self.jsx.pragma_frag.clone())),
+                    },
+                    &comments,
+                    unresolved_mark,
+                    top_level_mark,
+                ),
+                // `react` only runs its own refresh pass in development mode
+                refresh(
+                    self.refresh,
+                    Some(RefreshOptions::default()),
+                    self.source_map.clone(),
+                    Some(&comments),
+                    top_level_mark,
+                ),
                 react(
                     self.source_map.clone(),
-                    Some(&NoopComments),
-                    react_options,
+                    Some(&comments),
+                    self.jsx.to_swc(),
                     top_level_mark,
                     unresolved_mark,
                 ),
@@ -84,15 +386,49 @@ impl ReactCompiler {
 
             // Apply all transforms at once
             program = program.apply(transforms);
+            // after the JSX and TypeScript passes, so folding sees plain JavaScript
+            if !self.define.is_empty() {
+                program = program.apply(self.define.pass(unresolved_mark)?);
+            }
+            program = program.apply(self.target.pass(unresolved_mark, Some(&comments))?);
 
-            let common_js_transform = common_js(
-                Resolver::default(),
+            let module_resolver = match &self.resolver {
+                Some(resolver) => Resolver::Real {
+                    base: FileName::Real(normalize_path(Path::new(filename))),
+                    resolver: Arc::new(NodeImportResolver::new(
+                        resolver.clone(),
+                        self.module_format.resolve_kind(),
+                    )),
+                },
+                None => Resolver::default(),
+            };
+            program = self.module_format.transform(
+                program,
+                self.source_map.clone(),
+                &comments,
+                module_resolver,
                 unresolved_mark,
-                swc_ecma_transforms_module::common_js::Config::default(),
-                swc_ecma_transforms_module::common_js::FeatureFlag::default(),
             );
-            
-            program = program.apply(common_js_transform);
+            program = program.apply(inject_helpers(unresolved_mark));
+
+            strip_source_mapping_urls(&comments);
+            if let Some(options) = &self.minify {
+                program = minify_program(
+                    program,
+                    self.source_map.clone(),
+                    &comments,
+                    options,
+                    unresolved_mark,
+                    top_level_mark,
+                );
+            } else {
+                // parenthesizes the `(0, _runtime.jsx)(...)` calls the module pass creates
+                program = program.apply((
+                    hygiene(),
+                    fixer(Some(&comments as &dyn Comments)),
+                ));
+            }
+            self.module_format.finish(&mut program);
 
             let module = match &program {
                 swc_ecma_ast::Program::Module(m) => m.clone(),
@@ -101,12 +437,23 @@ impl ReactCompiler {
 
             // Generate output code
             let mut buf = Vec::new();
+            let mut mappings = Vec::new();
             {
-                let writer = JsWriter::new(self.source_map.clone(), "\n", &mut buf, None);
+                let writer = JsWriter::new(
+                    self.source_map.clone(),
+                    "\n",
+                    &mut buf,
+                    (self.source_maps != SourceMaps::None).then_some(&mut mappings),
+                );
+                let writer: Box<dyn WriteJs> = if self.minify.is_some() {
+                    Box::new(omit_trailing_semi(writer))
+                } else {
+                    Box::new(writer)
+                };
                 let mut emitter = Emitter {
-                    cfg: swc_ecma_codegen::Config::default(),
+                    cfg: swc_ecma_codegen::Config::default().with_minify(self.minify.is_some()),
                     cm: self.source_map.clone(),
-                    comments: None,
+                    comments: Some(&comments),
                     wr: writer,
                 };
                 emitter
@@ -114,11 +461,32 @@ impl ReactCompiler {
                     .map_err(|e| anyhow::anyhow!("Emit error: {:?}", e))?;
             }
 
-            let output = String::from_utf8(buf)
+            let mut code = String::from_utf8(buf)
                 .map_err(|e| anyhow::anyhow!("UTF-8 conversion error: {:?}", e))?;
+            if self.source_maps == SourceMaps::None {
+                return Ok(CompileOutput { code, map: None });
+            }
+
+            let mut map = self.source_map.build_source_map(
+                &mappings,
+                input_source_map(source_file.src.as_str(), filename),
+                SourceMapConfig,
+            );
+            map.set_file(Some(output_file_name(filename)));
+            if let Some(url) = source_mapping_url(self.source_maps, &map, filename)? {
+                if !code.ends_with('\n') {
+                    code.push('\n');
+                }
+                code.push_str(&url);
+            }
+            let mut json = Vec::new();
+            map.to_writer(&mut json)?;
 
-            Ok(output)
-        })
+            Ok(CompileOutput {
+                code,
+                map: Some(String::from_utf8(json)?),
+            })
+        }))
     }
 
     pub fn compile_react_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<String> {
@@ -131,6 +499,15 @@ impl ReactCompiler {
     }
 }
 
+/// Copies a comments store without sharing its maps
+pub(crate) fn snapshot_comments(comments: &SingleThreadedComments) -> SingleThreadedComments {
+    let (leading, trailing) = comments.borrow_all();
+    SingleThreadedComments::from_leading_and_trailing(
+        Rc::new(RefCell::new(leading.clone())),
+        Rc::new(RefCell::new(trailing.clone())),
+    )
+}
+
 impl Default for ReactCompiler {
     fn default() -> Self {
         Self::new()
@@ -158,8 +535,9 @@ mod tests {
         assert!(result.is_ok());
 
         let compiled = result.unwrap();
-        // Should contain transformed JSX
-        assert!(compiled.contains("React.createElement") || compiled.contains("jsx"));
+        // Should contain transformed JSX, calling the imported React
+        assert!(compiled.contains("require(\"react\")"));
+        assert!(compiled.contains("_react.default.createElement(\"div\""));
     }
 
     #[test]
@@ -188,4 +566,157 @@ mod tests {
         let result = compiler.compile_react_component(code, Some("user-card.tsx"));
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_compile_keeps_comments() {
+        let compiler = ReactCompiler::new();
+        let code = r#"
+            import React from 'react';
+
+            /** Greets the user */
+            const Greeting = () => <p>Hi</p>;
+
+            export default Greeting;
+        "#;
+
+        let compiled = compiler
+            .compile_react_component(code, Some("greeting.tsx"))
+            .unwrap();
+        assert!(compiled.contains("/** Greets the user */"));
+
+        // each compile reads its own comments
+        let recompiled = compiler
+            .compile_react_component(code, Some("greeting.tsx"))
+            .unwrap();
+        assert_eq!(recompiled, compiled);
+    }
+
+    #[test]
+    fn test_compile_automatic_jsx_runtime() {
+        let code = r#"
+            export default function Greeting({ name }: { name: string }) {
+                return <><p key="greeting">Hello, {name}</p></>;
+            }
+        "#;
+
+        let compiled = ReactCompiler::new()
+            .with_jsx(JsxOptions::automatic())
+            .compile_react_component(code, Some("greeting.tsx"))
+            .unwrap();
+        assert!(compiled.contains("require(\"react/jsx-runtime\")"));
+        assert!(compiled.contains("(0, _jsxruntime.jsx)(_jsxruntime.Fragment, {"));
+        assert!(compiled.contains("(0, _jsxruntime.jsxs)(\"p\", {"));
+        assert!(!compiled.contains("React"));
+
+        let compiled = ReactCompiler::new()
+            .with_jsx(JsxOptions::automatic().import_source("preact").development(true))
+            .compile_react_component(code, Some("greeting.tsx"))
+            .unwrap();
+        assert!(compiled.contains("require(\"preact/jsx-dev-runtime\")"));
+        assert!(compiled.contains("_jsxdevruntime.jsxDEV"));
+        assert!(compiled.contains("fileName: \"greeting.tsx\""));
+    }
+
+    #[test]
+    fn test_compile_custom_pragma() {
+        let code = r#"
+            import { h, Fragment } from 'preact';
+
+            export const List = () => <><li>One</li></>;
+        "#;
+
+        let compiled = ReactCompiler::new()
+            .with_jsx(JsxOptions::new().pragma("h").pragma_frag("Fragment"))
+            .compile_react_component(code, Some("list.tsx"))
+            .unwrap();
+        // the pragma keeps the otherwise unused import alive
+        assert!(compiled.contains("require(\"preact\")"));
+        assert!(compiled.contains("(0, _preact.h)(_preact.Fragment, null"));
+
+        let compiled = ReactCompiler::new()
+            .with_jsx(JsxOptions::new().development(true))
+            .compile_react_component("function Br() { return <br />; }", Some("dev.tsx"))
+            .unwrap();
+        assert!(compiled.contains("__source: {"));
+        assert!(compiled.contains("__self: this"));
+    }
+
+    #[test]
+    fn test_compile_with_define() {
+        let code = r#"
+            export default function Banner() {
+                if (process.env.NODE_ENV !== "production") {
+                    console.log("rendering", import.meta.env.MODE);
+                }
+                return __DEV__ ? <p>dev</p> : <p>live</p>;
+            }
+        "#;
+
+        let compiled = ReactCompiler::new()
+            .with_define(Define::env("production"))
+            .compile_react_component(code, Some("banner.tsx"))
+            .unwrap();
+        assert!(!compiled.contains("process"));
+        assert!(!compiled.contains("console.log"));
+        assert!(!compiled.contains("\"dev\""));
+        assert!(compiled.contains("\"live\""));
+    }
+
+    #[test]
+    fn test_compile_with_target() {
+        let code = r#"
+            export class Store {
+                items = [];
+                async load(api) {
+                    return api?.items ?? new Map(this.items);
+                }
+            }
+        "#;
+
+        let compiled = ReactCompiler::new()
+            .compile_react_component(code, Some("store.ts"))
+            .unwrap();
+        assert!(compiled.contains("api?.items ?? new Map(this.items)"));
+        assert!(compiled.contains("async load"));
+
+        let compiled = ReactCompiler::new()
+            .with_target(TargetOptions::browsers("ie 11").polyfills("3.38"))
+            .compile_react_component(code, Some("store.ts"))
+            .unwrap();
+        assert!(!compiled.contains("?."));
+        assert!(!compiled.contains("class Store"));
+        assert!(!compiled.contains("async "));
+        assert!(compiled.contains(r#"require("core-js/modules/es.map.js");"#));
+
+        let compiled = ReactCompiler::new()
+            .with_target(TargetOptions::es_version(EsVersion::Es2019))
+            .compile_react_component(code, Some("store.ts"))
+            .unwrap();
+        assert!(!compiled.contains("?."));
+        assert!(compiled.contains("async load"));
+    }
+
+    #[test]
+    fn test_compile_with_refresh() {
+        let code = r#"
+            import { useState } from 'react';
+
+            export default function Counter() {
+                const [count, setCount] = useState(0);
+                return <button onClick={() => setCount(count + 1)}>{count}</button>;
+            }
+        "#;
+
+        let compiled = ReactCompiler::new()
+            .with_refresh(true)
+            .compile_react_component(code, Some("counter.tsx"))
+            .unwrap();
+        assert!(compiled.contains("$RefreshSig$()"));
+        assert!(compiled.contains("$RefreshReg$(_c, \"Counter\")"));
+
+        let compiled = ReactCompiler::new()
+            .compile_react_component(code, Some("counter.tsx"))
+            .unwrap();
+        assert!(!compiled.contains("$Refresh"));
+    }
 }
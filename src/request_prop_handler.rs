@@ -2,11 +2,35 @@ use ssr_rs::v8;
 use super::JsHttpRequestProcessor;
 use crate::ssr::http_request::SimpleHttpRequest;
 
+/// The properties of `HttpRequest` handled by [`JsHttpRequestProcessor::request_prop_handler`]
+pub const REQUEST_PROPERTIES: &[&str] = &[
+    "path",
+    "userAgent",
+    "referrer",
+    "host",
+    "method",
+    "headers",
+    "query",
+    "cookies",
+    "remoteAddress",
+    "protocol",
+    "body",
+    "json",
+    "bytes",
+];
+
 impl<'s, 'i> JsHttpRequestProcessor<'s, 'i>
 where
     's: 'i,
 {
     /// This handles the properties of `HttpRequest`
+    ///
+    /// `headers`, `query` and `cookies` are objects keyed by name. A header sent
+    /// more than once is its values joined with `", "`, a repeated cookie keeps
+    /// its first value, and a repeated query parameter is an array of its
+    /// values; everything else is a string. `body` is the body as text,
+    /// `json` parses it, throwing a `SyntaxError` if it is not JSON, and `bytes`
+    /// is a `Uint8Array` copy of it.
     #[allow(clippy::needless_pass_by_value)] // this function should follow the callback type
     pub fn request_prop_handler(
         scope: &mut v8::HandleScope,
@@ -26,16 +50,95 @@ where
 
         let key = key.to_rust_string_lossy(scope);
 
-        let value = match &*key {
-            "path" => request.path(),
-            "userAgent" => request.user_agent(),
-            "referrer" => request.referrer(),
-            "host" => request.host(),
+        let value: v8::Local<v8::Value> = match &*key {
+            "path" => v8::String::new(scope, request.path()).unwrap().into(),
+            "userAgent" => v8::String::new(scope, request.user_agent()).unwrap().into(),
+            "referrer" => v8::String::new(scope, request.referrer()).unwrap().into(),
+            "host" => v8::String::new(scope, request.host()).unwrap().into(),
+            "method" => v8::String::new(scope, request.method()).unwrap().into(),
+            "protocol" => v8::String::new(scope, request.protocol()).unwrap().into(),
+            "remoteAddress" => match request.remote_addr() {
+                Some(remote_addr) => v8::String::new(scope, remote_addr).unwrap().into(),
+                None => v8::null(scope).into(),
+            },
+            "headers" => string_map(scope, request.headers(), Repeated::Join(", ")).into(),
+            "query" => {
+                let query = request.query();
+                let query = query.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+                string_map(scope, query, Repeated::Array).into()
+            }
+            "cookies" => {
+                let cookies = request.cookies();
+                let cookies = cookies.iter().map(|(k, v)| (k.as_str(), v.as_str()));
+                string_map(scope, cookies, Repeated::First).into()
+            }
+            "body" => v8::String::new(scope, &request.text()).unwrap().into(),
+            "json" => {
+                let text = v8::String::new(scope, &request.text()).unwrap();
+                // on failure the SyntaxError is left pending for the caller
+                match v8::json::parse(scope, text) {
+                    Some(value) => value,
+                    None => return,
+                }
+            }
+            "bytes" => {
+                let bytes = request.body().to_vec();
+                let length = bytes.len();
+                let store = v8::ArrayBuffer::new_backing_store_from_vec(bytes).make_shared();
+                let buffer = v8::ArrayBuffer::with_backing_store(scope, &store);
+                v8::Uint8Array::new(scope, buffer, 0, length).unwrap().into()
+            }
             _ => {
                 return;
             }
         };
 
-        rv.set(v8::String::new(scope, value).unwrap().into());
+        rv.set(value);
+    }
+}
+
+/// What [`string_map`] does with a name that appears more than once
+enum Repeated {
+    /// Collects the values into an array
+    Array,
+    /// Joins the values into one string with a separator
+    Join(&'static str),
+    /// Keeps the first value
+    First,
+}
+
+/// An object of the `entries`, merging the values of a repeated name as `repeated` says
+fn string_map<'t, 'a>(
+    scope: &mut v8::HandleScope<'t>,
+    entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+    repeated: Repeated,
+) -> v8::Local<'t, v8::Object> {
+    let object = v8::Object::new(scope);
+    for (name, value) in entries {
+        let key: v8::Local<v8::Name> = v8::String::new(scope, name).unwrap().into();
+        let value = if object.has_own_property(scope, key) == Some(true) {
+            let existing = object.get(scope, key.into()).unwrap();
+            match repeated {
+                Repeated::First => continue,
+                Repeated::Join(separator) => {
+                    let existing = existing.to_rust_string_lossy(scope);
+                    v8::String::new(scope, &format!("{existing}{separator}{value}"))
+                        .unwrap()
+                        .into()
+                }
+                Repeated::Array => {
+                    let value = v8::String::new(scope, value).unwrap().into();
+                    if let Ok(array) = v8::Local::<v8::Array>::try_from(existing) {
+                        array.set_index(scope, array.length(), value);
+                        continue;
+                    }
+                    v8::Array::new_with_elements(scope, &[existing, value]).into()
+                }
+            }
+        } else {
+            v8::String::new(scope, value).unwrap().into()
+        };
+        object.set(scope, key.into(), value);
     }
+    object
 }
@@ -0,0 +1,849 @@
+use crate::module_graph::{normalize_path, resolve_file};
+use anyhow::Error;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use swc_atoms::Atom;
+use swc_common::FileName;
+use swc_ecma_transforms_module::path::ImportResolver;
+
+/// Node's built-in modules, which resolve without touching the file system
+pub const NODE_BUILTINS: &[&str] = &[
+    "assert", "async_hooks", "buffer", "child_process", "cluster", "console", "constants",
+    "crypto", "dgram", "diagnostics_channel", "dns", "domain", "events", "fs", "http", "http2",
+    "https", "inspector", "module", "net", "os", "path", "perf_hooks", "process", "punycode",
+    "querystring", "readline", "repl", "stream", "string_decoder", "sys", "timers", "tls",
+    "trace_events", "tty", "url", "util", "v8", "vm", "wasi", "worker_threads", "zlib",
+];
+
+/// Whether a specifier is loaded with `import` or `require`
+///
+/// Selects the `import` or `require` condition of `package.json` `exports`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveKind {
+    Import,
+    Require,
+}
+
+/// What a specifier resolved to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolved {
+    File(PathBuf),
+    /// A Node built-in such as `fs`, without the `node:` prefix
+    Builtin(String),
+}
+
+/// Why a specifier could not be resolved
+#[derive(Debug, thiserror::Error)]
+pub enum ResolveError {
+    #[error("cannot find module '{specifier}' from '{}'", from.display())]
+    NotFound { specifier: String, from: PathBuf },
+    #[error("package subpath '{subpath}' is not exported by {}", package_json.display())]
+    NotExported {
+        subpath: String,
+        package_json: PathBuf,
+    },
+    #[error("'{specifier}' is not defined in the imports of {}", package_json.display())]
+    ImportNotDefined {
+        specifier: String,
+        package_json: PathBuf,
+    },
+    #[error("failed to read {}: {message}", path.display())]
+    InvalidConfig { path: PathBuf, message: String },
+}
+
+/// The `compilerOptions.baseUrl`/`paths` of a `tsconfig.json`
+#[derive(Debug, Clone, Default)]
+pub struct TsConfigPaths {
+    /// Directory non-relative specifiers and `paths` targets are resolved from
+    pub base_url: PathBuf,
+    /// Patterns with at most one `*`, in declaration order
+    pub paths: Vec<(String, Vec<String>)>,
+    /// Whether `baseUrl` was set, so bare specifiers are also tried against it
+    pub has_base_url: bool,
+}
+
+impl TsConfigPaths {
+    /// Reads the `paths` and `baseUrl` of a `tsconfig.json`, following `extends`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ResolveError> {
+        let path = normalize_path(path.as_ref());
+        let mut config = Self {
+            base_url: path.parent().unwrap_or(Path::new("")).to_path_buf(),
+            ..Default::default()
+        };
+        config.merge_file(&path, 0)?;
+        Ok(config)
+    }
+
+    fn merge_file(&mut self, path: &Path, depth: usize) -> Result<(), ResolveError> {
+        let json = read_json(path, true)?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+
+        // the extended config is applied first so this one overrides it
+        if let Some(extends) = json.get("extends").and_then(Value::as_str)
+            && depth < 8
+            && (extends.starts_with('.') || Path::new(extends).is_absolute())
+        {
+            let mut base = dir.join(extends);
+            if base.extension().is_none() {
+                base.set_extension("json");
+            }
+            self.merge_file(&base, depth + 1)?;
+        }
+
+        let Some(options) = json.get("compilerOptions") else {
+            return Ok(());
+        };
+        if let Some(base_url) = options.get("baseUrl").and_then(Value::as_str) {
+            self.base_url = normalize_path(&dir.join(base_url));
+            self.has_base_url = true;
+        } else if options.get("paths").is_some() && !self.has_base_url {
+            // without baseUrl, paths are relative to the config declaring them
+            self.base_url = dir.to_path_buf();
+        }
+        if let Some(paths) = options.get("paths").and_then(Value::as_object) {
+            self.paths = paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Value::as_str)
+                        .map(str::to_string)
+                        .collect();
+                    (pattern.clone(), targets)
+                })
+                .collect();
+        }
+        Ok(())
+    }
+
+    /// Candidate paths for a specifier, from the most specific matching pattern
+    fn candidates(&self, specifier: &str) -> Vec<PathBuf> {
+        let mut best: Option<(usize, &[String], &str)> = None;
+        for (pattern, targets) in &self.paths {
+            let matched = match pattern.split_once('*') {
+                None if pattern == specifier => Some((usize::MAX, "")),
+                None => None,
+                Some((prefix, suffix)) => specifier
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix))
+                    .map(|star| (prefix.len(), star)),
+            };
+            if let Some((specificity, star)) = matched
+                && best.is_none_or(|(best, _, _)| specificity > best)
+            {
+                best = Some((specificity, targets, star));
+            }
+        }
+
+        let Some((_, targets, star)) = best else {
+            return vec![];
+        };
+        targets
+            .iter()
+            .map(|target| self.base_url.join(target.replace('*', star)))
+            .collect()
+    }
+}
+
+/// A parsed `package.json`
+#[derive(Debug)]
+struct PackageJson {
+    path: PathBuf,
+    json: Value,
+}
+
+impl PackageJson {
+    fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+}
+
+/// Resolves module specifiers the way Node and TypeScript do
+///
+/// Handles `node_modules` lookup, the `exports`, `imports` and main fields of
+/// `package.json`, and `tsconfig.json` `baseUrl`/`paths` aliases.
+#[derive(Debug)]
+pub struct NodeResolver {
+    conditions: Vec<String>,
+    main_fields: Vec<String>,
+    tsconfig: Option<TsConfigPaths>,
+    packages: Mutex<HashMap<PathBuf, Option<Arc<PackageJson>>>>,
+}
+
+impl NodeResolver {
+    /// A resolver with the `node` condition and the `main` field, like Node itself
+    pub fn new() -> Self {
+        Self {
+            conditions: vec!["node".to_string()],
+            main_fields: vec!["main".to_string()],
+            tsconfig: None,
+            packages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// A resolver for a project directory, using its `tsconfig.json` if there is one
+    pub fn for_project<P: AsRef<Path>>(root: P) -> Result<Self, ResolveError> {
+        let tsconfig = root.as_ref().join("tsconfig.json");
+        let resolver = Self::new();
+        if tsconfig.is_file() {
+            resolver.tsconfig(tsconfig)
+        } else {
+            Ok(resolver)
+        }
+    }
+
+    /// The settings that decide what specifiers resolve to, leaving out the
+    /// `package.json` cache
+    pub(crate) fn fingerprint(&self) -> String {
+        format!("{:?} {:?} {:?}", self.conditions, self.main_fields, self.tsconfig)
+    }
+
+    /// Conditions matched in `exports`/`imports` besides `import`/`require` and `default`,
+    /// e.g. `["react-server", "node"]`
+    pub fn conditions<S: Into<String>>(mut self, conditions: impl IntoIterator<Item = S>) -> Self {
+        self.conditions = conditions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// `package.json` fields tried, in order, for packages without `exports`
+    pub fn main_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.main_fields = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Applies the `baseUrl`/`paths` aliases of a `tsconfig.json`
+    pub fn tsconfig<P: AsRef<Path>>(mut self, path: P) -> Result<Self, ResolveError> {
+        self.tsconfig = Some(TsConfigPaths::load(path)?);
+        Ok(self)
+    }
+
+    pub fn tsconfig_paths(mut self, paths: TsConfigPaths) -> Self {
+        self.tsconfig = Some(paths);
+        self
+    }
+
+    /// Resolves `specifier` imported from a file in `from_dir`
+    pub fn resolve(
+        &self,
+        specifier: &str,
+        from_dir: &Path,
+        kind: ResolveKind,
+    ) -> Result<Resolved, ResolveError> {
+        let not_found = || ResolveError::NotFound {
+            specifier: specifier.to_string(),
+            from: from_dir.to_path_buf(),
+        };
+
+        if let Some(builtin) = specifier.strip_prefix("node:") {
+            return Ok(Resolved::Builtin(builtin.to_string()));
+        }
+        if NODE_BUILTINS.contains(&specifier.split('/').next().unwrap_or(specifier)) {
+            return Ok(Resolved::Builtin(specifier.to_string()));
+        }
+
+        if specifier.starts_with('#') {
+            return self.resolve_package_import(specifier, from_dir, kind);
+        }
+
+        if is_relative(specifier) {
+            return self
+                .load_file_or_directory(&from_dir.join(specifier))
+                .map(|path| Resolved::File(normalize_path(&path)))
+                .ok_or_else(not_found);
+        }
+
+        if let Some(tsconfig) = &self.tsconfig {
+            let mut candidates = tsconfig.candidates(specifier);
+            if tsconfig.has_base_url {
+                candidates.push(tsconfig.base_url.join(specifier));
+            }
+            if let Some(path) = candidates
+                .iter()
+                .find_map(|candidate| self.load_file_or_directory(candidate))
+            {
+                return Ok(Resolved::File(normalize_path(&path)));
+            }
+        }
+
+        self.resolve_package(specifier, from_dir, kind)?
+            .map(|path| Resolved::File(normalize_path(&path)))
+            .ok_or_else(not_found)
+    }
+
+    /// Looks a bare specifier up in the `node_modules` directories above `from_dir`
+    fn resolve_package(
+        &self,
+        specifier: &str,
+        from_dir: &Path,
+        kind: ResolveKind,
+    ) -> Result<Option<PathBuf>, ResolveError> {
+        let (name, subpath) = split_package_specifier(specifier);
+
+        for dir in from_dir.ancestors() {
+            if dir.file_name().is_some_and(|name| name == "node_modules") {
+                continue;
+            }
+            let package_dir = dir.join("node_modules").join(name);
+            if !package_dir.is_dir() {
+                continue;
+            }
+
+            let package = self.package_json(&package_dir.join("package.json"))?;
+            if let Some(package) = &package
+                && let Some(exports) = package.json.get("exports")
+            {
+                return self
+                    .resolve_exports(package, exports, &format!(".{subpath}"), kind)
+                    .map(Some);
+            }
+
+            let path = package_dir.join(subpath.trim_start_matches('/'));
+            return Ok(self.load_file_or_directory(&path));
+        }
+
+        Ok(None)
+    }
+
+    /// Resolves a subpath through the `exports` field of a package
+    fn resolve_exports(
+        &self,
+        package: &PackageJson,
+        exports: &Value,
+        subpath: &str,
+        kind: ResolveKind,
+    ) -> Result<PathBuf, ResolveError> {
+        let not_exported = || ResolveError::NotExported {
+            subpath: subpath.to_string(),
+            package_json: package.path.clone(),
+        };
+
+        // `"exports": "./index.js"` and condition-only objects map the root
+        let is_subpath_map = exports
+            .as_object()
+            .is_some_and(|map| map.keys().any(|key| key.starts_with('.')));
+        if !is_subpath_map {
+            if subpath != "." {
+                return Err(not_exported());
+            }
+            return self
+                .resolve_target(package, exports, None, kind, false)
+                .ok_or_else(not_exported);
+        }
+
+        let map = exports.as_object().expect("checked above");
+        self.resolve_in_map(package, map, subpath, kind, false)
+            .ok_or_else(not_exported)
+    }
+
+    /// Resolves `#internal` specifiers through the nearest `package.json` `imports`
+    fn resolve_package_import(
+        &self,
+        specifier: &str,
+        from_dir: &Path,
+        kind: ResolveKind,
+    ) -> Result<Resolved, ResolveError> {
+        let Some(package) = self.package_scope(from_dir)? else {
+            return Err(ResolveError::NotFound {
+                specifier: specifier.to_string(),
+                from: from_dir.to_path_buf(),
+            });
+        };
+        let not_defined = || ResolveError::ImportNotDefined {
+            specifier: specifier.to_string(),
+            package_json: package.path.clone(),
+        };
+
+        let imports = package
+            .json
+            .get("imports")
+            .and_then(Value::as_object)
+            .ok_or_else(not_defined)?;
+        let target = self
+            .resolve_in_map(&package, imports, specifier, kind, true)
+            .ok_or_else(not_defined)?;
+        Ok(Resolved::File(normalize_path(&target)))
+    }
+
+    /// Finds the entry of an `exports`/`imports` map matching `key`, including `*` patterns
+    fn resolve_in_map(
+        &self,
+        package: &PackageJson,
+        map: &Map<String, Value>,
+        key: &str,
+        kind: ResolveKind,
+        imports: bool,
+    ) -> Option<PathBuf> {
+        if let Some(target) = map.get(key).filter(|_| !key.contains('*')) {
+            return self.resolve_target(package, target, None, kind, imports);
+        }
+
+        // the pattern with the longest prefix wins
+        let (_, target, star) = map
+            .iter()
+            .filter_map(|(pattern, target)| {
+                let (prefix, suffix) = pattern.split_once('*')?;
+                let star = key.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                Some((prefix.len(), target, star))
+            })
+            .max_by_key(|(specificity, _, _)| *specificity)?;
+        self.resolve_target(package, target, Some(star), kind, imports)
+    }
+
+    /// Resolves one target of an `exports` entry, or of an `imports` one when `imports` is set
+    ///
+    /// As in Node, targets are paths starting with `./` that stay inside the
+    /// package, and only `imports` may also name another package. Any other
+    /// target, such as `../x` or an `imports` entry mapping to `#y`, matches nothing.
+    fn resolve_target(
+        &self,
+        package: &PackageJson,
+        target: &Value,
+        star: Option<&str>,
+        kind: ResolveKind,
+        imports: bool,
+    ) -> Option<PathBuf> {
+        match target {
+            Value::String(target) => {
+                let target = match star {
+                    Some(star) => target.replace('*', star),
+                    None => target.clone(),
+                };
+                if let Some(relative) = target.strip_prefix("./") {
+                    let escapes = relative
+                        .split(['/', '\\'])
+                        .any(|segment| matches!(segment, "" | "." | ".." | "node_modules"));
+                    let path = package.dir().join(relative);
+                    (!escapes && path.is_file()).then_some(path)
+                } else if imports && is_package_name(&target) {
+                    self.resolve_package(&target, package.dir(), kind)
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                }
+            }
+            Value::Array(targets) => targets
+                .iter()
+                .find_map(|target| self.resolve_target(package, target, star, kind, imports)),
+            Value::Object(conditions) => conditions
+                .iter()
+                .filter(|(condition, _)| self.matches_condition(condition, kind))
+                .find_map(|(_, target)| {
+                    self.resolve_target(package, target, star, kind, imports)
+                }),
+            _ => None,
+        }
+    }
+
+    fn matches_condition(&self, condition: &str, kind: ResolveKind) -> bool {
+        match condition {
+            "default" => true,
+            "import" => kind == ResolveKind::Import,
+            "require" => kind == ResolveKind::Require,
+            other => self.conditions.iter().any(|c| c == other),
+        }
+    }
+
+    /// Resolves a path as a file, then as a directory with a `package.json` or an index file
+    fn load_file_or_directory(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_dir()
+            && let Ok(Some(package)) = self.package_json(&path.join("package.json"))
+        {
+            let main = self
+                .main_fields
+                .iter()
+                .filter_map(|field| package.json.get(field).and_then(Value::as_str))
+                .find_map(|main| resolve_file(&path.join(main)));
+            if main.is_some() {
+                return main;
+            }
+        }
+        resolve_file(path)
+    }
+
+    /// The nearest `package.json` at or above `dir`
+    fn package_scope(&self, dir: &Path) -> Result<Option<Arc<PackageJson>>, ResolveError> {
+        for dir in dir.ancestors() {
+            if let Some(package) = self.package_json(&dir.join("package.json"))? {
+                return Ok(Some(package));
+            }
+            if dir.file_name().is_some_and(|name| name == "node_modules") {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether a file is an ES module according to its extension or its package `type`
+    pub fn is_esm(&self, path: &Path) -> bool {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("mjs" | "mts") => true,
+            Some("cjs" | "cts" | "json") => false,
+            _ => self
+                .package_scope(path.parent().unwrap_or(Path::new("")))
+                .ok()
+                .flatten()
+                .and_then(|package| package.json.get("type").and_then(Value::as_str).map(|t| t == "module"))
+                // project sources are compiled, so only packages default to CommonJS
+                .unwrap_or(!path.components().any(|c| c.as_os_str() == "node_modules")),
+        }
+    }
+
+    /// Reads and caches a `package.json`; `None` if the file does not exist
+    fn package_json(&self, path: &Path) -> Result<Option<Arc<PackageJson>>, ResolveError> {
+        let mut packages = self.packages.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(package) = packages.get(path) {
+            return Ok(package.clone());
+        }
+
+        let package = if path.is_file() {
+            Some(Arc::new(PackageJson {
+                path: path.to_path_buf(),
+                json: read_json(path, false)?,
+            }))
+        } else {
+            None
+        };
+        packages.insert(path.to_path_buf(), package.clone());
+        Ok(package)
+    }
+}
+
+impl Default for NodeResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Rewrites the specifiers of compiled modules to the files they resolve to
+///
+/// Project files become relative paths with their extension, so the output
+/// runs without TypeScript aliases. Packages, built-ins and specifiers that
+/// cannot be resolved are kept as written.
+pub struct NodeImportResolver {
+    resolver: Arc<NodeResolver>,
+    kind: ResolveKind,
+}
+
+impl NodeImportResolver {
+    /// Resolves with the `package.json` conditions of `kind`, which should match
+    /// how the output loads its imports
+    pub fn new(resolver: Arc<NodeResolver>, kind: ResolveKind) -> Self {
+        Self { resolver, kind }
+    }
+}
+
+impl ImportResolver for NodeImportResolver {
+    fn resolve_import(&self, base: &FileName, module_specifier: &str) -> Result<Atom, Error> {
+        let FileName::Real(base) = base else {
+            return Ok(module_specifier.into());
+        };
+        let base_dir = normalize_path(base.parent().unwrap_or(Path::new(".")));
+
+        match self.resolver.resolve(module_specifier, &base_dir, self.kind) {
+            Ok(Resolved::File(path))
+                if !path.components().any(|c| c.as_os_str() == "node_modules") =>
+            {
+                Ok(relative_specifier(&base_dir, &path).into())
+            }
+            _ => Ok(module_specifier.into()),
+        }
+    }
+}
+
+/// A `./`-prefixed, `/`-separated path from `from_dir` to `to`
+fn relative_specifier(from_dir: &Path, to: &Path) -> String {
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let common = from
+        .iter()
+        .zip(&to_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = vec![];
+    parts.extend(std::iter::repeat_n("..".to_string(), from.len() - common));
+    parts.extend(
+        to_components[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+
+    let path = parts.join("/");
+    if path.starts_with("..") {
+        path
+    } else {
+        format!("./{path}")
+    }
+}
+
+pub(crate) fn is_relative(specifier: &str) -> bool {
+    specifier == "."
+        || specifier == ".."
+        || specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || Path::new(specifier).is_absolute()
+}
+
+/// Whether an `imports` target names a package, rather than a path, a URL or
+/// another `#` import
+fn is_package_name(target: &str) -> bool {
+    !target.is_empty()
+        && !target.starts_with(['#', '.', '/', '\\'])
+        && !target.contains(':')
+}
+
+/// Splits `@scope/name/sub/path` into `@scope/name` and `/sub/path`
+fn split_package_specifier(specifier: &str) -> (&str, &str) {
+    let name_end = if specifier.starts_with('@') {
+        specifier
+            .match_indices('/')
+            .nth(1)
+            .map(|(i, _)| i)
+            .unwrap_or(specifier.len())
+    } else {
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+    specifier.split_at(name_end)
+}
+
+/// Reads a JSON file; `jsonc` allows the comments and trailing commas of tsconfig files
+fn read_json(path: &Path, jsonc: bool) -> Result<Value, ResolveError> {
+    let invalid = |message: String| ResolveError::InvalidConfig {
+        path: path.to_path_buf(),
+        message,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+    let text = if jsonc { strip_jsonc(&text) } else { text };
+    serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))
+}
+
+/// Removes comments and trailing commas, leaving string contents untouched
+fn strip_jsonc(text: &str) -> String {
+    let mut without_comments = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            without_comments.push(c);
+            match c {
+                '\\' => without_comments.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                without_comments.push(c);
+            }
+        }
+    }
+
+    let mut out = String::with_capacity(without_comments.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in without_comments.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ','
+            && without_comments[i + 1..]
+                .trim_start()
+                .starts_with(['}', ']'])
+        {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("js_processor_resolver_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        for (file, contents) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_resolve_package_exports_conditions() {
+        let root = project(
+            "exports",
+            &[
+                (
+                    "node_modules/ui/package.json",
+                    r#"{
+                        "name": "ui",
+                        "exports": {
+                            ".": {
+                                "react-server": "./server.js",
+                                "import": "./index.mjs",
+                                "require": "./index.cjs"
+                            },
+                            "./icons/*": "./dist/icons/*.js",
+                            "./internal/*": null,
+                            "./up": "../legacy/lib/main.js",
+                            "./bare": "legacy"
+                        }
+                    }"#,
+                ),
+                ("node_modules/ui/server.js", ""),
+                ("node_modules/ui/index.mjs", ""),
+                ("node_modules/ui/index.cjs", ""),
+                ("node_modules/ui/dist/icons/star.js", ""),
+                ("node_modules/legacy/package.json", r#"{ "main": "lib/main" }"#),
+                ("node_modules/legacy/lib/main.js", ""),
+                ("src/app.ts", ""),
+            ],
+        );
+        let src = root.join("src");
+        let file = |path: &str| Resolved::File(root.join(path));
+
+        let resolver = NodeResolver::new();
+        assert_eq!(
+            resolver.resolve("ui", &src, ResolveKind::Import).unwrap(),
+            file("node_modules/ui/index.mjs")
+        );
+        assert_eq!(
+            resolver.resolve("ui", &src, ResolveKind::Require).unwrap(),
+            file("node_modules/ui/index.cjs")
+        );
+        assert_eq!(
+            resolver.resolve("ui/icons/star", &src, ResolveKind::Import).unwrap(),
+            file("node_modules/ui/dist/icons/star.js")
+        );
+        // `exports` targets stay inside the package
+        for subpath in ["ui/internal/x", "ui/up", "ui/bare", "ui/icons/../../server"] {
+            assert!(matches!(
+                resolver.resolve(subpath, &src, ResolveKind::Import),
+                Err(ResolveError::NotExported { .. })
+            ));
+        }
+        assert_eq!(
+            resolver.resolve("legacy", &src, ResolveKind::Require).unwrap(),
+            file("node_modules/legacy/lib/main.js")
+        );
+        assert_eq!(
+            resolver.resolve("node:fs", &src, ResolveKind::Import).unwrap(),
+            Resolved::Builtin("fs".into())
+        );
+
+        let server = NodeResolver::new().conditions(["react-server", "node"]);
+        assert_eq!(
+            server.resolve("ui", &src, ResolveKind::Import).unwrap(),
+            file("node_modules/ui/server.js")
+        );
+    }
+
+    #[test]
+    fn test_resolve_tsconfig_paths_and_imports() {
+        let root = project(
+            "tsconfig",
+            &[
+                (
+                    "tsconfig.json",
+                    r#"{
+                        // comments and trailing commas are allowed
+                        "compilerOptions": {
+                            "baseUrl": "./src",
+                            "paths": { "@components/*": ["components/*"], },
+                        },
+                    }"#,
+                ),
+                (
+                    "package.json",
+                    r##"{ "imports": {
+                        "#config": "./src/config.ts",
+                        "#env": { "import": "./src/env.mjs", "require": "./src/env.cjs" },
+                        "#lib": "lib",
+                        "#self": "#self",
+                        "#a": "#b",
+                        "#b": "#a"
+                    } }"##,
+                ),
+                ("node_modules/lib/index.js", ""),
+                ("src/components/Button.tsx", ""),
+                ("src/config.ts", ""),
+                ("src/env.mjs", ""),
+                ("src/env.cjs", ""),
+                ("src/utils/index.ts", ""),
+                ("src/pages/home.tsx", ""),
+            ],
+        );
+        let pages = root.join("src/pages");
+        let resolver = NodeResolver::for_project(&root).unwrap();
+
+        assert_eq!(
+            resolver
+                .resolve("@components/Button", &pages, ResolveKind::Import)
+                .unwrap(),
+            Resolved::File(root.join("src/components/Button.tsx"))
+        );
+        assert_eq!(
+            resolver.resolve("utils", &pages, ResolveKind::Import).unwrap(),
+            Resolved::File(root.join("src/utils/index.ts"))
+        );
+        assert_eq!(
+            resolver.resolve("#config", &pages, ResolveKind::Import).unwrap(),
+            Resolved::File(root.join("src/config.ts"))
+        );
+        assert_eq!(
+            resolver.resolve("#lib", &pages, ResolveKind::Import).unwrap(),
+            Resolved::File(root.join("node_modules/lib/index.js"))
+        );
+        // `imports` targets never lead to another `#` import, so cycles end
+        for specifier in ["#self", "#a"] {
+            assert!(matches!(
+                resolver.resolve(specifier, &pages, ResolveKind::Import),
+                Err(ResolveError::ImportNotDefined { .. })
+            ));
+        }
+        assert!(matches!(
+            resolver.resolve("missing", &pages, ResolveKind::Import),
+            Err(ResolveError::NotFound { .. })
+        ));
+
+        let resolver = Arc::new(resolver);
+        let import_resolver = NodeImportResolver::new(resolver.clone(), ResolveKind::Import);
+        let base = FileName::Real(root.join("src/pages/home.tsx"));
+        assert_eq!(
+            &*import_resolver
+                .resolve_import(&base, "@components/Button")
+                .unwrap(),
+            "../components/Button.tsx"
+        );
+        assert_eq!(&*import_resolver.resolve_import(&base, "react").unwrap(), "react");
+        assert_eq!(&*import_resolver.resolve_import(&base, "#env").unwrap(), "../env.mjs");
+        let require_resolver = NodeImportResolver::new(resolver, ResolveKind::Require);
+        assert_eq!(&*require_resolver.resolve_import(&base, "#env").unwrap(), "../env.cjs");
+    }
+}
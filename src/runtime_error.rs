@@ -0,0 +1,170 @@
+use crate::diagnostics::ParseDiagnostic;
+use serde::Serialize;
+use ssr_rs::v8;
+use std::fmt;
+
+/// A JavaScript error with the location it was raised at
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsError {
+    pub message: String,
+    /// The `stack` of the thrown value, when it is an `Error`
+    pub stack: Option<String>,
+    pub script_name: Option<String>,
+    /// 1-based line
+    pub line: Option<usize>,
+    /// 1-based column
+    pub column: Option<usize>,
+}
+
+impl JsError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The exception `try_catch` caught, or a note that execution was terminated
+    pub(crate) fn from_try_catch(try_catch: &mut v8::TryCatch<v8::HandleScope>) -> Self {
+        match try_catch.exception() {
+            Some(exception) => Self::from_exception(try_catch, exception),
+            None => Self::new("execution was terminated"),
+        }
+    }
+
+    /// Describes a thrown value, such as the reason of a rejected promise
+    pub(crate) fn from_exception(
+        scope: &mut v8::HandleScope,
+        exception: v8::Local<v8::Value>,
+    ) -> Self {
+        let message = v8::Exception::create_message(scope, exception);
+        let stack = exception.to_object(scope).and_then(|object| {
+            let key = v8::String::new(scope, "stack")?;
+            object
+                .get(scope, key.into())
+                .filter(|stack| stack.is_string())
+                .map(|stack| stack.to_rust_string_lossy(scope))
+        });
+
+        Self {
+            message: exception.to_rust_string_lossy(scope),
+            stack,
+            script_name: message
+                .get_script_resource_name(scope)
+                .filter(|name| name.is_string())
+                .map(|name| name.to_rust_string_lossy(scope)),
+            line: message.get_line_number(scope),
+            column: message
+                .get_line_number(scope)
+                .map(|_| message.get_start_column() + 1),
+        }
+    }
+}
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(script_name) = &self.script_name {
+            write!(f, "{script_name}:")?;
+            if let (Some(line), Some(column)) = (self.line, self.column) {
+                write!(f, "{line}:{column}:")?;
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Why a [`crate::JsHttpRequestProcessor`] could not load or run its script
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(tag = "kind", content = "error", rename_all = "camelCase")]
+pub enum RuntimeError {
+    /// SWC could not compile the script to JavaScript V8 runs
+    #[error("failed to transpile {0}")]
+    Transpile(JsError),
+    /// V8 rejected the script, usually with a `SyntaxError`
+    #[error("failed to compile {0}")]
+    Compile(JsError),
+    /// The imports of a module could not be linked
+    #[error("failed to instantiate {0}")]
+    Instantiate(JsError),
+    /// The top level of the script threw
+    #[error("failed to evaluate {0}")]
+    Evaluate(JsError),
+    /// A call into the script threw
+    #[error("uncaught exception {0}")]
+    Exception(JsError),
+    /// The script defines no `Process` function
+    #[error("missing entrypoint: {0}")]
+    MissingEntrypoint(JsError),
+    /// `output` does not describe a valid HTTP response
+    #[error("invalid response: {0}")]
+    Response(JsError),
+    /// The thread running the script exited, so no JavaScript ran
+    #[error("the script worker stopped")]
+    WorkerStopped,
+    /// The script ran past [`crate::script_pool::ScriptConfig::timeout_ms`] and was terminated
+    #[error("the script ran for more than {0} ms")]
+    Timeout(u64),
+}
+
+impl RuntimeError {
+    /// A [`Self::Transpile`] error, located when SWC failed to parse
+    pub fn transpile(error: &anyhow::Error, script_name: &str) -> Self {
+        Self::Transpile(match error.downcast_ref::<ParseDiagnostic>() {
+            Some(diagnostic) => JsError {
+                message: diagnostic.message.clone(),
+                stack: None,
+                script_name: Some(diagnostic.file_name.clone()),
+                line: Some(diagnostic.start_line),
+                column: Some(diagnostic.start_column),
+            },
+            None => JsError {
+                script_name: Some(script_name.to_string()),
+                ..JsError::new(format!("{error:#}"))
+            },
+        })
+    }
+
+    /// The JavaScript error, whichever step failed, unless the script never ran
+    pub fn js_error(&self) -> Option<&JsError> {
+        match self {
+            Self::Transpile(error)
+            | Self::Compile(error)
+            | Self::Instantiate(error)
+            | Self::Evaluate(error)
+            | Self::Exception(error)
+            | Self::MissingEntrypoint(error)
+            | Self::Response(error) => Some(error),
+            Self::WorkerStopped | Self::Timeout(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::js_parser::JavaScriptParser;
+
+    #[test]
+    fn test_transpile_error_location() {
+        let error = JavaScriptParser::new()
+            .parse_code("const a = ;", Some("in.js"))
+            .unwrap_err();
+        let error = RuntimeError::transpile(&error, "in.js");
+
+        let js_error = error.js_error().unwrap();
+        assert_eq!(js_error.script_name.as_deref(), Some("in.js"));
+        assert_eq!((js_error.line, js_error.column), (Some(1), Some(11)));
+        assert!(error.to_string().starts_with("failed to transpile in.js:1:11: "));
+
+        let error = RuntimeError::transpile(&anyhow::anyhow!("no such target"), "in.js");
+        assert_eq!(error.to_string(), "failed to transpile in.js: no such target");
+        assert_eq!(
+            RuntimeError::MissingEntrypoint(JsError::new("function Process is not defined"))
+                .to_string(),
+            "missing entrypoint: function Process is not defined"
+        );
+        assert_eq!(RuntimeError::WorkerStopped.js_error(), None);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::processor_response::ProcessorResponse;
+use crate::runtime_error::RuntimeError;
+use crate::{JsHttpRequestProcessor, StringHttpRequest};
+use anyhow::Context;
+use serde::Deserialize;
+use ssr_rs::v8;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, Once, mpsc};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Initializes V8 once for the whole process, as every isolate shares its platform
+pub fn init_v8() {
+    static INIT: Once = Once::new();
+    INIT.call_once(ssr_rs::Ssr::create_platform);
+}
+
+/// A script whose `Process` function handles requests
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScriptConfig {
+    pub script: PathBuf,
+    /// Passed to the script as the global `options`
+    #[serde(default)]
+    pub options: HashMap<String, String>,
+    /// The number of isolates running the script, each handling one request at a time
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// How long loading the script or handling a request may run before it is terminated
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, usize::from)
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+impl ScriptConfig {
+    pub fn new(script: impl Into<PathBuf>) -> Self {
+        Self {
+            script: script.into(),
+            options: HashMap::new(),
+            workers: default_workers(),
+            timeout_ms: default_timeout_ms(),
+        }
+    }
+
+    pub fn option(mut self, name: &str, value: &str) -> Self {
+        self.options.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    pub fn timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+}
+
+type Job = (
+    StringHttpRequest,
+    oneshot::Sender<Result<ProcessorResponse, RuntimeError>>,
+);
+
+/// Threads running a script in their own V8 isolates, which take requests in turn
+///
+/// Each worker loads the script once, so globals it sets last across the
+/// requests that worker handles. A worker that panics is replaced by a fresh
+/// one, and the workers stop when the pool is dropped.
+pub struct ScriptPool {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl ScriptPool {
+    /// Reads the script and starts its workers
+    pub fn start(config: &ScriptConfig) -> std::io::Result<Self> {
+        let source: Arc<str> = std::fs::read_to_string(&config.script)?.into();
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..config.workers.max(1) {
+            Worker {
+                name: format!("js-worker-{i}"),
+                source: source.clone(),
+                options: config.options.clone(),
+                timeout_ms: config.timeout_ms,
+                jobs: receiver.clone(),
+            }
+            .spawn()?;
+        }
+        Ok(Self { jobs: sender })
+    }
+
+    /// Runs `Process` for `request` on the next free worker
+    pub async fn process(
+        &self,
+        request: StringHttpRequest,
+    ) -> Result<ProcessorResponse, RuntimeError> {
+        let (sender, receiver) = oneshot::channel();
+        self.jobs
+            .send((request, sender))
+            .map_err(|_| RuntimeError::WorkerStopped)?;
+        receiver.await.map_err(|_| RuntimeError::WorkerStopped)?
+    }
+}
+
+/// A thread of a [`ScriptPool`], with what it needs to start its replacement
+#[derive(Clone)]
+struct Worker {
+    name: String,
+    source: Arc<str>,
+    options: HashMap<String, String>,
+    timeout_ms: u64,
+    jobs: Arc<Mutex<mpsc::Receiver<Job>>>,
+}
+
+impl Worker {
+    fn spawn(self) -> std::io::Result<()> {
+        std::thread::Builder::new()
+            .name(self.name.clone())
+            .spawn(move || self.run())?;
+        Ok(())
+    }
+
+    /// Loads the script and processes requests until the pool is dropped
+    ///
+    /// A script that fails to load answers every request with its error. The
+    /// request being handled when the worker panics gets
+    /// [`RuntimeError::WorkerStopped`].
+    fn run(self) {
+        let respawn = Respawn(self);
+        let worker = &respawn.0;
+
+        init_v8();
+        let isolate = &mut v8::Isolate::new(v8::CreateParams::default());
+        let watchdog = match Watchdog::start(isolate.thread_safe_handle(), worker.timeout_ms) {
+            Ok(watchdog) => watchdog,
+            Err(error) => {
+                eprintln!("{}: failed to start its watchdog: {error}", worker.name);
+                return;
+            }
+        };
+        let isolate_scope = &mut v8::HandleScope::new(isolate);
+        let source = v8::String::new(isolate_scope, &worker.source).unwrap();
+        let options = worker.options.clone();
+        let mut processor =
+            watchdog.run(|| JsHttpRequestProcessor::new(isolate_scope, source, options));
+
+        loop {
+            // the lock is released before the request is processed
+            let Ok((request, reply)) = worker.jobs.lock().unwrap().recv() else {
+                break;
+            };
+            let response = match &mut processor {
+                Ok(processor) => watchdog.run(|| processor.process(request)),
+                Err(error) => Err(error.clone()),
+            };
+            let _ = reply.send(response);
+        }
+    }
+}
+
+/// Starts a new worker in place of one that panicked
+struct Respawn(Worker);
+
+impl Drop for Respawn {
+    fn drop(&mut self) {
+        if std::thread::panicking()
+            && let Err(error) = self.0.clone().spawn()
+        {
+            eprintln!("failed to restart {}: {error}", self.0.name);
+        }
+    }
+}
+
+/// Terminates the script of a worker when a call runs past its deadline
+struct Watchdog {
+    state: Arc<(Mutex<WatchState>, Condvar)>,
+    isolate: v8::IsolateHandle,
+    timeout_ms: u64,
+}
+
+#[derive(Default)]
+struct WatchState {
+    deadline: Option<Instant>,
+    timed_out: bool,
+    stopped: bool,
+}
+
+impl Watchdog {
+    fn start(isolate: v8::IsolateHandle, timeout_ms: u64) -> std::io::Result<Self> {
+        let state = Arc::new((Mutex::new(WatchState::default()), Condvar::new()));
+        std::thread::Builder::new()
+            .name(format!("{}-watchdog", std::thread::current().name().unwrap_or("js")))
+            .spawn({
+                let state = state.clone();
+                let isolate = isolate.clone();
+                move || watch(&state, &isolate)
+            })?;
+        Ok(Self {
+            state,
+            isolate,
+            timeout_ms,
+        })
+    }
+
+    /// Runs `f`, failing with [`RuntimeError::Timeout`] if the script had to be terminated
+    fn run<T>(&self, f: impl FnOnce() -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        let (state, wake) = &*self.state;
+        state.lock().unwrap().deadline =
+            Some(Instant::now() + Duration::from_millis(self.timeout_ms));
+        wake.notify_one();
+
+        let result = f();
+
+        // the watchdog only terminates while there is a deadline, so once it is
+        // cleared the termination can be cancelled for good
+        let timed_out = {
+            let mut state = state.lock().unwrap();
+            state.deadline = None;
+            std::mem::take(&mut state.timed_out)
+        };
+        if timed_out {
+            self.isolate.cancel_terminate_execution();
+            return Err(RuntimeError::Timeout(self.timeout_ms));
+        }
+        result
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        let (state, wake) = &*self.state;
+        state.lock().unwrap().stopped = true;
+        wake.notify_one();
+    }
+}
+
+fn watch((state, wake): &(Mutex<WatchState>, Condvar), isolate: &v8::IsolateHandle) {
+    let mut state = state.lock().unwrap();
+    while !state.stopped {
+        state = match state.deadline {
+            None => wake.wait(state).unwrap(),
+            Some(deadline) => {
+                let now = Instant::now();
+                if now < deadline {
+                    wake.wait_timeout(state, deadline - now).unwrap().0
+                } else {
+                    isolate.terminate_execution();
+                    state.deadline = None;
+                    state.timed_out = true;
+                    state
+                }
+            }
+        };
+    }
+}
+
+/// The scripts handling each path prefix, for
+/// [`crate::actix_integration::handle_js_request`]
+#[derive(Clone, Default)]
+pub struct ScriptRoutes {
+    /// Longest prefix first
+    routes: Vec<(String, Arc<ScriptPool>)>,
+}
+
+impl ScriptRoutes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `prefix` and the paths under it with `pool`; `/` serves every path
+    pub fn route(mut self, prefix: &str, pool: ScriptPool) -> Self {
+        self.routes
+            .push((prefix.trim_end_matches('/').to_string(), Arc::new(pool)));
+        self.routes
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        self
+    }
+
+    /// Starts the scripts of a JSON file mapping path prefixes to [`ScriptConfig`]s
+    ///
+    /// ```json
+    /// { "/js/api": { "script": "api.js", "options": { "region": "eu" }, "workers": 2 } }
+    /// ```
+    ///
+    /// Relative script paths are resolved against the directory of the file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let routes = parse_routes(&std::fs::read_to_string(path)?, path)
+            .with_context(|| format!("invalid script routes in {}", path.display()))?;
+        routes
+            .into_iter()
+            .try_fold(Self::new(), |routes, (prefix, config)| {
+                let pool = ScriptPool::start(&config)
+                    .with_context(|| format!("failed to start {}", config.script.display()))?;
+                Ok(routes.route(&prefix, pool))
+            })
+    }
+
+    /// The pool of the longest prefix `path` is under
+    pub fn pool(&self, path: &str) -> Option<&ScriptPool> {
+        self.routes
+            .iter()
+            .find(|(prefix, _)| is_under(path, prefix))
+            .map(|(_, pool)| &**pool)
+    }
+}
+
+fn parse_routes(json: &str, path: &Path) -> anyhow::Result<BTreeMap<String, ScriptConfig>> {
+    let mut routes: BTreeMap<String, ScriptConfig> = serde_json::from_str(json)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for config in routes.values_mut() {
+        config.script = dir.join(&config.script);
+    }
+    Ok(routes)
+}
+
+/// Whether `path` is `prefix`, without a trailing slash, or a path under it
+fn is_under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_routes() {
+        let routes = parse_routes(
+            r#"{
+                "/js/api": {
+                    "script": "api.js",
+                    "options": { "region": "eu" },
+                    "workers": 2,
+                    "timeout_ms": 500
+                },
+                "/": { "script": "/srv/index.js" }
+            }"#,
+            Path::new("config/routes.json"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            routes["/js/api"],
+            ScriptConfig::new("config/api.js")
+                .option("region", "eu")
+                .workers(2)
+                .timeout_ms(500)
+        );
+        assert_eq!(routes["/"].script, Path::new("/srv/index.js"));
+        assert_eq!(routes["/"].workers, default_workers());
+        assert_eq!(routes["/"].timeout_ms, default_timeout_ms());
+
+        let error = parse_routes(
+            r#"{ "/": { "path": "index.js" } }"#,
+            Path::new("routes.json"),
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown field `path`"));
+    }
+
+    #[test]
+    fn test_routes_match_whole_segments() {
+        assert!(is_under("/js/api", "/js/api"));
+        assert!(is_under("/js/api/users", "/js/api"));
+        assert!(!is_under("/js/apis", "/js/api"));
+        // the `/` route, with its slash trimmed
+        assert!(is_under("/", ""));
+        assert!(is_under("/anything", ""));
+    }
+}
@@ -23,4 +23,80 @@ mod test_simple {
         assert_eq!(request.user_agent(), "Mozilla/5.0");
         assert_eq!(request.referrer(), "https://example.com");
     }
+
+    #[test]
+    fn test_string_http_request_full_model() {
+        let request = StringHttpRequest::new("/search?q=rust%20js&page=2", "example.com", "", "")
+            .with_method("POST")
+            .with_header("Cookie", "session=abc; theme=\"dark\"; broken")
+            .with_header("Content-Type", "application/json")
+            .with_body(r#"{"ok":true}"#);
+
+        assert_eq!(request.path(), "/search");
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.header("content-type"), Some("application/json"));
+        assert_eq!(request.header("CONTENT-TYPE"), Some("application/json"));
+        assert_eq!(
+            request.query(),
+            vec![("q".to_string(), "rust js".to_string()), ("page".to_string(), "2".to_string())]
+        );
+        assert_eq!(
+            request.cookies(),
+            vec![("session".to_string(), "abc".to_string()), ("theme".to_string(), "dark".to_string())]
+        );
+        assert_eq!(request.remote_addr(), None);
+        assert_eq!(request.protocol(), "http");
+        assert_eq!(request.text(), r#"{"ok":true}"#);
+        assert_eq!(request.json().unwrap()["ok"], true);
+        assert!(request.headers().contains(&("host", "example.com")));
+    }
+
+    #[test]
+    fn test_string_http_request_binary_body() {
+        let request = StringHttpRequest::new("/upload", "example.com", "", "")
+            .with_body(vec![0x89, b'P', b'N', b'G', 0xff]);
+
+        assert_eq!(request.body(), [0x89, b'P', b'N', b'G', 0xff]);
+        assert_eq!(request.text(), "\u{fffd}PNG\u{fffd}");
+        assert!(request.json().is_err());
+    }
+
+    #[test]
+    fn test_string_http_request_repeated_headers() {
+        let request = StringHttpRequest::new("/", "example.com", "", "")
+            .with_header("Cookie", "a=1")
+            .with_header("cookie", "b=2");
+
+        // empty arguments are not headers
+        assert_eq!(request.header("referer"), None);
+        assert_eq!(
+            request.headers(),
+            vec![("host", "example.com"), ("cookie", "a=1"), ("cookie", "b=2")]
+        );
+        assert_eq!(request.header("cookie"), Some("a=1"));
+        assert_eq!(
+            request.cookies(),
+            vec![("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]
+        );
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/js/items?page=2")
+            .insert_header(("host", "example.com"))
+            .append_header(("accept", "text/html"))
+            .append_header(("accept", "application/json"))
+            .to_http_request();
+        let request = crate::actix_integration::string_request(&req, b"\xff");
+        assert_eq!((request.method(), request.path()), ("POST", "/js/items"));
+        assert_eq!(request.query_string(), "page=2");
+        let accept: Vec<_> = request
+            .headers()
+            .into_iter()
+            .filter(|(name, _)| *name == "accept")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(accept, ["text/html", "application/json"]);
+        assert_eq!(request.user_agent(), "");
+        assert_eq!(request.host(), "example.com");
+        assert_eq!(request.body(), b"\xff");
+    }
 }
\ No newline at end of file
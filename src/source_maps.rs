@@ -0,0 +1,238 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sourcemap::{
+    DecodedMap, SourceMap as RawSourceMap, decode_data_url, locate_sourcemap_reference_slice,
+};
+use std::fs;
+use std::path::Path;
+use swc_common::FileName;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::source_map::SourceMapGenConfig;
+
+/// How [`crate::react_compiler::ReactCompiler`] emits a source map for its output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SourceMaps {
+    /// No map is generated
+    #[default]
+    None,
+    /// The map is returned in [`CompileOutput::map`] and the code ends with a
+    /// `sourceMappingURL` comment naming `<file>.js.map`
+    External,
+    /// The code ends with the map as a base64 `data:` URI, which is also
+    /// returned in [`CompileOutput::map`]
+    Inline,
+}
+
+/// Compiled code and the source map JSON tracing it back to the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileOutput {
+    pub code: String,
+    pub map: Option<String>,
+}
+
+/// Where a position of compiled code came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OriginalPosition {
+    pub source: String,
+    /// 0-based, like the mappings of the map
+    pub line: u32,
+    /// 0-based, in UTF-16 code units
+    pub column: u32,
+    pub name: Option<String>,
+}
+
+impl CompileOutput {
+    /// Writes the code to `path` and the map, if any, next to it as `<path>.map`
+    ///
+    /// With [`SourceMaps::External`] the `sourceMappingURL` comment points there
+    /// as long as `path` has the source's file stem and a `.js` extension.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        fs::write(path, &self.code).with_context(|| format!("failed to write {path:?}"))?;
+        if let Some(map) = &self.map {
+            let mut map_path = path.as_os_str().to_owned();
+            map_path.push(".map");
+            fs::write(&map_path, map).with_context(|| format!("failed to write {map_path:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Maps a 0-based line and column of [`Self::code`] back to the source, for
+    /// example to rewrite the frames of an SSR stack trace
+    pub fn original_position(&self, line: u32, column: u32) -> Option<OriginalPosition> {
+        let map = RawSourceMap::from_slice(self.map.as_ref()?.as_bytes()).ok()?;
+        let token = map.lookup_token(line, column)?;
+
+        Some(OriginalPosition {
+            source: token.get_source()?.to_string(),
+            line: token.get_src_line(),
+            column: token.get_src_col(),
+            name: token.get_name().map(str::to_string),
+        })
+    }
+}
+
+/// Names sources by the filename they were compiled with and embeds their text,
+/// so devtools can show the TSX without fetching it
+pub(crate) struct SourceMapConfig;
+
+impl SourceMapGenConfig for SourceMapConfig {
+    fn file_name_to_source(&self, f: &FileName) -> String {
+        f.to_string()
+    }
+
+    fn inline_sources_content(&self, _f: &FileName) -> bool {
+        true
+    }
+}
+
+/// The map a `//# sourceMappingURL=` comment of `code` points to
+///
+/// Data URIs are decoded and other URLs are read relative to `filename`. A
+/// missing or unreadable map is treated as no map.
+pub(crate) fn input_source_map(code: &str, filename: &str) -> Option<RawSourceMap> {
+    let reference = locate_sourcemap_reference_slice(code.as_bytes()).ok()??;
+    let url = reference.get_url();
+    let decoded = if let Some(data) = url.strip_prefix("data:application/json;") {
+        // `decode_data_url` only knows the form without a `charset` parameter
+        let (_, base64) = data.split_once("base64,")?;
+        decode_data_url(&format!("data:application/json;base64,{base64}")).ok()?
+    } else {
+        let path = Path::new(filename).parent()?.join(url);
+        DecodedMap::from_reader(fs::File::open(path).ok()?).ok()?
+    };
+
+    match decoded {
+        DecodedMap::Regular(map) => Some(map),
+        DecodedMap::Index(index) => index.flatten().ok(),
+        DecodedMap::Hermes(_) => None,
+    }
+}
+
+/// Drops `sourceMappingURL` comments, which would point at the input's map
+pub(crate) fn strip_source_mapping_urls(comments: &SingleThreadedComments) {
+    let (mut leading, mut trailing) = comments.borrow_all_mut();
+    for map in [&mut *leading, &mut *trailing] {
+        map.retain(|_, list| {
+            list.retain(|comment| {
+                let text = comment.text.trim_start();
+                !(text.starts_with("# sourceMappingURL=")
+                    || text.starts_with("@ sourceMappingURL="))
+            });
+            !list.is_empty()
+        });
+    }
+}
+
+/// The comment that links `code` to its map
+pub(crate) fn source_mapping_url(
+    mode: SourceMaps,
+    map: &RawSourceMap,
+    filename: &str,
+) -> Result<Option<String>> {
+    let url = match mode {
+        SourceMaps::None => return Ok(None),
+        SourceMaps::Inline => map.to_data_url()?,
+        SourceMaps::External => format!("{}.map", output_file_name(filename)),
+    };
+    Ok(Some(format!("//# sourceMappingURL={url}\n")))
+}
+
+/// The file name compiled code of `filename` is served as
+pub(crate) fn output_file_name(filename: &str) -> String {
+    Path::new(filename)
+        .with_extension("js")
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "output.js".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::react_compiler::ReactCompiler;
+    use sourcemap::SourceMapBuilder;
+
+    const COMPONENT: &str = "import React from 'react';
+
+interface Props {
+    name: string;
+}
+
+export default function Greeting({ name }: Props) {
+    return <div>Hello, {name}</div>;
+}
+";
+
+    #[test]
+    fn test_external_source_map() {
+        let output = ReactCompiler::new()
+            .with_source_maps(SourceMaps::External)
+            .compile(COMPONENT, Some("greeting.tsx"))
+            .unwrap();
+
+        assert!(output.code.ends_with("//# sourceMappingURL=greeting.js.map\n"));
+        let map = RawSourceMap::from_slice(output.map.as_ref().unwrap().as_bytes()).unwrap();
+        assert_eq!(map.get_file(), Some("greeting.js"));
+        assert_eq!(map.get_source(0), Some("greeting.tsx"));
+        assert_eq!(map.get_source_contents(0), Some(COMPONENT));
+
+        // `<div>` is on line 7 of the source
+        let line = output
+            .code
+            .lines()
+            .position(|line| line.contains("createElement(\"div\""))
+            .unwrap();
+        let column = output.code.lines().nth(line).unwrap().find("_react").unwrap();
+        let position = output.original_position(line as u32, column as u32).unwrap();
+        assert_eq!(position.source, "greeting.tsx");
+        assert_eq!(position.line, 7);
+    }
+
+    #[test]
+    fn test_inline_and_no_source_map() {
+        let inline = ReactCompiler::new()
+            .with_source_maps(SourceMaps::Inline)
+            .compile(COMPONENT, Some("greeting.tsx"))
+            .unwrap();
+        assert!(
+            inline
+                .code
+                .contains("//# sourceMappingURL=data:application/json;charset=utf-8;base64,")
+        );
+        assert!(inline.map.is_some());
+
+        let none = ReactCompiler::new().compile(COMPONENT, Some("greeting.tsx")).unwrap();
+        assert!(!none.code.contains("sourceMappingURL"));
+        assert_eq!(none.map, None);
+    }
+
+    #[test]
+    fn test_composes_with_input_source_map() {
+        // an earlier step moved every line of `original.ts` down by two
+        let mut builder = SourceMapBuilder::new(Some("greeting.tsx"));
+        let src = builder.add_source("original.ts");
+        for line in 0..COMPONENT.lines().count() as u32 {
+            builder.add_raw(line, 0, line + 2, 0, Some(src), None, false);
+            builder.add_raw(line, 4, line + 2, 4, Some(src), None, false);
+        }
+        let input_map = builder.into_sourcemap().to_data_url().unwrap();
+        let code = format!("{COMPONENT}//# sourceMappingURL={input_map}\n");
+
+        let output = ReactCompiler::new()
+            .with_source_maps(SourceMaps::External)
+            .compile(&code, Some("greeting.tsx"))
+            .unwrap();
+
+        assert_eq!(output.code.matches("sourceMappingURL").count(), 1);
+        let line = output
+            .code
+            .lines()
+            .position(|line| line.contains("createElement(\"div\""))
+            .unwrap();
+        let column = output.code.lines().nth(line).unwrap().find("_react").unwrap();
+        let position = output.original_position(line as u32, column as u32).unwrap();
+        assert_eq!(position.source, "original.ts");
+        assert_eq!(position.line, 9);
+    }
+}
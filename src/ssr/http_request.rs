@@ -1,14 +1,108 @@
+use std::borrow::Cow;
+
 /// A trait representing HTTP request information needed for server-side rendering
+///
+/// Only the path and the three headers are required; the rest have defaults
+/// derived from them, so simple implementations stay simple.
 pub trait SimpleHttpRequest {
     /// Returns the request path
     fn path(&self) -> &str;
-    
+
     /// Returns the user agent header
     fn user_agent(&self) -> &str;
-    
-    /// Returns the referrer header  
+
+    /// Returns the referrer header
     fn referrer(&self) -> &str;
-    
+
     /// Returns the host header
     fn host(&self) -> &str;
-}
\ No newline at end of file
+
+    /// Returns the request method, such as `GET` or `POST`
+    fn method(&self) -> &str {
+        "GET"
+    }
+
+    /// Returns every header as a lowercase name and its value, repeating the
+    /// name for each value of a header sent more than once
+    fn headers(&self) -> Vec<(&str, &str)> {
+        [
+            ("host", self.host()),
+            ("user-agent", self.user_agent()),
+            ("referer", self.referrer()),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .collect()
+    }
+
+    /// Returns the first value of the header `name`, matched case-insensitively
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers()
+            .into_iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the query string, without the leading `?`
+    fn query_string(&self) -> &str {
+        ""
+    }
+
+    /// Returns the decoded query parameters, in order and with repeats
+    fn query(&self) -> Vec<(String, String)> {
+        form_urlencoded::parse(self.query_string().as_bytes())
+            .into_owned()
+            .collect()
+    }
+
+    /// Returns the cookies of every `cookie` header
+    fn cookies(&self) -> Vec<(String, String)> {
+        self.headers()
+            .into_iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("cookie"))
+            .flat_map(|(_, value)| parse_cookies(value))
+            .collect()
+    }
+
+    /// Returns the address of the client, when it is known
+    fn remote_addr(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the scheme the request was made with, `http` or `https`
+    fn protocol(&self) -> &str {
+        "http"
+    }
+
+    /// Returns the raw request body
+    fn body(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Returns the body as text, replacing invalid UTF-8
+    fn text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(self.body())
+    }
+
+    /// Parses the body as JSON
+    fn json(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::from_slice(self.body())
+    }
+}
+
+/// Parses a `cookie` header such as `a=1; b="two"`, skipping malformed pairs
+pub fn parse_cookies(header: &str) -> Vec<(String, String)> {
+    header
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            (!name.is_empty()).then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
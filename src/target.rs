@@ -0,0 +1,154 @@
+use anyhow::Result;
+use preset_env_base::query::{Query, targets_to_versions};
+use swc_common::Mark;
+use swc_common::comments::Comments;
+use swc_ecma_ast::{EsVersion, Pass, noop_pass};
+use swc_ecma_preset_env::{
+    Config as EnvConfig, Mode, Targets, Version, transform_from_env, transform_from_es_version,
+};
+use swc_ecma_transforms_base::assumptions::Assumptions;
+
+/// The environments compiled output must run in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Engines supporting everything up to this version
+    EsVersion(EsVersion),
+    /// The browsers a browserslist query such as `"defaults"` or
+    /// `"> 0.5%, last 2 versions, not dead"` selects
+    Browsers(String),
+}
+
+/// Downleveling settings for [`crate::react_compiler::ReactCompiler::with_target`]
+///
+/// Syntax the target lacks, such as optional chaining, class fields or
+/// `async`/`await`, is rewritten with the matching preset-env transforms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetOptions {
+    pub target: Target,
+    /// The core-js version whose modules are imported for the built-ins the
+    /// code uses and the target lacks, or `None` to add no polyfills
+    ///
+    /// Only a [`Target::Browsers`] target says which built-ins are missing.
+    pub core_js: Option<String>,
+}
+
+impl TargetOptions {
+    pub fn es_version(version: EsVersion) -> Self {
+        Self {
+            target: Target::EsVersion(version),
+            core_js: None,
+        }
+    }
+
+    pub fn browsers(query: &str) -> Self {
+        Self {
+            target: Target::Browsers(query.to_string()),
+            core_js: None,
+        }
+    }
+
+    /// Imports core-js `version` polyfills by usage
+    pub fn polyfills(mut self, version: &str) -> Self {
+        self.core_js = Some(version.to_string());
+        self
+    }
+
+    /// The preset-env config of a browsers target, failing if the query or
+    /// core-js version is invalid
+    pub(crate) fn env_config(&self) -> Result<Option<EnvConfig>> {
+        let query = match &self.target {
+            Target::Browsers(query) => query,
+            Target::EsVersion(_) if self.core_js.is_some() => {
+                anyhow::bail!("polyfills need a browserslist target")
+            }
+            Target::EsVersion(_) => return Ok(None),
+        };
+        // resolved here, since preset-env panics on queries it cannot resolve
+        let versions = targets_to_versions(Some(Targets::Query(Query::from(query.clone()))), None)?;
+        let core_js = match &self.core_js {
+            Some(version) => Some(
+                version
+                    .parse::<Version>()
+                    .map_err(|_| anyhow::anyhow!("invalid core-js version `{version}`"))?,
+            ),
+            None => None,
+        };
+
+        Ok(Some(EnvConfig {
+            mode: core_js.map(|_| Mode::Usage),
+            core_js,
+            targets: Some(Targets::Versions(*versions)),
+            ..Default::default()
+        }))
+    }
+
+    /// The transforms downleveling to this target, which must run before the
+    /// module transforms so polyfill imports are compiled with the rest
+    pub(crate) fn pass<'a, C>(
+        &self,
+        unresolved_mark: Mark,
+        comments: Option<C>,
+    ) -> Result<Box<dyn Pass + 'a>>
+    where
+        C: Comments + Clone + 'a,
+    {
+        Ok(match (&self.target, self.env_config()?) {
+            (_, Some(config)) => Box::new(transform_from_env(
+                unresolved_mark,
+                comments,
+                config.into(),
+                Assumptions::default(),
+            )),
+            (Target::EsVersion(EsVersion::EsNext), None) => Box::new(noop_pass()),
+            (Target::EsVersion(version), None) => Box::new(transform_from_es_version(
+                unresolved_mark,
+                comments,
+                *version,
+                Assumptions::default(),
+                false,
+            )),
+            (Target::Browsers(_), None) => unreachable!("browsers always have an env config"),
+        })
+    }
+}
+
+impl Default for TargetOptions {
+    /// Output keeps the syntax of the source
+    fn default() -> Self {
+        Self::es_version(EsVersion::EsNext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_targets_fail() {
+        assert!(
+            TargetOptions::browsers("defaults")
+                .env_config()
+                .unwrap()
+                .is_some()
+        );
+        assert!(TargetOptions::default().env_config().unwrap().is_none());
+
+        assert!(
+            TargetOptions::browsers("not a browser 12")
+                .env_config()
+                .is_err()
+        );
+        assert!(
+            TargetOptions::es_version(EsVersion::Es5)
+                .polyfills("3.38")
+                .env_config()
+                .is_err()
+        );
+        assert!(
+            TargetOptions::browsers("defaults")
+                .polyfills("three")
+                .env_config()
+                .is_err()
+        );
+    }
+}
@@ -2,6 +2,10 @@
 mod test {
     use crate::JsHttpRequestProcessor;
     use crate::StringHttpRequest;
+    use crate::actix_integration::create_app;
+    use crate::runtime_error::RuntimeError;
+    use crate::script_pool::{ScriptConfig, ScriptPool, ScriptRoutes, init_v8};
+    use actix_web::test as actix_test;
     use std::collections::HashMap;
     use std::fs;
     use ssr_rs::v8;
@@ -10,9 +14,7 @@ mod test {
     #[test]
     fn test_editor_ssr_require() {
         GLOBALS.set(&Default::default(), || {
-            let platform = ssr_rs::v8::new_default_platform(0, false).make_shared();
-            ssr_rs::v8::V8::initialize_platform(platform);
-            ssr_rs::v8::V8::initialize();
+            init_v8();
 
             let isolate = &mut ssr_rs::v8::Isolate::new(ssr_rs::v8::CreateParams::default());
             let mut isolate_scope = ssr_rs::v8::HandleScope::new(isolate);
@@ -29,10 +31,10 @@ mod test {
             });
             let source = ssr_rs::v8::String::new(&mut isolate_scope, &source).unwrap();
 
-            let mut processor = JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new());
+            let mut processor = JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new()).unwrap();
 
             let request = StringHttpRequest::new("/test-path", "example.com", "test-agent", "test-referer");
-            processor.process(request);
+            processor.process(request).unwrap();
 
             let output_str = v8::String::new(&mut processor.context_scope, "output").unwrap();
             let output_obj = processor.context.global(&mut processor.context_scope)
@@ -55,4 +57,308 @@ mod test {
             assert_eq!(status_int, 200);
         });
     }
+
+    #[test]
+    fn test_script_errors() {
+        GLOBALS.set(&Default::default(), || {
+            init_v8();
+            let isolate = &mut v8::Isolate::new(v8::CreateParams::default());
+            let mut isolate_scope = v8::HandleScope::new(isolate);
+
+            let mut load = |code: &str| {
+                let source = v8::String::new(&mut isolate_scope, code).unwrap();
+                JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new()).err()
+            };
+
+            let Some(RuntimeError::Transpile(error)) = load("function Process( {") else {
+                panic!("expected a transpile error");
+            };
+            assert_eq!(error.line, Some(1));
+
+            let Some(RuntimeError::Evaluate(error)) = load("var a = 1;\nthrow new Error('boom');") else {
+                panic!("expected an evaluate error");
+            };
+            assert_eq!(error.message, "Error: boom");
+            assert_eq!(error.script_name.as_deref(), Some("in.js"));
+            assert_eq!(error.line, Some(2));
+            assert!(error.stack.unwrap().contains("in.js:2"));
+
+            assert!(matches!(
+                load("var Handle = function () {};"),
+                Some(RuntimeError::MissingEntrypoint(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_processed_requests_are_freed() {
+        GLOBALS.set(&Default::default(), || {
+            init_v8();
+            let isolate = &mut v8::Isolate::new(v8::CreateParams::default());
+            let mut isolate_scope = v8::HandleScope::new(isolate);
+
+            let source = v8::String::new(
+                &mut isolate_scope,
+                "function Process(request) { output.body = request.path; }",
+            )
+            .unwrap();
+            let mut processor =
+                JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new()).unwrap();
+
+            let used_heap_after = |processor: &mut JsHttpRequestProcessor, requests: usize| {
+                for i in 0..requests {
+                    let request = StringHttpRequest::new(&format!("/{i}"), "example.com", "", "");
+                    processor.process(request).unwrap();
+                }
+                processor.context_scope.low_memory_notification();
+                processor.context_scope.get_heap_statistics().used_heap_size()
+            };
+
+            let warmed_up = used_heap_after(&mut processor, 1_000);
+            let used = used_heap_after(&mut processor, 100_000);
+
+            // only wrappers that were not collected yet keep their request
+            assert!(processor.requests.len() < 1_000, "{} requests alive", processor.requests.len());
+            assert!(used < warmed_up + 1024 * 1024, "heap grew from {warmed_up} to {used} bytes");
+        });
+    }
+
+    #[test]
+    fn test_full_request_is_exposed() {
+        GLOBALS.set(&Default::default(), || {
+            init_v8();
+            let isolate = &mut v8::Isolate::new(v8::CreateParams::default());
+            let mut isolate_scope = v8::HandleScope::new(isolate);
+
+            let source = v8::String::new(
+                &mut isolate_scope,
+                r#"
+                function Process(request) {
+                    output.body = JSON.stringify([
+                        request.method,
+                        request.headers["content-type"],
+                        request.headers.accept,
+                        request.query.tag,
+                        request.query.page,
+                        request.cookies.session,
+                        request.cookies.theme,
+                        request.remoteAddress,
+                        request.protocol,
+                        request.body,
+                        request.json.name,
+                        request.bytes.length,
+                    ]);
+                }
+                "#,
+            )
+            .unwrap();
+            let mut processor =
+                JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new()).unwrap();
+
+            let request = StringHttpRequest::new("/items?tag=a&tag=b&page=2", "example.com", "", "")
+                .with_method("POST")
+                .with_header("Content-Type", "application/json")
+                .with_header("Accept", "text/html")
+                .with_header("Accept", "application/json")
+                .with_header("Cookie", "session=abc; theme=dark")
+                .with_header("Cookie", "theme=light")
+                .with_remote_addr("127.0.0.1")
+                .with_protocol("https")
+                .with_body(r#"{"name":"ssr"}"#);
+            processor.process(request).unwrap();
+
+            let output_str = v8::String::new(&mut processor.context_scope, "output").unwrap();
+            let output_obj = processor.context.global(&mut processor.context_scope)
+                .get(&mut processor.context_scope, output_str.into())
+                .unwrap();
+            let output_obj = v8::Local::<v8::Object>::try_from(output_obj).unwrap();
+            let body_key = v8::String::new(&mut processor.context_scope, "body").unwrap();
+            let body = output_obj
+                .get(&mut processor.context_scope, body_key.into())
+                .unwrap()
+                .to_rust_string_lossy(&mut processor.context_scope);
+
+            assert_eq!(
+                body,
+                concat!(
+                    r#"["POST","application/json","text/html, application/json","#,
+                    r#"["a","b"],"2","abc","dark","127.0.0.1","https","#,
+                    r#""{\"name\":\"ssr\"}","ssr",14]"#,
+                )
+            );
+        });
+    }
+
+    #[test]
+    fn test_process_returns_response() {
+        GLOBALS.set(&Default::default(), || {
+            init_v8();
+            let isolate = &mut v8::Isolate::new(v8::CreateParams::default());
+            let mut isolate_scope = v8::HandleScope::new(isolate);
+
+            let source = v8::String::new(
+                &mut isolate_scope,
+                r#"
+                function Process(request) {
+                    switch (request.path) {
+                        case "/text":
+                            output.status = 404;
+                            output.headers = { "Content-Type": "text/plain", "Set-Cookie": ["a=1", "b=2"] };
+                            output.body = "missing";
+                            break;
+                        case "/bytes":
+                            output.body = new Uint8Array([0, 255]);
+                            break;
+                        case "/json":
+                            output.body = { ok: true };
+                            break;
+                        case "/cycle":
+                            output.body = {};
+                            output.body.self = output.body;
+                            break;
+                        case "/getter":
+                            output.headers = { get a() { throw new Error("no header"); } };
+                            break;
+                        case "/proxy":
+                            output.headers = new Proxy({}, { ownKeys() { throw new Error("no keys"); } });
+                            break;
+                        case "/body":
+                            Object.defineProperty(output, "body", { get() { throw new Error("no body"); } });
+                            break;
+                        default:
+                            output.status = "ok";
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+            let mut processor =
+                JsHttpRequestProcessor::new(&mut isolate_scope, source, HashMap::new()).unwrap();
+            let mut process = |path: &str| {
+                processor.process(StringHttpRequest::new(path, "example.com", "", ""))
+            };
+
+            let response = process("/text").unwrap();
+            assert_eq!(response.status, 404);
+            assert_eq!(response.header("content-type"), Some("text/plain"));
+            assert_eq!(response.headers.len(), 3);
+            assert_eq!(response.body, b"missing");
+
+            // `output` starts empty on every call
+            let response = process("/bytes").unwrap();
+            assert_eq!((response.status, response.headers.len()), (200, 0));
+            assert_eq!(response.body, [0, 255]);
+
+            let response = process("/json").unwrap();
+            assert_eq!(response.header("content-type"), Some("application/json"));
+            assert_eq!(response.body, br#"{"ok":true}"#);
+
+            let error = process("/cycle").unwrap_err();
+            assert!(matches!(error, RuntimeError::Response(_)));
+            assert!(error.to_string().contains("output.body could not be serialized"));
+            let error = process("/status").unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "invalid response: output.status must be an integer from 100 to 999, got ok"
+            );
+
+            // exceptions thrown while `output` is read are errors, not panics or empty bodies
+            for (path, message) in [
+                ("/getter", "no header"),
+                ("/proxy", "no keys"),
+                ("/body", "no body"),
+            ] {
+                let error = process(path).unwrap_err();
+                assert!(matches!(error, RuntimeError::Response(_)));
+                assert!(error.to_string().contains(message), "{error}");
+            }
+        });
+    }
+
+    #[actix_web::test]
+    async fn test_js_routes_run_scripts() {
+        let root = std::env::temp_dir().join("js_processor_script_routes");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("api.js"),
+            r#"
+            function Process(request) {
+                output.body = { region: options.region, method: request.method, data: request.json };
+            }
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("index.js"),
+            "function Process(request) { output.status = 418; output.body = request.path; }",
+        )
+        .unwrap();
+        fs::write(root.join("broken.js"), "function Process(request) { throw new Error('boom'); }")
+            .unwrap();
+        fs::write(
+            root.join("routes.json"),
+            r#"{
+                "/js/api": { "script": "api.js", "options": { "region": "eu" }, "workers": 2 },
+                "/js/broken": { "script": "broken.js", "workers": 1 },
+                "/js/index": { "script": "index.js", "workers": 1 }
+            }"#,
+        )
+        .unwrap();
+
+        let scripts = ScriptRoutes::load(&root.join("routes.json")).unwrap();
+        let app = actix_test::init_service(create_app(scripts)).await;
+
+        let request = actix_test::TestRequest::post()
+            .uri("/js/api/users")
+            .set_payload(r#"{"name":"ssr"}"#)
+            .to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers().get("content-type").unwrap(), "application/json");
+        assert_eq!(
+            actix_test::read_body(response).await,
+            r#"{"region":"eu","method":"POST","data":{"name":"ssr"}}"#
+        );
+
+        let request = actix_test::TestRequest::get().uri("/js/index/page").to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), 418);
+        assert_eq!(actix_test::read_body(response).await, "/js/index/page");
+
+        let request = actix_test::TestRequest::get().uri("/js/broken").to_request();
+        let response = actix_test::call_service(&app, request).await;
+        assert_eq!(response.status(), 500);
+        assert!(
+            String::from_utf8_lossy(&actix_test::read_body(response).await).contains("Error: boom")
+        );
+
+        let request = actix_test::TestRequest::get().uri("/js/unrouted").to_request();
+        assert_eq!(actix_test::call_service(&app, request).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_script_pool_terminates_slow_requests() {
+        let script = std::env::temp_dir().join("js_processor_slow_script.js");
+        fs::write(
+            &script,
+            r#"
+            function Process(request) {
+                if (request.path === "/loop") {
+                    while (true) {}
+                }
+                output.body = request.path;
+            }
+            "#,
+        )
+        .unwrap();
+        let config = ScriptConfig::new(&script).workers(1).timeout_ms(100);
+        let pool = ScriptPool::start(&config).unwrap();
+        let process =
+            |path: &str| pool.process(StringHttpRequest::new(path, "example.com", "", ""));
+
+        assert_eq!(process("/loop").await.unwrap_err(), RuntimeError::Timeout(100));
+        // the only worker is free again and its script keeps running
+        assert_eq!(process("/next").await.unwrap().body, b"/next");
+    }
 }
@@ -0,0 +1,404 @@
+use crate::compile_cache::content_hash;
+use crate::diagnostics::ParseDiagnostic;
+use crate::js_parser::JavaScriptParser;
+use crate::module_graph::{ModuleGraph, normalize_path};
+use crate::react_compiler::ReactCompiler;
+use crate::resolver::NodeResolver;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+/// Directories that are never watched
+const IGNORED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// Files that are watched
+const WATCHED_EXTENSIONS: &[&str] = &["tsx", "ts", "jsx", "js", "mjs", "cjs", "mts", "cts", "json"];
+
+/// A module that failed to compile after a change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompileError {
+    pub path: PathBuf,
+    pub message: String,
+    /// Source excerpt around a syntax error
+    pub code_frame: Option<String>,
+}
+
+impl CompileError {
+    pub fn new(path: PathBuf, error: &anyhow::Error) -> Self {
+        Self {
+            path,
+            message: format!("{error:#}"),
+            code_frame: error
+                .downcast_ref::<ParseDiagnostic>()
+                .map(|diagnostic| diagnostic.code_frame.clone()),
+        }
+    }
+}
+
+/// What changed in the watched directory and what was compiled again
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct WatchEvent {
+    /// Files that were added or modified
+    pub changed: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    /// The changed modules and the modules importing a changed or removed file,
+    /// in path order
+    pub recompiled: Vec<PathBuf>,
+    pub errors: Vec<CompileError>,
+}
+
+/// Modification time, size and content hash, as an edit that keeps the size
+/// can land within the resolution of the modification time
+type Stamp = (Option<SystemTime>, u64, String);
+
+/// Polls a source directory and recompiles the modules a change affects
+///
+/// Compiling through a [`ReactCompiler`] with a
+/// [`crate::compile_cache::CompileCache`] leaves the fresh output in the cache
+/// for the next request. Each change is also sent to every [subscriber](Self::subscribe).
+///
+/// Imports are followed with the compiler's [`NodeResolver`], so a change
+/// reaches the files importing it through `tsconfig.json` aliases too.
+pub struct Watcher {
+    root: PathBuf,
+    interval: Duration,
+    files: BTreeMap<PathBuf, Stamp>,
+    resolver: Arc<NodeResolver>,
+    graph: ModuleGraph,
+    sender: broadcast::Sender<WatchEvent>,
+}
+
+impl Watcher {
+    /// Scans `root` and the imports of its files, without compiling anything yet
+    ///
+    /// Until a compiler with a resolver is polled, imports are resolved with
+    /// the `tsconfig.json` of `root`, if it has one.
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        let root = normalize_path(root.as_ref());
+        let files = scan(&root);
+        let resolver = Arc::new(NodeResolver::for_project(&root).unwrap_or_default());
+        let graph =
+            ModuleGraph::build(&JavaScriptParser::new(), Some(resolver.clone()), files.keys());
+        let (sender, _) = broadcast::channel(64);
+
+        Self {
+            root,
+            interval: Duration::from_millis(250),
+            files,
+            resolver,
+            graph,
+            sender,
+        }
+    }
+
+    /// How often the directory is scanned by [`Self::spawn`]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Receives an event for every change found from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Scans the directory once, recompiling and notifying subscribers if
+    /// anything changed since the last scan
+    pub fn poll(&mut self, compiler: &ReactCompiler) -> Option<WatchEvent> {
+        if let Some(resolver) = compiler.resolver()
+            && !Arc::ptr_eq(resolver, &self.resolver)
+        {
+            self.resolver = resolver.clone();
+            self.graph = ModuleGraph::build(
+                &JavaScriptParser::new(),
+                Some(resolver.clone()),
+                self.files.keys(),
+            );
+        }
+
+        let files = scan(&self.root);
+        let changed: Vec<PathBuf> = files
+            .iter()
+            .filter(|(path, stamp)| self.files.get(*path) != Some(*stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        let removed: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !files.contains_key(*path))
+            .cloned()
+            .collect();
+        self.files = files;
+        if changed.is_empty() && removed.is_empty() {
+            return None;
+        }
+
+        // importers are found before the graph forgets removed files
+        let mut affected: BTreeSet<PathBuf> = changed.iter().cloned().collect();
+        for path in changed.iter().chain(&removed) {
+            affected.extend(
+                self.graph
+                    .dependents(path)
+                    .into_iter()
+                    .map(Path::to_path_buf),
+            );
+        }
+        affected.retain(|path| !removed.contains(path));
+        self.graph
+            .update(&JavaScriptParser::new(), affected.iter().chain(&removed));
+
+        let mut event = WatchEvent {
+            changed,
+            removed,
+            ..Default::default()
+        };
+        for path in affected.into_iter().filter(|path| is_compiled(path)) {
+            let result = std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|code| compiler.compile(&code, Some(&path.to_string_lossy())));
+            if let Err(err) = result {
+                event.errors.push(CompileError::new(path.clone(), &err));
+            }
+            event.recompiled.push(path);
+        }
+
+        // having no subscribers is not an error
+        let _ = self.sender.send(event.clone());
+        Some(event)
+    }
+
+    /// Polls on a background thread until the returned handle is dropped
+    ///
+    /// `compiler` is called once on that thread, since a [`ReactCompiler`]
+    /// cannot be sent between threads.
+    pub fn spawn<F>(mut self, compiler: F) -> WatchHandle
+    where
+        F: FnOnce() -> ReactCompiler + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let sender = self.sender.clone();
+        let thread = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let compiler = compiler();
+                while !stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(self.interval);
+                    self.poll(&compiler);
+                }
+            }
+        });
+
+        WatchHandle {
+            stop,
+            sender,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// A [`Watcher`] running on its own thread, which stops when this is dropped
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+    sender: broadcast::Sender<WatchEvent>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    pub fn subscribe(&self) -> broadcast::Receiver<WatchEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// The watched files under `root` with their current stamps
+fn scan(root: &Path) -> BTreeMap<PathBuf, Stamp> {
+    let mut files = BTreeMap::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if metadata.is_dir() {
+                if !name.starts_with('.') && !IGNORED_DIRS.contains(&name.as_ref()) {
+                    dirs.push(path);
+                }
+            } else if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| WATCHED_EXTENSIONS.contains(&ext))
+                && let Ok(contents) = std::fs::read(&path)
+            {
+                let stamp = (metadata.modified().ok(), metadata.len(), content_hash(&[&contents]));
+                files.insert(path, stamp);
+            }
+        }
+    }
+    files
+}
+
+/// Whether a watched file is compiled rather than only imported, like JSON
+fn is_compiled(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext != "json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compile_cache::CompileCache;
+    use std::fs;
+
+    fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("js_processor_watcher_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        for (file, contents) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_poll_recompiles_changed_modules_and_importers() {
+        let root = project(
+            "poll",
+            &[
+                (
+                    "app.tsx",
+                    "import { greet } from './greet';\nexport const App = () => <p>{greet()}</p>;\n",
+                ),
+                ("greet.ts", "export const greet = () => 'hi';\n"),
+                ("other.ts", "export const other = 1;\n"),
+                ("node_modules/lib/index.js", "module.exports = 1;\n"),
+            ],
+        );
+        let cache = Arc::new(CompileCache::new(16));
+        let compiler = ReactCompiler::new().with_cache(cache.clone());
+        let mut watcher = Watcher::new(&root);
+        let mut events = watcher.subscribe();
+
+        assert_eq!(watcher.poll(&compiler), None);
+
+        fs::write(
+            root.join("greet.ts"),
+            "export const greet = () => 'hello';\n",
+        )
+        .unwrap();
+        let event = watcher.poll(&compiler).unwrap();
+        assert_eq!(event.changed, vec![root.join("greet.ts")]);
+        assert_eq!(
+            event.recompiled,
+            vec![root.join("app.tsx"), root.join("greet.ts")]
+        );
+        assert!(event.errors.is_empty());
+        assert_eq!(events.try_recv().unwrap(), event);
+
+        // the recompiled output is what the next compile gets
+        let hits = cache.stats().hits;
+        compiler
+            .compile(
+                &fs::read_to_string(root.join("greet.ts")).unwrap(),
+                Some(&root.join("greet.ts").to_string_lossy()),
+            )
+            .unwrap();
+        assert_eq!(cache.stats().hits, hits + 1);
+    }
+
+    #[test]
+    fn test_poll_sees_edits_keeping_size_and_modification_time() {
+        let root = project("same_size", &[("greet.ts", "export const greet = 'hi';\n")]);
+        let compiler = ReactCompiler::new();
+        let mut watcher = Watcher::new(&root);
+
+        let path = root.join("greet.ts");
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        fs::write(&path, "export const greet = 'yo';\n").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let event = watcher.poll(&compiler).unwrap();
+        assert_eq!(event.changed, vec![path]);
+    }
+
+    #[test]
+    fn test_poll_reports_removed_files_and_errors() {
+        let root = project(
+            "removed",
+            &[
+                (
+                    "app.tsx",
+                    "import { greet } from './greet';\nexport const App = greet;\n",
+                ),
+                ("greet.ts", "export const greet = 1;\n"),
+            ],
+        );
+        let compiler = ReactCompiler::new();
+        let mut watcher = Watcher::new(&root);
+
+        fs::remove_file(root.join("greet.ts")).unwrap();
+        let event = watcher.poll(&compiler).unwrap();
+        assert_eq!(event.removed, vec![root.join("greet.ts")]);
+        assert_eq!(event.recompiled, vec![root.join("app.tsx")]);
+
+        fs::write(root.join("app.tsx"), "export const App = <p>;\n").unwrap();
+        let event = watcher.poll(&compiler).unwrap();
+        assert_eq!(event.errors.len(), 1);
+        assert_eq!(event.errors[0].path, root.join("app.tsx"));
+        assert!(event.errors[0].code_frame.as_ref().unwrap().contains("> 1 |"));
+    }
+
+    #[test]
+    fn test_poll_follows_imports_with_the_compiler_resolver() {
+        let root = project(
+            "aliases",
+            &[
+                (
+                    "tsconfig.json",
+                    r#"{"compilerOptions": {"paths": {"@/*": ["./src/*"]}}}"#,
+                ),
+                ("src/app.ts", "import { greet } from '@/greet';\nexport const app = greet;\n"),
+                ("src/greet.ts", "export const greet = 'hi';\n"),
+            ],
+        );
+        let resolver = Arc::new(NodeResolver::for_project(&root).unwrap());
+        let compiler = ReactCompiler::new().with_resolver(resolver);
+        let mut watcher = Watcher::new(root.join("src"));
+
+        fs::write(root.join("src/greet.ts"), "export const greet = 'hello';\n").unwrap();
+        let event = watcher.poll(&compiler).unwrap();
+        assert_eq!(
+            event.recompiled,
+            vec![root.join("src/app.ts"), root.join("src/greet.ts")]
+        );
+    }
+}
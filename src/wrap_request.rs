@@ -1,40 +1,92 @@
 use ssr_rs::v8;
 use super::JsHttpRequestProcessor;
+use crate::request_prop_handler::REQUEST_PROPERTIES;
 use crate::ssr::http_request::SimpleHttpRequest;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::ffi::c_void;
+use std::rc::Rc;
+
+/// A request handed to JavaScript, kept alive as long as its wrapper object
+struct WrappedRequest {
+    _handle: v8::Weak<v8::Object>,
+    // Double-box to get C-sized reference of Box<dyn SimpleHttpRequest>
+    _request: Box<Box<dyn SimpleHttpRequest>>,
+}
+
+/// The requests of a [`JsHttpRequestProcessor`] whose wrappers V8 has not
+/// collected yet
+///
+/// Each request is freed by a finalizer once its wrapper is garbage collected,
+/// and the rest when the processor is dropped.
+#[derive(Default)]
+pub struct WrappedRequests {
+    next_id: Cell<u64>,
+    live: RefCell<HashMap<u64, WrappedRequest>>,
+}
+
+impl WrappedRequests {
+    pub fn len(&self) -> usize {
+        self.live.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.live.borrow().is_empty()
+    }
+}
 
 impl<'s, 'i> JsHttpRequestProcessor<'s, 'i>
 where
     's: 'i,
 {
     /// Utility function that wraps a http request object in a JavaScript object.
-    pub fn wrap_request(&mut self, request: Box<dyn SimpleHttpRequest>) -> v8::Local<'s, v8::Object> {
-        // TODO: fix memory leak
-
-        // Double-box to get C-sized reference of Box<dyn SimpleHttpRequest>
-        let request = Box::new(request);
+    ///
+    /// The object should be created in a scope that is closed after the call,
+    /// so it can be collected once JavaScript no longer holds it.
+    pub fn wrap_request<'t>(
+        scope: &mut v8::HandleScope<'t>,
+        request_template: &v8::Global<v8::ObjectTemplate>,
+        requests: &Rc<WrappedRequests>,
+        request: Box<dyn SimpleHttpRequest>,
+    ) -> v8::Local<'t, v8::Object> {
+        let mut request = Box::new(request);
 
-        // Local scope for temporary handles.
-        let scope = &mut self.context_scope;
-
-        let request_template = v8::Local::new(scope, &self.request_template);
+        let request_template = v8::Local::new(scope, request_template);
         let result = request_template.new_instance(scope).unwrap();
 
         let external = v8::External::new(
             scope,
-            Box::leak(request) as *mut Box<dyn SimpleHttpRequest> as *mut c_void,
+            &mut *request as *mut Box<dyn SimpleHttpRequest> as *mut c_void,
         );
 
         result.set_internal_field(0, external.into());
 
-        let name = v8::String::new(scope, "path").unwrap().into();
-        result.set_accessor(scope, name, Self::request_prop_handler);
-        let name = v8::String::new(scope, "userAgent").unwrap().into();
-        result.set_accessor(scope, name, Self::request_prop_handler);
-        let name = v8::String::new(scope, "referrer").unwrap().into();
-        result.set_accessor(scope, name, Self::request_prop_handler);
-        let name = v8::String::new(scope, "host").unwrap().into();
-        result.set_accessor(scope, name, Self::request_prop_handler);
+        for name in REQUEST_PROPERTIES {
+            let name = v8::String::new(scope, name).unwrap().into();
+            result.set_accessor(scope, name, Self::request_prop_handler);
+        }
+
+        let id = requests.next_id.get();
+        requests.next_id.set(id + 1);
+        let finalized = Rc::downgrade(requests);
+        let handle = v8::Weak::with_finalizer(
+            scope,
+            result,
+            Box::new(move |_| {
+                if let Some(requests) = finalized.upgrade() {
+                    // dropped after the borrow ends
+                    let request = requests.live.borrow_mut().remove(&id);
+                    drop(request);
+                }
+            }),
+        );
+        requests.live.borrow_mut().insert(
+            id,
+            WrappedRequest {
+                _handle: handle,
+                _request: request,
+            },
+        );
 
         result
     }